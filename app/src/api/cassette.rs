@@ -0,0 +1,409 @@
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fs::{self, File},
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use color_eyre::eyre::{eyre, Context, Result};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use reqwest::header::{self, HeaderName};
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tracing::{debug, error, info};
+
+use crate::api::{
+    http::{self, HttpSettings},
+    Service,
+};
+
+/// Replaces the cassette's own address in recorded bodies, so links point at the replaying server.
+const BASE_URL: &str = "{base_url}";
+const REDACTED: &str = "REDACTED";
+/// Response fields holding secrets, redacted in addition to the API tokens.
+const SECRET_FIELDS: [&str; 1] = ["secretKey"];
+/// Headers forwarded to the API when recording, and replayed from the recorded response.
+const REQUEST_HEADERS: [HeaderName; 3] = [header::ACCEPT, header::CONTENT_TYPE, header::USER_AGENT];
+const RESPONSE_HEADERS: [HeaderName; 2] = [header::CONTENT_TYPE, header::RETRY_AFTER];
+
+/// A recorded request and its response.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Interaction {
+    pub service: Service,
+    pub method: String,
+    /// The path and query, relative to the service's base URL.
+    pub path: String,
+    pub request: Option<Value>,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub response: String,
+}
+
+/// Records API requests through a local proxy, or replays recorded responses instead of the
+/// network. Clients are pointed at the cassette through their base URLs, it stops when dropped.
+#[derive(Debug)]
+pub struct Cassette {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record(Recorder),
+    Replay(Mutex<Vec<(Interaction, bool)>>),
+}
+
+#[derive(Debug)]
+struct Recorder {
+    dir: PathBuf,
+    client: reqwest::Client,
+    up_base_url: String,
+    ynab_base_url: String,
+    secrets: Vec<String>,
+    count: AtomicUsize,
+}
+
+impl Cassette {
+    /// Records requests into `dir`, forwarding them to the APIs with the given HTTP settings.
+    /// `secrets` are redacted from every recorded body.
+    pub fn record<P: AsRef<Path>>(
+        dir: P,
+        http: &HttpSettings,
+        secrets: Vec<String>,
+    ) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .wrap_err(format!("failed to create `{}`", dir.to_string_lossy()))?;
+        info!("recording api requests to `{}`", dir.to_string_lossy());
+
+        let recorder =
+            Recorder {
+                dir,
+                client: http.client()?,
+                up_base_url: http::base_url(http.up_base_url.as_deref().unwrap_or(
+                    &up_client::apis::configuration::Configuration::default().base_path,
+                ))?,
+                ynab_base_url: http::base_url(http.ynab_base_url.as_deref().unwrap_or(
+                    &ynab_client::apis::configuration::Configuration::default().base_path,
+                ))?,
+                secrets: secrets.into_iter().filter(|x| !x.is_empty()).collect(),
+                count: AtomicUsize::new(0),
+            };
+        Self::start(Mode::Record(recorder))
+    }
+
+    /// Replays the requests recorded in `dir`.
+    pub fn replay<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let dir_str = dir.to_string_lossy();
+        let mut paths = fs::read_dir(dir)
+            .wrap_err(format!("failed to open cassette `{dir_str}`"))?
+            .map(|x| x.map(|x| x.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+
+        let interactions = paths
+            .iter()
+            .map(|path| {
+                let path_str = path.to_string_lossy();
+                File::open(path)
+                    .map(BufReader::new)
+                    .map(serde_json::from_reader::<_, Interaction>)
+                    .wrap_err(format!("failed to open `{path_str}`"))?
+                    .wrap_err(format!("failed to parse `{path_str}`"))
+            })
+            .map(|x| x.map(|x| (x, false)))
+            .collect::<Result<Vec<_>>>()?;
+        info!(
+            "replaying {} api requests from `{dir_str}`",
+            interactions.len()
+        );
+        Self::start(Mode::Replay(Mutex::new(interactions)))
+    }
+
+    #[must_use]
+    pub fn up_base_url(&self) -> String {
+        format!("http://{}/up", self.addr)
+    }
+
+    #[must_use]
+    pub fn ynab_base_url(&self) -> String {
+        format!("http://{}/ynab", self.addr)
+    }
+
+    fn start(mode: Mode) -> Result<Self> {
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .wrap_err("failed to start cassette")?;
+        let addr = server.local_addr();
+        let mode = Arc::new(mode);
+        let make_service = make_service_fn(move |_| {
+            let mode = mode.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let mode = mode.clone();
+                    async move { Ok::<_, Infallible>(handle(&mode, addr, req).await) }
+                }))
+            }
+        });
+
+        let (shutdown, rx) = oneshot::channel::<()>();
+        tokio::spawn(server.serve(make_service).with_graceful_shutdown(async {
+            rx.await.ok();
+        }));
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+        })
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+async fn handle(mode: &Mode, addr: SocketAddr, req: Request<Body>) -> Response<Body> {
+    match handle_impl(mode, addr, req).await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("cassette failed: {e:?}");
+            let mut response = Response::new(Body::from(e.to_string()));
+            *response.status_mut() = StatusCode::BAD_GATEWAY;
+            response
+        }
+    }
+}
+
+async fn handle_impl(mode: &Mode, addr: SocketAddr, req: Request<Body>) -> Result<Response<Body>> {
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let (service, path) = if let Some(path) = path_and_query.strip_prefix("/up") {
+        (Service::Up, path.to_owned())
+    } else if let Some(path) = path_and_query.strip_prefix("/ynab") {
+        (Service::Ynab, path.to_owned())
+    } else {
+        return Err(eyre!("unknown service for `{path_and_query}`"));
+    };
+
+    let method = req.method().clone();
+    let headers = req.headers().clone();
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let request = (!body.is_empty())
+        .then(|| serde_json::from_slice::<Value>(&body))
+        .transpose()?;
+    let base_url = format!("http://{addr}");
+
+    let interaction = match mode {
+        Mode::Record(recorder) => {
+            let upstream = match service {
+                Service::Up => &recorder.up_base_url,
+                Service::Ynab => &recorder.ynab_base_url,
+            };
+            let mut builder = recorder
+                .client
+                .request(method.clone(), format!("{upstream}{path}"))
+                .body(body);
+            for name in REQUEST_HEADERS.iter().chain([&header::AUTHORIZATION]) {
+                if let Some(value) = headers.get(name) {
+                    builder = builder.header(name, value);
+                }
+            }
+            let response = builder.send().await?;
+
+            // Links to the API, e.g. the next page, are routed through the recorder.
+            let local_url = format!("{base_url}/{service}");
+            let interaction = Interaction {
+                service,
+                method: method.to_string(),
+                path,
+                request,
+                status: response.status().as_u16(),
+                headers: response_headers(response.headers()),
+                response: response
+                    .text()
+                    .await?
+                    .replace(upstream.as_str(), &local_url),
+            };
+            recorder.write(&interaction, &base_url)?;
+            interaction
+        }
+        Mode::Replay(interactions) => {
+            let mut interactions = interactions
+                .lock()
+                .map_err(|_| eyre!("cassette state poisoned"))?;
+            let is_match = |x: &Interaction| {
+                x.service == service && x.method == method.as_str() && x.path == path
+            };
+            // Bodies can differ in order between runs, so the first unused match is a fallback.
+            let i = interactions
+                .iter()
+                .position(|(x, used)| !used && is_match(x) && x.request == request)
+                .or_else(|| {
+                    interactions
+                        .iter()
+                        .position(|(x, used)| !used && is_match(x))
+                })
+                .ok_or_else(|| eyre!("no recorded response for {service} `{method} {path}`"))?;
+            interactions[i].1 = true;
+            let mut interaction = interactions[i].0.clone();
+            interaction.response = interaction.response.replace(BASE_URL, &base_url);
+            interaction
+        }
+    };
+    debug!(
+        "{} {service} `{} {}`",
+        match mode {
+            Mode::Record(_) => "recorded",
+            Mode::Replay(_) => "replayed",
+        },
+        interaction.method,
+        interaction.path
+    );
+
+    let mut response = Response::new(Body::from(interaction.response));
+    *response.status_mut() = StatusCode::from_u16(interaction.status)?;
+    for (name, value) in interaction.headers {
+        response
+            .headers_mut()
+            .insert(HeaderName::try_from(name)?, value.try_into()?);
+    }
+    Ok(response)
+}
+
+impl Recorder {
+    fn write(&self, interaction: &Interaction, base_url: &str) -> Result<()> {
+        let mut interaction = interaction.clone();
+        interaction.request = interaction.request.map(|x| self.redact_value(x));
+        interaction.response = match serde_json::from_str::<Value>(&interaction.response) {
+            Ok(x) => self.redact_value(x).to_string(),
+            Err(_) => self.redact(&interaction.response),
+        }
+        .replace(base_url, BASE_URL);
+
+        let i = self.count.fetch_add(1, Ordering::SeqCst);
+        let path = self.dir.join(format!(
+            "{i:05}-{}-{}.json",
+            interaction.service,
+            interaction.method.to_lowercase()
+        ));
+        let path_str = path.to_string_lossy();
+        let file = File::create(&path).wrap_err(format!("failed to create `{path_str}`"))?;
+        serde_json::to_writer_pretty(file, &interaction)
+            .wrap_err(format!("failed to write `{path_str}`"))?;
+        Ok(())
+    }
+
+    fn redact(&self, s: &str) -> String {
+        self.secrets.iter().fold(s.to_owned(), |s, secret| {
+            s.replace(secret.as_str(), REDACTED)
+        })
+    }
+
+    fn redact_value(&self, value: Value) -> Value {
+        match value {
+            Value::String(x) => Value::String(self.redact(&x)),
+            Value::Array(x) => Value::Array(x.into_iter().map(|x| self.redact_value(x)).collect()),
+            Value::Object(x) => Value::Object(
+                x.into_iter()
+                    .map(|(k, v)| {
+                        let v = if SECRET_FIELDS.contains(&k.as_str()) && !v.is_null() {
+                            Value::String(REDACTED.to_owned())
+                        } else {
+                            self.redact_value(v)
+                        };
+                        (k, v)
+                    })
+                    .collect(),
+            ),
+            x => x,
+        }
+    }
+}
+
+fn response_headers(headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+    RESPONSE_HEADERS
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            Some((name.to_string(), value.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use futures::TryStreamExt;
+    use mock_server::MockServer;
+
+    use super::*;
+    use crate::api::up;
+
+    fn up_client(base_url: String) -> Result<up::Client> {
+        up::Client::builder()
+            .api_token("up-token")
+            .http(HttpSettings {
+                up_base_url: Some(base_url),
+                ..HttpSettings::default()
+            })
+            .build()
+    }
+
+    async fn transaction_ids(client: &up::Client) -> Result<Vec<String>> {
+        client
+            .transactions()
+            .send()?
+            .map_ok(|x| x.id.clone())
+            .try_collect::<Vec<_>>()
+            .await
+    }
+
+    #[tokio::test]
+    async fn record_replay() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("up_ynab-{}-cassette", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let server = MockServer::start()?;
+        server.seed_run("test/data/run")?;
+        server.set_max_page_size(1);
+        let http = HttpSettings {
+            up_base_url: Some(server.up_base_url()),
+            ..HttpSettings::default()
+        };
+
+        let recorded = {
+            let cassette = Cassette::record(&dir, &http, vec!["up-token".to_owned()])?;
+            transaction_ids(&up_client(cassette.up_base_url())?).await?
+        };
+        assert_eq!(2, recorded.len());
+        // Both pages are fetched through the recorder, without the token being recorded.
+        assert_eq!(2, fs::read_dir(&dir)?.count());
+        for path in fs::read_dir(&dir)? {
+            let content = fs::read_to_string(path?.path())?;
+            assert!(!content.contains("up-token"));
+            assert!(!content.contains(&server.up_base_url()));
+        }
+        drop(server);
+
+        let cassette = Cassette::replay(&dir)?;
+        let replayed = transaction_ids(&up_client(cassette.up_base_url())?).await?;
+        assert_eq!(recorded, replayed);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::api::retry;
+
+/// The API an error was returned by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Service {
+    Up,
+    Ynab,
+}
+
+/// Errors returned by the Up and YNAB APIs, parsed from the error body of the response.
+#[derive(Debug)]
+pub enum Error {
+    /// The API token is invalid, expired or lacks permission.
+    Unauthorized { service: Service, detail: String },
+    /// The requested resource doesn't exist.
+    NotFound { service: Service, detail: String },
+    /// Too many requests were made, either reported by the API or the client-side rate limit.
+    RateLimited {
+        service: Service,
+        retry_after: Option<Duration>,
+    },
+    /// The request was rejected, `pointer` points to the offending part of the request.
+    Validation {
+        service: Service,
+        detail: String,
+        pointer: Option<String>,
+    },
+    /// The API failed to process the request.
+    Server {
+        service: Service,
+        status: StatusCode,
+        detail: String,
+    },
+    /// The request didn't reach the API or its response couldn't be read.
+    Transport {
+        service: Service,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The response didn't match the API's schema.
+    Decode {
+        service: Service,
+        source: serde_json::Error,
+    },
+}
+
+impl Error {
+    #[must_use]
+    pub fn service(&self) -> Service {
+        match self {
+            Self::Unauthorized { service, .. }
+            | Self::NotFound { service, .. }
+            | Self::RateLimited { service, .. }
+            | Self::Validation { service, .. }
+            | Self::Server { service, .. }
+            | Self::Transport { service, .. }
+            | Self::Decode { service, .. } => *service,
+        }
+    }
+
+    fn from_response(
+        service: Service,
+        status: StatusCode,
+        headers: &HeaderMap,
+        detail: Option<String>,
+        pointer: Option<String>,
+    ) -> Self {
+        let detail = detail.unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("unknown error")
+                .to_owned()
+        });
+
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Self::Unauthorized { service, detail }
+            }
+            StatusCode::NOT_FOUND => Self::NotFound { service, detail },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited {
+                service,
+                retry_after: retry::retry_after(headers),
+            },
+            status if status.is_server_error() => Self::Server {
+                service,
+                status,
+                detail,
+            },
+            _ => Self::Validation {
+                service,
+                detail,
+                pointer,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Up => write!(f, "up"),
+            Self::Ynab => write!(f, "ynab"),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized { service, detail } => write!(
+                f,
+                "{service} api token was rejected, check that it's valid and hasn't expired: {detail}"
+            ),
+            Self::NotFound { service, detail } => write!(f, "{service} resource not found: {detail}"),
+            Self::RateLimited {
+                service,
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "{service} rate limit reached, retry in {}s",
+                retry_after.as_secs()
+            ),
+            Self::RateLimited {
+                service,
+                retry_after: None,
+            } => write!(f, "{service} rate limit reached"),
+            Self::Validation {
+                service,
+                detail,
+                pointer: Some(pointer),
+            } => write!(f, "{service} rejected the request at `{pointer}`: {detail}"),
+            Self::Validation {
+                service,
+                detail,
+                pointer: None,
+            } => write!(f, "{service} rejected the request: {detail}"),
+            Self::Server {
+                service,
+                status,
+                detail,
+            } => write!(f, "{service} is unavailable ({status}): {detail}"),
+            Self::Transport { service, source } => {
+                write!(f, "failed to reach {service}: {source}")
+            }
+            Self::Decode { service, source } => {
+                write!(f, "failed to decode {service} response: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport { source, .. } => Some(source.as_ref()),
+            Self::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the first error of an Up error body, returning its detail and source pointer.
+fn up_detail(content: &str) -> (Option<String>, Option<String>) {
+    let Ok(response) = serde_json::from_str::<up_client::models::ErrorResponse>(content) else {
+        return (None, None);
+    };
+    let Some(error) = response.errors.into_iter().next() else {
+        return (None, None);
+    };
+
+    let pointer = error
+        .source
+        .and_then(|x| x.pointer.or(x.parameter.map(|x| format!("?{x}"))));
+    (Some(format!("{}: {}", error.title, error.detail)), pointer)
+}
+
+/// Parses a YNAB error body, returning its detail. YNAB errors don't have a source pointer.
+fn ynab_detail(content: &str) -> (Option<String>, Option<String>) {
+    let detail = serde_json::from_str::<ynab_client::models::ErrorResponse>(content)
+        .ok()
+        .map(|x| format!("{}: {}", x.error.name, x.error.detail));
+    (detail, None)
+}
+
+macro_rules! from_impl {
+    ($client:ident, $service:expr, $detail:ident) => {
+        impl<T> From<$client::apis::Error<T>> for Error {
+            fn from(e: $client::apis::Error<T>) -> Self {
+                let service = $service;
+                match e {
+                    $client::apis::Error::Reqwest(e) => Self::Transport {
+                        service,
+                        source: Box::new(e),
+                    },
+                    $client::apis::Error::Serde(source) => Self::Decode { service, source },
+                    $client::apis::Error::Io(e) => Self::Transport {
+                        service,
+                        source: Box::new(e),
+                    },
+                    $client::apis::Error::ResponseError(e) => {
+                        let (detail, pointer) = $detail(&e.content);
+                        Self::from_response(service, e.status, &e.headers, detail, pointer)
+                    }
+                }
+            }
+        }
+    };
+}
+
+from_impl!(up_client, Service::Up, up_detail);
+from_impl!(ynab_client, Service::Ynab, ynab_detail);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn response<T>(status: StatusCode, content: &str) -> up_client::apis::Error<T> {
+        up_client::apis::Error::ResponseError(up_client::apis::ResponseContent {
+            status,
+            headers: HeaderMap::new(),
+            content: content.to_owned(),
+            entity: None,
+        })
+    }
+
+    #[test]
+    fn up_validation() {
+        let content = r#"{"errors":[{"status":"400","title":"Invalid Parameter","detail":"The page size is too large.","source":{"parameter":"page[size]"}}]}"#;
+        let e = Error::from(response::<()>(StatusCode::BAD_REQUEST, content));
+        match e {
+            Error::Validation {
+                service,
+                detail,
+                pointer,
+            } => {
+                assert_eq!(Service::Up, service);
+                assert_eq!("Invalid Parameter: The page size is too large.", detail);
+                assert_eq!(Some("?page[size]".to_owned()), pointer);
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn ynab_unauthorized() {
+        let content = r#"{"error":{"id":"401","name":"unauthorized","detail":"Unauthorized"}}"#;
+        let e = Error::from(ynab_client::apis::Error::<()>::ResponseError(
+            ynab_client::apis::ResponseContent {
+                status: StatusCode::UNAUTHORIZED,
+                headers: HeaderMap::new(),
+                content: content.to_owned(),
+                entity: None,
+            },
+        ));
+        match e {
+            Error::Unauthorized { service, detail } => {
+                assert_eq!(Service::Ynab, service);
+                assert_eq!("unauthorized: Unauthorized", detail);
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn unparsed_body() {
+        let e = Error::from(response::<()>(StatusCode::SERVICE_UNAVAILABLE, "<html>"));
+        assert!(matches!(
+            e,
+            Error::Server {
+                service: Service::Up,
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn ynab_decode() {
+        let source = serde_json::from_str::<ynab_client::models::ErrorResponse>("{}").err();
+        let e = source.map(|x| Error::from(ynab_client::apis::Error::<()>::Serde(x)));
+        assert!(matches!(
+            e,
+            Some(Error::Decode {
+                service: Service::Ynab,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use color_eyre::eyre::{eyre, Context, Result};
+
+/// The default user agent, identifying the app and its version.
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+const END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+
+/// HTTP settings shared by the Up and YNAB clients.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    /// Overrides the Up API base URL, e.g. to point at a mock server or recording proxy.
+    pub up_base_url: Option<String>,
+    /// Overrides the YNAB API base URL.
+    pub ynab_base_url: Option<String>,
+    /// The timeout for a whole request, in seconds.
+    pub timeout_secs: Option<u64>,
+    /// The timeout for connecting, in seconds.
+    pub connect_timeout_secs: Option<u64>,
+    /// The proxy all requests are sent through.
+    pub proxy: Option<String>,
+    /// Path to a PEM bundle of CA certificates trusted in addition to the system ones.
+    pub ca_bundle: Option<PathBuf>,
+    /// Overrides the user agent.
+    pub user_agent: Option<String>,
+}
+
+impl HttpSettings {
+    #[must_use]
+    pub fn user_agent(&self) -> String {
+        self.user_agent
+            .clone()
+            .unwrap_or_else(|| USER_AGENT.to_owned())
+    }
+
+    /// Builds the HTTP client used by the generated API clients.
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().user_agent(self.user_agent());
+
+        if let Some(timeout) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy).wrap_err(format!("invalid proxy `{proxy}`"))?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_bundle {
+            let path_str = path.to_string_lossy();
+            let bundle = fs::read(path).wrap_err(format!("failed to read `{path_str}`"))?;
+            let certificates = certificates(&bundle)
+                .wrap_err(format!("failed to parse certificates in `{path_str}`"))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder.build().wrap_err("failed to build http client")
+    }
+}
+
+/// Normalizes a base URL override, the generated clients append paths with a leading slash.
+pub fn base_url(url: &str) -> Result<String> {
+    let parsed = url::Url::parse(url).wrap_err(format!("invalid base url `{url}`"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(eyre!("base url `{url}` must use http or https"));
+    }
+    Ok(url.trim_end_matches('/').to_owned())
+}
+
+/// Splits a PEM bundle into its certificates.
+fn certificates(bundle: &[u8]) -> Result<Vec<reqwest::Certificate>> {
+    let bundle = std::str::from_utf8(bundle)?;
+    let certificates = bundle
+        .split_inclusive(END_CERTIFICATE)
+        .filter(|x| x.contains(END_CERTIFICATE))
+        .map(|x| reqwest::Certificate::from_pem(x.trim().as_bytes()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if certificates.is_empty() {
+        return Err(eyre!("no certificates found"));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_base_url() -> Result<()> {
+        assert_eq!(
+            "http://localhost:8080/api/v1",
+            base_url("http://localhost:8080/api/v1/")?
+        );
+        assert!(base_url("localhost:8080").is_err());
+        assert!(base_url("ftp://localhost").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn default_user_agent() {
+        let settings = HttpSettings::default();
+        assert!(settings.user_agent().starts_with("up_ynab/"));
+        assert!(settings.client().is_ok());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod cassette;
+pub mod error;
+pub mod http;
+pub mod rate_limit;
+pub mod retry;
 pub mod up;
 pub mod ynab;
+
+pub use self::error::{Error, Service};
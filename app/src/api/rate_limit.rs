@@ -0,0 +1,196 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::{Context, Result};
+use tracing::{debug, info};
+
+use crate::{
+    api::{Error, Service},
+    frontend::atomic,
+};
+
+/// What to do when the rate limit would be exceeded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnLimit {
+    /// Wait until a request becomes available.
+    #[default]
+    Wait,
+    /// Stop with an error, the next run resumes from where this one stopped.
+    Stop,
+}
+
+/// Client-side rate limit, YNAB allows 200 requests per hour per token.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct RateLimitPolicy {
+    /// The maximum number of requests per window.
+    pub max_requests: usize,
+    /// The window length, in seconds.
+    pub window_secs: i64,
+    /// What to do when the rate limit would be exceeded.
+    pub on_limit: OnLimit,
+}
+
+/// Tracks requests made within the rate limit window, persisting them across runs.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    service: Service,
+    policy: RateLimitPolicy,
+    path: PathBuf,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            max_requests: 200,
+            window_secs: 60 * 60,
+            on_limit: OnLimit::default(),
+        }
+    }
+}
+
+impl RateLimiter {
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(service: Service, policy: RateLimitPolicy, path: P) -> Self {
+        Self {
+            service,
+            policy,
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Records a request, waiting or failing if the rate limit would be exceeded.
+    pub async fn acquire(&self) -> Result<()> {
+        loop {
+            let now = Utc::now();
+            let mut requests = self.read()?;
+            requests.retain(|x| now - *x < self.window());
+
+            if requests.len() < self.policy.max_requests {
+                requests.push(now);
+                self.write(&requests)?;
+                debug!(
+                    "{} of {} requests used",
+                    requests.len(),
+                    self.policy.max_requests
+                );
+                return Ok(());
+            }
+
+            let available_at = requests.iter().min().map_or(now, |x| *x + self.window());
+            match self.policy.on_limit {
+                OnLimit::Stop => {
+                    info!(
+                        "rate limit of {} requests reached, resume after {}",
+                        self.policy.max_requests,
+                        available_at.to_rfc3339()
+                    );
+                    return Err(Error::RateLimited {
+                        service: self.service,
+                        retry_after: (available_at - now).to_std().ok(),
+                    }
+                    .into());
+                }
+                OnLimit::Wait => {
+                    info!(
+                        "rate limit of {} requests reached, waiting until {}...",
+                        self.policy.max_requests,
+                        available_at.to_rfc3339()
+                    );
+                    let delay = (available_at - now).to_std().unwrap_or_default();
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    /// Returns the number of requests available in the current window.
+    pub fn remaining(&self) -> Result<usize> {
+        let now = Utc::now();
+        let used = self
+            .read()?
+            .into_iter()
+            .filter(|x| now - *x < self.window())
+            .count();
+        Ok(self.policy.max_requests.saturating_sub(used))
+    }
+
+    fn window(&self) -> Duration {
+        Duration::seconds(self.policy.window_secs)
+    }
+
+    fn read(&self) -> Result<Vec<DateTime<Utc>>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let path_str = self.path.to_string_lossy();
+        let requests = File::open(&self.path)
+            .map(BufReader::new)
+            .map(serde_json::from_reader::<_, Vec<DateTime<Utc>>>)
+            .wrap_err(format!("failed to open `{path_str}`"))?
+            .wrap_err(format!("failed to parse `{path_str}`"))?;
+        Ok(requests)
+    }
+
+    fn write(&self, requests: &[DateTime<Utc>]) -> Result<()> {
+        let path_str = self.path.to_string_lossy();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents =
+            serde_json::to_vec(requests).wrap_err(format!("failed to serialize `{path_str}`"))?;
+        atomic::write(&self.path, &contents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn limiter(name: &str, on_limit: OnLimit) -> RateLimiter {
+        let path = std::env::temp_dir().join(format!("up_ynab-{}-{name}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let policy = RateLimitPolicy {
+            max_requests: 2,
+            window_secs: 60,
+            on_limit,
+        };
+        RateLimiter::new(Service::Ynab, policy, path)
+    }
+
+    #[tokio::test]
+    async fn acquire_stop() -> Result<()> {
+        let limiter = limiter("acquire_stop", OnLimit::Stop);
+        limiter.acquire().await?;
+        assert_eq!(1, limiter.remaining()?);
+        limiter.acquire().await?;
+        assert_eq!(0, limiter.remaining()?);
+        let e = limiter.acquire().await.err();
+        assert!(matches!(
+            e.as_ref().and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::RateLimited { .. })
+        ));
+        fs::remove_file(&limiter.path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn acquire_persisted() -> Result<()> {
+        let limiter = limiter("acquire_persisted", OnLimit::Stop);
+        let stale = Utc::now() - Duration::seconds(120);
+        limiter.write(&[stale, Utc::now()])?;
+        assert_eq!(1, limiter.remaining()?);
+
+        let reloaded = RateLimiter::new(Service::Ynab, limiter.policy.clone(), &limiter.path);
+        reloaded.acquire().await?;
+        assert_eq!(0, limiter.remaining()?);
+        fs::remove_file(&limiter.path)?;
+        Ok(())
+    }
+}
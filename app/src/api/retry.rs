@@ -0,0 +1,233 @@
+use std::{fmt, future::Future, time::Duration};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{header::HeaderMap, StatusCode};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Retry policy shared by the Up and YNAB clients. Connection errors and `429` responses are
+/// always retried, `5xx` responses and timeouts only for idempotent requests.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// The maximum number of retries per request.
+    pub max_retries: u32,
+    /// The delay before the first retry, in milliseconds.
+    pub initial_interval_ms: u64,
+    /// The maximum delay between retries, in milliseconds.
+    pub max_interval_ms: u64,
+    /// The factor the delay grows by after each retry.
+    pub multiplier: f64,
+    /// The maximum time spent retrying a request, in seconds.
+    pub max_elapsed_secs: u64,
+}
+
+/// How a failed request should be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The request can't succeed by retrying.
+    Permanent,
+    /// The request was never processed by the server and can always be retried.
+    Rejected { retry_after: Option<Duration> },
+    /// The request may have been processed by the server and can only be retried if idempotent.
+    Failed { retry_after: Option<Duration> },
+}
+
+/// Classifies errors returned by the generated API clients.
+pub trait Classify {
+    fn classify(&self) -> Failure;
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_interval_ms: 500,
+            max_interval_ms: 60_000,
+            multiplier: 2.0,
+            max_elapsed_secs: 300,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retries an idempotent request.
+    pub async fn retry<T, E, F, Fut>(&self, f: F) -> Result<T, E>
+    where
+        E: Classify + fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.retry_impl(true, f).await
+    }
+
+    /// Retries a request which isn't idempotent, only retrying if the server didn't process it.
+    pub async fn retry_mutation<T, E, F, Fut>(&self, f: F) -> Result<T, E>
+    where
+        E: Classify + fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.retry_impl(false, f).await
+    }
+
+    async fn retry_impl<T, E, F, Fut>(&self, is_idempotent: bool, mut f: F) -> Result<T, E>
+    where
+        E: Classify + fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let start = Instant::now();
+        let max_elapsed = Duration::from_secs(self.max_elapsed_secs);
+        let mut attempt = 0;
+
+        loop {
+            let e = match f().await {
+                Ok(x) => return Ok(x),
+                Err(e) => e,
+            };
+
+            let retry_after = match e.classify() {
+                Failure::Rejected { retry_after } => retry_after,
+                Failure::Failed { retry_after } if is_idempotent => retry_after,
+                Failure::Failed { .. } | Failure::Permanent => return Err(e),
+            };
+
+            let delay = retry_after.unwrap_or_else(|| self.backoff(attempt));
+            if attempt >= self.max_retries || start.elapsed() + delay > max_elapsed {
+                return Err(e);
+            }
+
+            attempt += 1;
+            warn!(
+                "request failed, retrying in {:.1}s ({attempt}/{}): {e}",
+                delay.as_secs_f64(),
+                self.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Returns the jittered exponential backoff delay for the given attempt.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        #[allow(clippy::cast_precision_loss)]
+        let interval = (self.initial_interval_ms as f64
+            * self
+                .multiplier
+                .powi(i32::try_from(attempt).unwrap_or(i32::MAX)))
+        .min(self.max_interval_ms as f64);
+        let jitter = rand::thread_rng().gen_range(0.5..=1.5);
+        Duration::from_secs_f64(interval * jitter / 1000.0)
+    }
+}
+
+/// Parses the `Retry-After` header, either a number of seconds or an HTTP date.
+#[must_use]
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+fn classify_status(status: StatusCode, headers: &HeaderMap) -> Failure {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        Failure::Rejected {
+            retry_after: retry_after(headers),
+        }
+    } else if status.is_server_error() {
+        Failure::Failed {
+            retry_after: retry_after(headers),
+        }
+    } else {
+        Failure::Permanent
+    }
+}
+
+fn classify_reqwest(e: &reqwest::Error) -> Failure {
+    if e.is_connect() {
+        Failure::Rejected { retry_after: None }
+    } else if e.is_timeout() {
+        Failure::Failed { retry_after: None }
+    } else {
+        Failure::Permanent
+    }
+}
+
+macro_rules! classify_impl {
+    ($error:ident) => {
+        impl<T> Classify for $error::apis::Error<T> {
+            fn classify(&self) -> Failure {
+                match self {
+                    Self::Reqwest(e) => classify_reqwest(e),
+                    Self::ResponseError(e) => classify_status(e.status, &e.headers),
+                    Self::Serde(_) | Self::Io(_) => Failure::Permanent,
+                }
+            }
+        }
+    };
+}
+
+classify_impl!(up_client);
+classify_impl!(ynab_client);
+
+#[cfg(test)]
+mod test {
+    use reqwest::header::{HeaderValue, RETRY_AFTER};
+
+    use super::*;
+
+    #[test]
+    fn retry_after_secs() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+        assert_eq!(Some(Duration::from_secs(30)), retry_after(&headers));
+    }
+
+    #[test]
+    fn retry_after_date() -> color_eyre::eyre::Result<()> {
+        let mut headers = HeaderMap::new();
+        let date = (Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date)?);
+        let delay = retry_after(&headers).unwrap_or_default();
+        assert!(delay > Duration::from_secs(25) && delay <= Duration::from_secs(30));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(None, retry_after(&headers));
+        Ok(())
+    }
+
+    #[test]
+    fn backoff_bounds() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..20 {
+            let delay = policy.backoff(attempt);
+            assert!(delay <= Duration::from_millis(policy.max_interval_ms * 3 / 2));
+        }
+    }
+
+    #[test]
+    fn classify() {
+        let headers = HeaderMap::new();
+        assert_eq!(
+            Failure::Rejected { retry_after: None },
+            classify_status(StatusCode::TOO_MANY_REQUESTS, &headers)
+        );
+        assert_eq!(
+            Failure::Failed { retry_after: None },
+            classify_status(StatusCode::BAD_GATEWAY, &headers)
+        );
+        assert_eq!(
+            Failure::Permanent,
+            classify_status(StatusCode::UNAUTHORIZED, &headers)
+        );
+    }
+}
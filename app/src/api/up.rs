@@ -1,34 +1,65 @@
-use std::pin::Pin;
+use std::{collections::HashSet, pin::Pin};
 
 use accounts_api::AccountsGetParams;
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use color_eyre::eyre::Context;
 use futures::{
+    future,
     stream::{self},
-    Stream,
+    Stream, StreamExt, TryStreamExt,
 };
-use transactions_api::TransactionsGetParams;
+use tags_api::TagsGetParams;
+use transactions_api::{AccountsAccountIdTransactionsGetParams, TransactionsGetParams};
 use up_client::{
-    apis::{accounts_api, configuration::Configuration, transactions_api, util},
+    apis::{
+        accounts_api, categories_api, configuration::Configuration, tags_api, transactions_api,
+        util, utility_endpoints_api, webhooks_api,
+    },
     models,
 };
+use webhooks_api::{WebhooksGetParams, WebhooksWebhookIdLogsGetParams};
 
 use crate::{
-    model::{UpAccount, UpTransaction},
+    api::{
+        http::{self, HttpSettings},
+        retry::RetryPolicy,
+        Error,
+    },
+    model::{UpAccount, UpCategory, UpTag, UpTransaction, UpWebhook, UpWebhookEvent, UpWebhookLog},
     Result,
 };
 
 #[derive(Debug, Clone)]
 pub struct Client {
     config: Configuration,
+    retry: RetryPolicy,
 }
 
 pub type AccountKind = up_client::models::AccountTypeEnum;
 pub type OwnershipKind = up_client::models::OwnershipTypeEnum;
 pub type TransactionState = up_client::models::TransactionStatusEnum;
+pub type TransactionStream<'a> = Pin<Box<dyn Stream<Item = Result<UpTransaction>> + 'a>>;
 
 const PAGE_SIZE: i32 = 100;
 
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(
+    pattern = "owned",
+    setter(into),
+    build_fn(private, name = "build_params")
+)]
+#[builder_struct_attr(must_use)]
+pub struct ClientParams {
+    /// The personal access token.
+    api_token: String,
+    /// HTTP client settings.
+    #[builder(default)]
+    http: HttpSettings,
+    /// The policy used to retry failed requests.
+    #[builder(default)]
+    retry: RetryPolicy,
+}
+
 #[derive(Debug, Clone, derive_builder::Builder)]
 #[builder(pattern = "owned", setter(into), build_fn(private))]
 #[builder_struct_attr(must_use)]
@@ -56,6 +87,55 @@ pub struct GetTransactionsParams<'a> {
     /// records are returned and a success response is given.
     #[builder(default)]
     filter_tag: Option<String>,
+    /// Fetches each account's transactions concurrently, with at most this many pages in flight.
+    /// Transactions are no longer ordered newest first.
+    #[builder(default)]
+    concurrency: Option<usize>,
+    /// Splits the date range from `filter_since` into slices of this length, fetched concurrently.
+    /// Only used with `concurrency`.
+    #[builder(default)]
+    slice: Option<Duration>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetAccountTransactionsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the account.
+    account_id: String,
+    /// The transaction status for which to return records. This can be used to filter `HELD`
+    /// transactions from those that are `SETTLED`.
+    #[builder(default)]
+    filter_status: Option<TransactionState>,
+    /// The start date-time from which to return records, formatted according to rfc-3339. Not to
+    /// be used for pagination purposes.
+    #[builder(default)]
+    filter_since: Option<DateTime<FixedOffset>>,
+    /// The end date-time up to which to return records, formatted according to rfc-3339. Not to be
+    /// used for pagination purposes.
+    #[builder(default)]
+    filter_until: Option<DateTime<FixedOffset>>,
+    /// The category identifier for which to filter transactions. Both parent and child categories
+    /// can be filtered through this parameter. Providing an invalid category identifier results in
+    /// a `404` response.
+    #[builder(default)]
+    filter_category: Option<String>,
+    /// A transaction tag to filter for which to return records. If the tag does not exist, zero
+    /// records are returned and a success response is given.
+    #[builder(default)]
+    filter_tag: Option<String>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetTransactionParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the transaction.
+    id: String,
 }
 
 #[derive(Debug, Clone, derive_builder::Builder)]
@@ -74,6 +154,121 @@ pub struct GetAccountsParams<'a> {
     filter_ownership: Option<OwnershipKind>,
 }
 
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetAccountParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the account.
+    id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetCategoriesParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier of a parent category for which to return only its children.
+    /// Providing an invalid category identifier results in a `404` response.
+    #[builder(default)]
+    filter_parent: Option<String>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetCategoryParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the category.
+    id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct UpdateTransactionCategoryParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the transaction.
+    transaction_id: String,
+    /// The unique identifier for the category, or `None` to de-categorize the transaction.
+    #[builder(default)]
+    category_id: Option<String>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetTagsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct UpdateTransactionTagsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the transaction.
+    transaction_id: String,
+    /// The tags to add to or remove from the transaction. Tags are identified by their label.
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetWebhooksParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct WebhookParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the webhook.
+    id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct NewWebhookParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The URL that this webhook should post events to. This must be a valid HTTP or HTTPS URL
+    /// that does not exceed 300 characters in length.
+    url: String,
+    /// An optional description for this webhook, up to 64 characters in length.
+    #[builder(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetWebhookLogsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The unique identifier for the webhook.
+    webhook_id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct PingParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+}
+
 macro_rules! stream_pages_impl {
     ($name:ident, $page_fn:ident, $T:ident, $A:ty) => {
         fn $name(
@@ -123,6 +318,28 @@ macro_rules! stream_pages_impl {
     };
 }
 
+macro_rules! page_impl {
+    ($name:ident, $api_fn:path, $R:ty, $E:ty, $A:ty, $msg:expr) => {
+        async fn $name(&self, page: Option<&str>, params: $A) -> Result<$R> {
+            // Failed pages are retried from the same link, resuming pagination.
+            self.retry
+                .retry(|| {
+                    let params = params.clone();
+                    async move {
+                        if let Some(page) = page {
+                            util::get_page::<$R, $E>(&self.config, page).await
+                        } else {
+                            $api_fn(&self.config, params).await
+                        }
+                    }
+                })
+                .await
+                .map_err(Error::from)
+                .wrap_err($msg)
+        }
+    };
+}
+
 impl<'a> GetAccountsParams<'a> {
     fn into_api(self) -> accounts_api::AccountsGetParams {
         accounts_api::AccountsGetParams {
@@ -133,6 +350,12 @@ impl<'a> GetAccountsParams<'a> {
     }
 }
 
+impl<'a> GetAccountParams<'a> {
+    fn into_api(self) -> accounts_api::AccountsIdGetParams {
+        accounts_api::AccountsIdGetParams { id: self.id }
+    }
+}
+
 impl<'a> GetTransactionsParams<'a> {
     fn into_api(self) -> transactions_api::TransactionsGetParams {
         transactions_api::TransactionsGetParams {
@@ -146,10 +369,141 @@ impl<'a> GetTransactionsParams<'a> {
     }
 }
 
+impl<'a> GetAccountTransactionsParams<'a> {
+    fn into_api(self) -> transactions_api::AccountsAccountIdTransactionsGetParams {
+        transactions_api::AccountsAccountIdTransactionsGetParams {
+            account_id: self.account_id,
+            page_size: Some(PAGE_SIZE),
+            filter_status: self.filter_status,
+            filter_since: self.filter_since.map(|x| x.to_rfc3339()),
+            filter_until: self.filter_until.map(|x| x.to_rfc3339()),
+            filter_category: self.filter_category,
+            filter_tag: self.filter_tag,
+        }
+    }
+}
+
+impl<'a> GetTransactionParams<'a> {
+    fn into_api(self) -> transactions_api::TransactionsIdGetParams {
+        transactions_api::TransactionsIdGetParams { id: self.id }
+    }
+}
+
+impl<'a> GetCategoriesParams<'a> {
+    fn into_api(self) -> categories_api::CategoriesGetParams {
+        categories_api::CategoriesGetParams {
+            filter_left_square_bracket_parent_right_square_bracket: self.filter_parent,
+        }
+    }
+}
+
+impl<'a> GetCategoryParams<'a> {
+    fn into_api(self) -> categories_api::CategoriesIdGetParams {
+        categories_api::CategoriesIdGetParams { id: self.id }
+    }
+}
+
+impl<'a> UpdateTransactionCategoryParams<'a> {
+    fn into_api(self) -> categories_api::TransactionsTransactionIdRelationshipsCategoryPatchParams {
+        categories_api::TransactionsTransactionIdRelationshipsCategoryPatchParams {
+            transaction_id: self.transaction_id,
+            update_transaction_category_request: Some(
+                models::UpdateTransactionCategoryRequest::new(self.category_id.map(|id| {
+                    models::UpdateTransactionCategoryRequestData::new("categories".to_owned(), id)
+                })),
+            ),
+        }
+    }
+}
+
+impl<'a> GetTagsParams<'a> {
+    #[allow(clippy::unused_self)]
+    fn into_api(self) -> tags_api::TagsGetParams {
+        tags_api::TagsGetParams {
+            page_left_square_bracket_size_right_square_bracket: Some(PAGE_SIZE),
+        }
+    }
+}
+
+impl<'a> UpdateTransactionTagsParams<'a> {
+    fn request(self) -> (String, Option<models::UpdateTransactionTagsRequest>) {
+        let tags = self
+            .tags
+            .into_iter()
+            .map(|id| models::TagInputResourceIdentifier::new("tags".to_owned(), id))
+            .collect::<Vec<_>>();
+        (
+            self.transaction_id,
+            Some(models::UpdateTransactionTagsRequest::new(tags)),
+        )
+    }
+}
+
+impl<'a> GetWebhooksParams<'a> {
+    #[allow(clippy::unused_self)]
+    fn into_api(self) -> webhooks_api::WebhooksGetParams {
+        webhooks_api::WebhooksGetParams {
+            page_left_square_bracket_size_right_square_bracket: Some(PAGE_SIZE),
+        }
+    }
+}
+
+impl<'a> NewWebhookParams<'a> {
+    fn into_api(self) -> webhooks_api::WebhooksPostParams {
+        let mut attributes = models::WebhookInputResourceAttributes::new(self.url);
+        attributes.description = self.description.map(Some);
+        webhooks_api::WebhooksPostParams {
+            create_webhook_request: Some(models::CreateWebhookRequest::new(
+                models::WebhookInputResource::new(attributes),
+            )),
+        }
+    }
+}
+
+impl<'a> GetWebhookLogsParams<'a> {
+    fn into_api(self) -> webhooks_api::WebhooksWebhookIdLogsGetParams {
+        webhooks_api::WebhooksWebhookIdLogsGetParams {
+            webhook_id: self.webhook_id,
+            page_left_square_bracket_size_right_square_bracket: Some(PAGE_SIZE),
+        }
+    }
+}
+
 impl<'a> GetTransactionsParamsBuilder<'a> {
+    pub fn send(self) -> Result<TransactionStream<'a>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        Ok(match params.concurrency {
+            Some(concurrency) => params
+                .client
+                .concurrent_transactions_send(params, concurrency),
+            None => params.client.transactions_send(params.into_api()),
+        })
+    }
+}
+
+impl<'a> GetAccountTransactionsParamsBuilder<'a> {
     pub fn send(self) -> Result<Pin<Box<impl Stream<Item = Result<UpTransaction>> + 'a>>> {
         let params = self.build().wrap_err("failed to build parameters")?;
-        Ok(params.client.transactions_send(params.into_api()))
+        Ok(params.client.account_transactions_send(params.into_api()))
+    }
+}
+
+impl<'a> GetTransactionParamsBuilder<'a> {
+    pub async fn send(self) -> Result<UpTransaction> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry(|| {
+                transactions_api::transactions_id_get(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to get up transaction")?;
+        Ok(UpTransaction::new(*response.data))
     }
 }
 
@@ -160,15 +514,254 @@ impl<'a> GetAccountsParamsBuilder<'a> {
     }
 }
 
-impl Client {
-    #[must_use]
-    pub fn new(api_token: &str) -> Self {
-        Self {
-            config: Configuration {
-                bearer_access_token: Some(api_token.to_owned()),
-                ..Default::default()
-            },
+impl<'a> GetAccountParamsBuilder<'a> {
+    pub async fn send(self) -> Result<UpAccount> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry(|| {
+                accounts_api::accounts_id_get(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to get up account")?;
+        Ok(UpAccount::new(*response.data))
+    }
+}
+
+impl<'a> GetCategoriesParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Vec<UpCategory>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        Ok(params
+            .client
+            .retry
+            .retry(|| {
+                categories_api::categories_get(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to get up categories")?
+            .data
+            .into_iter()
+            .map(UpCategory::new)
+            .collect::<Vec<_>>())
+    }
+}
+
+impl<'a> GetCategoryParamsBuilder<'a> {
+    pub async fn send(self) -> Result<UpCategory> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry(|| {
+                categories_api::categories_id_get(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to get up category")?;
+        Ok(UpCategory::new(*response.data))
+    }
+}
+
+impl<'a> UpdateTransactionCategoryParamsBuilder<'a> {
+    pub async fn send(self) -> Result<()> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        params
+            .client
+            .retry
+            .retry(|| {
+                categories_api::transactions_transaction_id_relationships_category_patch(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to update up transaction category")
+    }
+}
+
+impl<'a> GetTagsParamsBuilder<'a> {
+    pub fn send(self) -> Result<Pin<Box<impl Stream<Item = Result<UpTag>> + 'a>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        Ok(params.client.tags_send(params.into_api()))
+    }
+}
+
+impl<'a> UpdateTransactionTagsParamsBuilder<'a> {
+    /// Adds the tags to the transaction, existing tags are kept.
+    pub async fn add(self) -> Result<()> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        params
+            .client
+            .retry
+            .retry(|| {
+                let (transaction_id, update_transaction_tags_request) = params.clone().request();
+                tags_api::transactions_transaction_id_relationships_tags_post(
+                    &params.client.config,
+                    tags_api::TransactionsTransactionIdRelationshipsTagsPostParams {
+                        transaction_id,
+                        update_transaction_tags_request,
+                    },
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to add up transaction tags")
+    }
+
+    /// Removes the tags from the transaction, missing tags are ignored.
+    pub async fn remove(self) -> Result<()> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        params
+            .client
+            .retry
+            .retry(|| {
+                let (transaction_id, update_transaction_tags_request) = params.clone().request();
+                tags_api::transactions_transaction_id_relationships_tags_delete(
+                    &params.client.config,
+                    tags_api::TransactionsTransactionIdRelationshipsTagsDeleteParams {
+                        transaction_id,
+                        update_transaction_tags_request,
+                    },
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to remove up transaction tags")
+    }
+}
+
+impl<'a> GetWebhooksParamsBuilder<'a> {
+    pub fn send(self) -> Result<Pin<Box<impl Stream<Item = Result<UpWebhook>> + 'a>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        Ok(params.client.webhooks_send(params.into_api()))
+    }
+}
+
+impl<'a> WebhookParamsBuilder<'a> {
+    /// Retrieves the webhook.
+    pub async fn get(self) -> Result<UpWebhook> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry(|| {
+                webhooks_api::webhooks_id_get(
+                    &params.client.config,
+                    webhooks_api::WebhooksIdGetParams {
+                        id: params.id.clone(),
+                    },
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to get up webhook")?;
+        Ok(UpWebhook::new(*response.data))
+    }
+
+    /// Deletes the webhook, it will no longer receive events.
+    pub async fn delete(self) -> Result<()> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        params
+            .client
+            .retry
+            .retry_mutation(|| {
+                webhooks_api::webhooks_id_delete(
+                    &params.client.config,
+                    webhooks_api::WebhooksIdDeleteParams {
+                        id: params.id.clone(),
+                    },
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to delete up webhook")
+    }
+
+    /// Sends a `PING` event to the webhook, returning the event sent.
+    pub async fn ping(self) -> Result<UpWebhookEvent> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry_mutation(|| {
+                webhooks_api::webhooks_webhook_id_ping_post(
+                    &params.client.config,
+                    webhooks_api::WebhooksWebhookIdPingPostParams {
+                        webhook_id: params.id.clone(),
+                    },
+                )
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to ping up webhook")?;
+        Ok(UpWebhookEvent::new(*response.data))
+    }
+}
+
+impl<'a> NewWebhookParamsBuilder<'a> {
+    pub async fn send(self) -> Result<UpWebhook> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry_mutation(|| {
+                webhooks_api::webhooks_post(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to create up webhook")?;
+        Ok(UpWebhook::new(*response.data))
+    }
+}
+
+impl<'a> GetWebhookLogsParamsBuilder<'a> {
+    pub fn send(self) -> Result<Pin<Box<impl Stream<Item = Result<UpWebhookLog>> + 'a>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        Ok(params.client.webhook_logs_send(params.into_api()))
+    }
+}
+
+impl<'a> PingParamsBuilder<'a> {
+    pub async fn send(self) -> Result<models::PingResponseMeta> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let response = params
+            .client
+            .retry
+            .retry(|| utility_endpoints_api::util_ping_get(&params.client.config))
+            .await
+            .map_err(Error::from)
+            .wrap_err("failed to ping up")?;
+        Ok(*response.meta)
+    }
+}
+
+impl ClientParamsBuilder {
+    pub fn build(self) -> Result<Client> {
+        let params = self.build_params().wrap_err("failed to build parameters")?;
+        let mut config = Configuration {
+            user_agent: Some(params.http.user_agent()),
+            client: params.http.client()?,
+            bearer_access_token: Some(params.api_token),
+            ..Default::default()
+        };
+        if let Some(url) = &params.http.up_base_url {
+            config.base_path = http::base_url(url)?;
         }
+
+        Ok(Client {
+            config,
+            retry: params.retry,
+        })
+    }
+}
+
+impl Client {
+    pub fn builder() -> ClientParamsBuilder {
+        ClientParamsBuilder::default()
     }
 
     /// Retrieve a paginated list of all accounts for the currently authenticated user. The returned
@@ -181,6 +774,23 @@ impl Client {
         }
     }
 
+    /// Retrieve a list of all transactions for a specific account. Results are ordered newest
+    /// first to oldest last.
+    pub fn account_transactions(&self) -> GetAccountTransactionsParamsBuilder<'_> {
+        GetAccountTransactionsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a specific transaction by providing its unique identifier.
+    pub fn transaction(&self) -> GetTransactionParamsBuilder<'_> {
+        GetTransactionParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
     /// Retrieve a list of all transactions across all accounts for the currently authenticated
     /// user. The returned list is [paginated](#pagination) and can be scrolled by following the
     /// `next` and `prev` links where present. To narrow the results to a specific date range
@@ -194,6 +804,145 @@ impl Client {
         }
     }
 
+    /// Retrieve a specific account by providing its unique identifier.
+    pub fn account(&self) -> GetAccountParamsBuilder<'_> {
+        GetAccountParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a list of all categories and their ancestry. The returned list is not paginated.
+    pub fn categories(&self) -> GetCategoriesParamsBuilder<'_> {
+        GetCategoriesParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a specific category by providing its unique identifier.
+    pub fn category(&self) -> GetCategoryParamsBuilder<'_> {
+        GetCategoryParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Updates the category associated with a transaction. Only transactions for which
+    /// `isCategorizable` is set to true support this operation.
+    pub fn update_transaction_category(&self) -> UpdateTransactionCategoryParamsBuilder<'_> {
+        UpdateTransactionCategoryParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a list of all tags currently in use. The returned list is paginated.
+    pub fn tags(&self) -> GetTagsParamsBuilder<'_> {
+        GetTagsParamsBuilder { client: Some(self) }
+    }
+
+    /// Adds or removes tags on a transaction, see [`UpdateTransactionTagsParamsBuilder::add`] and
+    /// [`UpdateTransactionTagsParamsBuilder::remove`].
+    pub fn transaction_tags(&self) -> UpdateTransactionTagsParamsBuilder<'_> {
+        UpdateTransactionTagsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a list of configured webhooks. The returned list is paginated.
+    pub fn webhooks(&self) -> GetWebhooksParamsBuilder<'_> {
+        GetWebhooksParamsBuilder { client: Some(self) }
+    }
+
+    /// Retrieves, deletes or pings a specific webhook, see [`WebhookParamsBuilder::get`],
+    /// [`WebhookParamsBuilder::delete`] and [`WebhookParamsBuilder::ping`].
+    pub fn webhook(&self) -> WebhookParamsBuilder<'_> {
+        WebhookParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Create a new webhook with a given URL. The URL will receive webhook events as JSON-encoded
+    /// `POST` requests.
+    pub fn new_webhook(&self) -> NewWebhookParamsBuilder<'_> {
+        NewWebhookParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Retrieve a list of delivery logs for a webhook by providing its unique identifier. Results
+    /// are ordered newest first to oldest last.
+    pub fn webhook_logs(&self) -> GetWebhookLogsParamsBuilder<'_> {
+        GetWebhookLogsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Make a basic ping request to the API, checking the authentication token is valid.
+    pub fn ping(&self) -> PingParamsBuilder<'_> {
+        PingParamsBuilder { client: Some(self) }
+    }
+
+    /// Fetches the transactions of every account concurrently, page by page, removing
+    /// transactions fetched by more than one slice.
+    fn concurrent_transactions_send<'a>(
+        &'a self,
+        params: GetTransactionsParams<'a>,
+        concurrency: usize,
+    ) -> TransactionStream<'a> {
+        let slices = slices(params.filter_since, params.filter_until, params.slice);
+        let streams = self
+            .accounts_send(
+                GetAccountsParams {
+                    client: self,
+                    filter_type: None,
+                    filter_ownership: None,
+                }
+                .into_api(),
+            )
+            .flat_map(
+                move |account| -> Pin<Box<dyn Stream<Item = TransactionStream<'a>> + 'a>> {
+                    let account = match account {
+                        Ok(account) => account,
+                        Err(e) => {
+                            let e: TransactionStream<'a> =
+                                Box::pin(stream::once(future::ready(Err(e))));
+                            return Box::pin(stream::once(future::ready(e)));
+                        }
+                    };
+
+                    let params = params.clone();
+                    Box::pin(stream::iter(slices.clone()).map(move |(since, until)| {
+                        let api_params = GetAccountTransactionsParams {
+                            client: self,
+                            account_id: account.id.clone(),
+                            filter_status: params.filter_status,
+                            filter_since: since,
+                            filter_until: until,
+                            filter_category: params.filter_category.clone(),
+                            filter_tag: params.filter_tag.clone(),
+                        }
+                        .into_api();
+                        let transactions: TransactionStream<'a> =
+                            self.account_transactions_send(api_params);
+                        transactions
+                    }))
+                },
+            );
+
+        let mut ids = HashSet::new();
+        Box::pin(
+            streams
+                .flatten_unordered(concurrency.max(1))
+                .try_filter(move |x| future::ready(ids.insert(x.id.clone()))),
+        )
+    }
+
     stream_pages_impl!(
         transactions_send,
         transactions_page,
@@ -201,39 +950,204 @@ impl Client {
         TransactionsGetParams
     );
 
+    stream_pages_impl!(
+        account_transactions_send,
+        account_transactions_page,
+        UpTransaction,
+        AccountsAccountIdTransactionsGetParams
+    );
+
     stream_pages_impl!(accounts_send, accounts_page, UpAccount, AccountsGetParams);
 
-    async fn transactions_page(
-        &self,
-        page: Option<&str>,
-        params: transactions_api::TransactionsGetParams,
-    ) -> Result<models::ListTransactionsResponse> {
-        if let Some(page) = page {
-            util::get_page::<
-                models::ListTransactionsResponse,
-                transactions_api::TransactionsGetError,
-            >(&self.config, page)
-            .await
-        } else {
-            transactions_api::transactions_get(&self.config, params).await
-        }
-        .wrap_err("failed to get up transactions page")
+    stream_pages_impl!(tags_send, tags_page, UpTag, TagsGetParams);
+
+    stream_pages_impl!(webhooks_send, webhooks_page, UpWebhook, WebhooksGetParams);
+
+    stream_pages_impl!(
+        webhook_logs_send,
+        webhook_logs_page,
+        UpWebhookLog,
+        WebhooksWebhookIdLogsGetParams
+    );
+
+    page_impl!(
+        transactions_page,
+        transactions_api::transactions_get,
+        models::ListTransactionsResponse,
+        transactions_api::TransactionsGetError,
+        TransactionsGetParams,
+        "failed to get up transactions page"
+    );
+
+    page_impl!(
+        account_transactions_page,
+        transactions_api::accounts_account_id_transactions_get,
+        models::ListTransactionsResponse,
+        transactions_api::AccountsAccountIdTransactionsGetError,
+        AccountsAccountIdTransactionsGetParams,
+        "failed to get up account transactions page"
+    );
+
+    page_impl!(
+        accounts_page,
+        accounts_api::accounts_get,
+        models::ListAccountsResponse,
+        accounts_api::AccountsGetError,
+        AccountsGetParams,
+        "failed to get up accounts page"
+    );
+
+    page_impl!(
+        tags_page,
+        tags_api::tags_get,
+        models::ListTagsResponse,
+        tags_api::TagsGetError,
+        TagsGetParams,
+        "failed to get up tags page"
+    );
+
+    page_impl!(
+        webhooks_page,
+        webhooks_api::webhooks_get,
+        models::ListWebhooksResponse,
+        webhooks_api::WebhooksGetError,
+        WebhooksGetParams,
+        "failed to get up webhooks page"
+    );
+
+    page_impl!(
+        webhook_logs_page,
+        webhooks_api::webhooks_webhook_id_logs_get,
+        models::ListWebhookDeliveryLogsResponse,
+        webhooks_api::WebhooksWebhookIdLogsGetError,
+        WebhooksWebhookIdLogsGetParams,
+        "failed to get up webhook logs page"
+    );
+}
+
+/// A date range, open ended if either end is missing.
+type Slice = (Option<DateTime<FixedOffset>>, Option<DateTime<FixedOffset>>);
+
+/// Splits the date range into slices of the given length, the last slice is open ended if the
+/// range is. Ranges without a start aren't split.
+fn slices(
+    since: Option<DateTime<FixedOffset>>,
+    until: Option<DateTime<FixedOffset>>,
+    slice: Option<Duration>,
+) -> Vec<Slice> {
+    let (Some(since), Some(slice)) = (since, slice.filter(|x| *x > Duration::zero())) else {
+        return vec![(since, until)];
+    };
+
+    let end = until.unwrap_or_else(|| Utc::now().fixed_offset());
+    let mut slices = Vec::new();
+    let mut start = since;
+    while let Some(next) = start.checked_add_signed(slice).filter(|x| *x < end) {
+        slices.push((Some(start), Some(next)));
+        start = next;
     }
+    slices.push((Some(start), until));
+    slices
+}
 
-    async fn accounts_page(
-        &self,
-        page: Option<&str>,
-        params: accounts_api::AccountsGetParams,
-    ) -> Result<models::ListAccountsResponse> {
-        if let Some(page) = page {
-            util::get_page::<models::ListAccountsResponse, accounts_api::AccountsGetError>(
-                &self.config,
-                page,
-            )
-            .await
-        } else {
-            accounts_api::accounts_get(&self.config, params).await
-        }
-        .wrap_err("failed to get up accounts page")
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_slices() -> Result<()> {
+        let since = DateTime::parse_from_rfc3339("2024-01-01T00:00:00+11:00")?;
+        let until = DateTime::parse_from_rfc3339("2024-01-25T00:00:00+11:00")?;
+        let slices = slices(Some(since), Some(until), Some(Duration::days(10)));
+        assert_eq!(
+            vec![
+                (Some(since), Some(since + Duration::days(10))),
+                (
+                    Some(since + Duration::days(10)),
+                    Some(since + Duration::days(20))
+                ),
+                (Some(since + Duration::days(20)), Some(until)),
+            ],
+            slices
+        );
+        assert_eq!(
+            vec![(None, Some(until))],
+            super::slices(None, Some(until), Some(Duration::days(10)))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_transactions() -> Result<()> {
+        let server = mock_server::MockServer::start()?;
+        server.seed_run("test/data/run")?;
+        server.set_max_page_size(1);
+        let client = Client::builder()
+            .api_token("token")
+            .http(HttpSettings {
+                up_base_url: Some(server.up_base_url()),
+                ..HttpSettings::default()
+            })
+            .build()?;
+
+        let ids = |transactions: Vec<UpTransaction>| {
+            let mut ids = transactions
+                .into_iter()
+                .map(|x| x.id.clone())
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        let expected = ids(client.transactions().send()?.try_collect().await?);
+        let transactions = client
+            .transactions()
+            .filter_since(DateTime::parse_from_rfc3339("2023-11-01T00:00:00+11:00")?)
+            .concurrency(4)
+            .slice(Duration::days(7))
+            .send()?
+            .try_collect()
+            .await?;
+        assert_eq!(2, expected.len());
+        assert_eq!(expected, ids(transactions));
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_tags_request() -> Result<()> {
+        let client = Client::builder().api_token("token").build()?;
+        let params = client
+            .transaction_tags()
+            .transaction_id("1")
+            .tags(vec!["Pizza Night".to_owned()])
+            .build()?;
+        let (transaction_id, request) = params.request();
+
+        assert_eq!("1", transaction_id);
+        assert_eq!(
+            Some(models::UpdateTransactionTagsRequest::new(vec![
+                models::TagInputResourceIdentifier::new(
+                    "tags".to_owned(),
+                    "Pizza Night".to_owned()
+                )
+            ])),
+            request
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_category_request() -> Result<()> {
+        let client = Client::builder().api_token("token").build()?;
+        let params = client
+            .update_transaction_category()
+            .transaction_id("1")
+            .build()?
+            .into_api();
+
+        assert_eq!(
+            Some(models::UpdateTransactionCategoryRequest::new(None)),
+            params.update_transaction_category_request
+        );
+        Ok(())
     }
 }
@@ -1,23 +1,97 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, future::Future};
 
 use chrono::{DateTime, FixedOffset};
-use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use color_eyre::eyre::{eyre, Context, ContextCompat, Report, Result};
 use pretty_assertions::Comparison;
 use tracing::error;
 use uuid::Uuid;
 use ynab_client::{
-    apis::{accounts_api, budgets_api, configuration::Configuration, transactions_api},
+    apis::{
+        accounts_api, budgets_api, categories_api, configuration::Configuration, months_api,
+        payees_api, scheduled_transactions_api, transactions_api, user_api,
+    },
     models,
 };
 
-use crate::model::{
-    transaction::{NewYnabTransaction, UpdateYnabTransaction},
-    YnabAccount, YnabTransaction,
+use crate::{
+    api::{
+        http::{self, HttpSettings},
+        rate_limit::RateLimiter,
+        retry::{Classify, Failure, RetryPolicy},
+        Error,
+    },
+    model::{
+        transaction::{NewYnabTransaction, UpdateYnabTransaction},
+        YnabAccount, YnabHybridTransaction, YnabTransaction,
+    },
 };
 
 #[derive(Debug, Clone)]
 pub struct Client {
     pub(crate) config: Configuration,
+    retry: RetryPolicy,
+    rate_limit: Option<RateLimiter>,
+}
+
+/// A failed request attempt.
+enum Attempt<E> {
+    /// The client-side rate limit was reached, which is never retried.
+    Limited(Report),
+    /// The request failed.
+    Failed(E),
+}
+
+impl<E: Classify> Classify for Attempt<E> {
+    fn classify(&self) -> Failure {
+        match self {
+            Self::Limited(_) => Failure::Permanent,
+            Self::Failed(e) => e.classify(),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Attempt<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limited(e) => e.fmt(f),
+            Self::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E> Attempt<E>
+where
+    Error: From<E>,
+{
+    fn into_report(self) -> Report {
+        match self {
+            Self::Limited(e) => e,
+            Self::Failed(e) => Error::from(e).into(),
+        }
+    }
+}
+
+pub type AccountKind = ynab_client::models::AccountType;
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(
+    pattern = "owned",
+    setter(into),
+    build_fn(private, name = "build_params")
+)]
+#[builder_struct_attr(must_use)]
+pub struct ClientParams {
+    /// The personal access token.
+    api_token: String,
+    /// HTTP client settings.
+    #[builder(default)]
+    http: HttpSettings,
+    /// The policy used to retry failed requests.
+    #[builder(default)]
+    retry: RetryPolicy,
+    /// The rate limiter every request must acquire before being sent.
+    #[builder(default, setter(strip_option))]
+    rate_limit: Option<RateLimiter>,
 }
 
 #[derive(Debug, Clone, derive_builder::Builder)]
@@ -35,6 +109,23 @@ pub struct GetAccountsParams<'a> {
     last_knowledge_of_server: Option<i64>,
 }
 
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct NewAccountParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    budget_id: String,
+    /// The name of the account.
+    name: String,
+    /// The type of account.
+    kind: AccountKind,
+    /// The current balance of the account in milliunits format.
+    balance: i64,
+}
+
 #[derive(Debug, Clone, derive_builder::Builder)]
 #[builder(pattern = "owned", setter(into), build_fn(private))]
 #[builder_struct_attr(must_use)]
@@ -94,6 +185,227 @@ pub struct UpdateTransactionsParams<'a> {
     pub transactions: Vec<UpdateYnabTransaction>,
 }
 
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetTransactionParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The id of the transaction.
+    pub transaction_id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetAccountTransactionsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The id of the account.
+    pub account_id: String,
+    /// If specified, only transactions on or after this date will be included.
+    #[builder(default)]
+    pub since_date: Option<DateTime<FixedOffset>>,
+    /// If specified, only transactions of the specified type will be included. `uncategorized`
+    /// and `unapproved` are currently supported.
+    #[builder(default)]
+    pub kind: Option<String>,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetCategoryTransactionsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The id of the category.
+    pub category_id: String,
+    /// If specified, only transactions on or after this date will be included.
+    #[builder(default)]
+    pub since_date: Option<DateTime<FixedOffset>>,
+    /// If specified, only transactions of the specified type will be included. `uncategorized`
+    /// and `unapproved` are currently supported.
+    #[builder(default)]
+    pub kind: Option<String>,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetPayeeTransactionsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The id of the payee.
+    pub payee_id: String,
+    /// If specified, only transactions on or after this date will be included.
+    #[builder(default)]
+    pub since_date: Option<DateTime<FixedOffset>>,
+    /// If specified, only transactions of the specified type will be included. `uncategorized`
+    /// and `unapproved` are currently supported.
+    #[builder(default)]
+    pub kind: Option<String>,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct DeleteTransactionParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The id of the transaction.
+    pub transaction_id: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetScheduledTransactionsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetCategoriesParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct UpdateMonthCategoryParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The budget month in ISO format (e.g. 2016-12-01) or `current` for the current calendar
+    /// month (UTC).
+    pub month: String,
+    /// The id of the category.
+    pub category_id: String,
+    /// The amount assigned to the category in milliunits format.
+    pub budgeted: i64,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetPayeesParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetMonthsParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetMonthParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The budget month in ISO format (e.g. 2016-12-01) or `current` for the current calendar
+    /// month (UTC).
+    pub month: String,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetBudgetParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+    /// The id of the budget. `last-used` can be used to specify the last used budget and
+    /// `default` can be used if default budget selection is enabled (see: https://api.ynab.com/#oauth-default-budget).
+    pub budget_id: String,
+    /// The starting server knowledge.  If provided, only entities that have changed since
+    /// `last_knowledge_of_server` will be included.
+    #[builder(default)]
+    pub last_knowledge_of_server: Option<i64>,
+}
+
+#[derive(Debug, Clone, derive_builder::Builder)]
+#[builder(pattern = "owned", setter(into), build_fn(private))]
+#[builder_struct_attr(must_use)]
+pub struct GetUserParams<'a> {
+    #[builder(private)]
+    client: &'a Client,
+}
+
+/// A delta response. Passing `server_knowledge` as `last_knowledge_of_server` in the next request
+/// only returns entities changed since. Endpoints which may omit it use `Option<i64>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta<T, K = i64> {
+    pub data: T,
+    pub server_knowledge: K,
+}
+
 #[derive(Debug, PartialEq)]
 struct TransactionDiff {
     import_id: Option<String>,
@@ -113,6 +425,19 @@ impl<'a> GetAccountsParams<'a> {
     }
 }
 
+impl<'a> NewAccountParams<'a> {
+    fn into_api(self) -> accounts_api::CreateAccountParams {
+        accounts_api::CreateAccountParams {
+            budget_id: self.budget_id,
+            data: models::PostAccountWrapper::new(models::SaveAccount::new(
+                self.name,
+                self.kind,
+                self.balance,
+            )),
+        }
+    }
+}
+
 impl<'a> GetBudgetsParams<'a> {
     fn into_api(self) -> budgets_api::GetBudgetsParams {
         budgets_api::GetBudgetsParams {
@@ -164,48 +489,187 @@ impl<'a> UpdateTransactionsParams<'a> {
     }
 }
 
+impl<'a> GetTransactionParams<'a> {
+    fn into_api(self) -> transactions_api::GetTransactionByIdParams {
+        transactions_api::GetTransactionByIdParams {
+            budget_id: self.budget_id,
+            transaction_id: self.transaction_id,
+        }
+    }
+}
+
+impl<'a> GetAccountTransactionsParams<'a> {
+    fn into_api(self) -> transactions_api::GetTransactionsByAccountParams {
+        transactions_api::GetTransactionsByAccountParams {
+            budget_id: self.budget_id,
+            account_id: self.account_id,
+            since_date: self.since_date.map(|x| x.to_rfc3339()),
+            r#type: self.kind,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> GetCategoryTransactionsParams<'a> {
+    fn into_api(self) -> transactions_api::GetTransactionsByCategoryParams {
+        transactions_api::GetTransactionsByCategoryParams {
+            budget_id: self.budget_id,
+            category_id: self.category_id,
+            since_date: self.since_date.map(|x| x.to_rfc3339()),
+            r#type: self.kind,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> GetPayeeTransactionsParams<'a> {
+    fn into_api(self) -> transactions_api::GetTransactionsByPayeeParams {
+        transactions_api::GetTransactionsByPayeeParams {
+            budget_id: self.budget_id,
+            payee_id: self.payee_id,
+            since_date: self.since_date.map(|x| x.to_rfc3339()),
+            r#type: self.kind,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> DeleteTransactionParams<'a> {
+    fn into_api(self) -> transactions_api::DeleteTransactionParams {
+        transactions_api::DeleteTransactionParams {
+            budget_id: self.budget_id,
+            transaction_id: self.transaction_id,
+        }
+    }
+}
+
+impl<'a> GetScheduledTransactionsParams<'a> {
+    fn into_api(self) -> scheduled_transactions_api::GetScheduledTransactionsParams {
+        scheduled_transactions_api::GetScheduledTransactionsParams {
+            budget_id: self.budget_id,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> GetCategoriesParams<'a> {
+    fn into_api(self) -> categories_api::GetCategoriesParams {
+        categories_api::GetCategoriesParams {
+            budget_id: self.budget_id,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> UpdateMonthCategoryParams<'a> {
+    fn into_api(self) -> categories_api::UpdateMonthCategoryParams {
+        categories_api::UpdateMonthCategoryParams {
+            budget_id: self.budget_id,
+            month: self.month,
+            category_id: self.category_id,
+            data: models::PatchMonthCategoryWrapper::new(models::SaveMonthCategory::new(
+                self.budgeted,
+            )),
+        }
+    }
+}
+
+impl<'a> GetPayeesParams<'a> {
+    fn into_api(self) -> payees_api::GetPayeesParams {
+        payees_api::GetPayeesParams {
+            budget_id: self.budget_id,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> GetMonthsParams<'a> {
+    fn into_api(self) -> months_api::GetBudgetMonthsParams {
+        months_api::GetBudgetMonthsParams {
+            budget_id: self.budget_id,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
+impl<'a> GetMonthParams<'a> {
+    fn into_api(self) -> months_api::GetBudgetMonthParams {
+        months_api::GetBudgetMonthParams {
+            budget_id: self.budget_id,
+            month: self.month,
+        }
+    }
+}
+
+impl<'a> GetBudgetParams<'a> {
+    fn into_api(self) -> budgets_api::GetBudgetByIdParams {
+        budgets_api::GetBudgetByIdParams {
+            budget_id: self.budget_id,
+            last_knowledge_of_server: self.last_knowledge_of_server,
+        }
+    }
+}
+
 impl<'a> GetAccountsParamsBuilder<'a> {
     pub async fn send(self) -> Result<Vec<YnabAccount>> {
         let params = self.build().wrap_err("failed to build parameters")?;
-        Ok(
-            accounts_api::get_accounts(&params.client.config, params.into_api())
-                .await
-                .wrap_err("failed to get accounts")?
-                .data
-                .accounts
-                .into_iter()
-                .map(YnabAccount::new)
-                .collect::<Vec<_>>(),
-        )
+        Ok(params
+            .client
+            .retry(|| accounts_api::get_accounts(&params.client.config, params.clone().into_api()))
+            .await
+            .wrap_err("failed to get accounts")?
+            .data
+            .accounts
+            .into_iter()
+            .map(YnabAccount::new)
+            .collect::<Vec<_>>())
+    }
+}
+
+impl<'a> NewAccountParamsBuilder<'a> {
+    pub async fn send(self) -> Result<YnabAccount> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let account = params
+            .client
+            .retry_mutation(|| {
+                accounts_api::create_account(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to create account")?
+            .data
+            .account;
+        Ok(YnabAccount::new(*account))
     }
 }
 
 impl<'a> GetBudgetsParamsBuilder<'a> {
     pub async fn send(self) -> Result<Vec<models::BudgetSummary>> {
         let params = self.build().wrap_err("failed to build parameters")?;
-        Ok(
-            budgets_api::get_budgets(&params.client.config, params.into_api())
-                .await
-                .wrap_err("failed to get budgets")?
-                .data
-                .budgets,
-        )
+        Ok(params
+            .client
+            .retry(|| budgets_api::get_budgets(&params.client.config, params.clone().into_api()))
+            .await
+            .wrap_err("failed to get budgets")?
+            .data
+            .budgets)
     }
 }
 
 impl<'a> GetTransactionsParamsBuilder<'a> {
     pub async fn send(self) -> Result<Vec<YnabTransaction>> {
         let params = self.build().wrap_err("failed to build parameters")?;
-        Ok(
-            transactions_api::get_transactions(&params.client.config, params.into_api())
-                .await
-                .wrap_err("failed to get transactions")?
-                .data
-                .transactions
-                .into_iter()
-                .map(YnabTransaction::new)
-                .collect::<Vec<_>>(),
-        )
+        Ok(params
+            .client
+            .retry(|| {
+                transactions_api::get_transactions(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to get transactions")?
+            .data
+            .transactions
+            .into_iter()
+            .map(YnabTransaction::new)
+            .collect::<Vec<_>>())
     }
 }
 
@@ -293,11 +757,17 @@ impl<'a> NewTransactionsParamsBuilder<'a> {
     pub async fn send(self) -> Result<models::SaveTransactionsResponseData> {
         let params = self.build().wrap_err("failed to build parameters")?;
         let transactions = params.transactions.clone();
-        let response =
-            *transactions_api::create_transaction(&params.client.config, params.into_api())
-                .await
-                .wrap_err("failed to create transactions")?
-                .data;
+        let response = *params
+            .client
+            .retry_mutation(|| {
+                transactions_api::create_transaction(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to create transactions")?
+            .data;
         check_response!(transactions, response, "create");
         Ok(response)
     }
@@ -307,25 +777,344 @@ impl<'a> UpdateTransactionsParamsBuilder<'a> {
     pub async fn send(self) -> Result<models::SaveTransactionsResponseData> {
         let params = self.build().wrap_err("failed to build parameters")?;
         let transactions = params.transactions.clone();
-        let response =
-            *transactions_api::update_transactions(&params.client.config, params.into_api())
-                .await
-                .wrap_err("failed to create transactions")?
-                .data;
+        let response = *params
+            .client
+            .retry_mutation(|| {
+                transactions_api::update_transactions(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to create transactions")?
+            .data;
         check_response!(transactions, response, "update");
         Ok(response)
     }
 }
 
+impl<'a> GetTransactionParamsBuilder<'a> {
+    pub async fn send(self) -> Result<YnabTransaction> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let transaction = params
+            .client
+            .retry(|| {
+                transactions_api::get_transaction_by_id(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to get transaction")?
+            .data
+            .transaction;
+        Ok(YnabTransaction::new(*transaction))
+    }
+}
+
+impl<'a> GetAccountTransactionsParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<YnabTransaction>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                transactions_api::get_transactions_by_account(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to get account transactions")?
+            .data;
+        Ok(Delta {
+            data: data
+                .transactions
+                .into_iter()
+                .map(YnabTransaction::new)
+                .collect::<Vec<_>>(),
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetCategoryTransactionsParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<YnabHybridTransaction>, Option<i64>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                transactions_api::get_transactions_by_category(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to get category transactions")?
+            .data;
+        Ok(Delta {
+            data: data
+                .transactions
+                .into_iter()
+                .map(YnabHybridTransaction::new)
+                .collect::<Vec<_>>(),
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetPayeeTransactionsParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<YnabHybridTransaction>, Option<i64>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                transactions_api::get_transactions_by_payee(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to get payee transactions")?
+            .data;
+        Ok(Delta {
+            data: data
+                .transactions
+                .into_iter()
+                .map(YnabHybridTransaction::new)
+                .collect::<Vec<_>>(),
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> DeleteTransactionParamsBuilder<'a> {
+    pub async fn send(self) -> Result<YnabTransaction> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let transaction = params
+            .client
+            .retry_mutation(|| {
+                transactions_api::delete_transaction(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to delete transaction")?
+            .data
+            .transaction;
+        Ok(YnabTransaction::new(*transaction))
+    }
+}
+
+impl<'a> GetScheduledTransactionsParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<models::ScheduledTransactionDetail>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                scheduled_transactions_api::get_scheduled_transactions(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to get scheduled transactions")?
+            .data;
+        Ok(Delta {
+            data: data.scheduled_transactions,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetCategoriesParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<models::CategoryGroupWithCategories>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                categories_api::get_categories(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to get categories")?
+            .data;
+        Ok(Delta {
+            data: data.category_groups,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> UpdateMonthCategoryParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<models::Category>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                categories_api::update_month_category(
+                    &params.client.config,
+                    params.clone().into_api(),
+                )
+            })
+            .await
+            .wrap_err("failed to update month category")?
+            .data;
+        Ok(Delta {
+            data: *data.category,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetPayeesParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<models::Payee>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| payees_api::get_payees(&params.client.config, params.clone().into_api()))
+            .await
+            .wrap_err("failed to get payees")?
+            .data;
+        Ok(Delta {
+            data: data.payees,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetMonthsParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<Vec<models::MonthSummary>>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                months_api::get_budget_months(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to get months")?
+            .data;
+        Ok(Delta {
+            data: data.months,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetMonthParamsBuilder<'a> {
+    pub async fn send(self) -> Result<models::MonthDetail> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let month = params
+            .client
+            .retry(|| {
+                months_api::get_budget_month(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to get month")?
+            .data
+            .month;
+        Ok(*month)
+    }
+}
+
+impl<'a> GetBudgetParamsBuilder<'a> {
+    pub async fn send(self) -> Result<Delta<models::BudgetDetail>> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let data = params
+            .client
+            .retry(|| {
+                budgets_api::get_budget_by_id(&params.client.config, params.clone().into_api())
+            })
+            .await
+            .wrap_err("failed to get budget")?
+            .data;
+        Ok(Delta {
+            data: *data.budget,
+            server_knowledge: data.server_knowledge,
+        })
+    }
+}
+
+impl<'a> GetUserParamsBuilder<'a> {
+    pub async fn send(self) -> Result<models::User> {
+        let params = self.build().wrap_err("failed to build parameters")?;
+        let user = params
+            .client
+            .retry(|| user_api::get_user(&params.client.config))
+            .await
+            .wrap_err("failed to get user")?
+            .data
+            .user;
+        Ok(*user)
+    }
+}
+
+impl ClientParamsBuilder {
+    pub fn build(self) -> Result<Client> {
+        let params = self.build_params().wrap_err("failed to build parameters")?;
+        let mut config = Configuration {
+            user_agent: Some(params.http.user_agent()),
+            client: params.http.client()?,
+            bearer_access_token: Some(params.api_token),
+            ..Default::default()
+        };
+        if let Some(url) = &params.http.ynab_base_url {
+            config.base_path = http::base_url(url)?;
+        }
+
+        Ok(Client {
+            config,
+            retry: params.retry,
+            rate_limit: params.rate_limit,
+        })
+    }
+}
+
 impl Client {
-    #[must_use]
-    pub fn new(api_token: &str) -> Self {
-        Self {
-            config: Configuration {
-                bearer_access_token: Some(api_token.to_owned()),
-                ..Default::default()
-            },
+    pub fn builder() -> ClientParamsBuilder {
+        ClientParamsBuilder::default()
+    }
+
+    async fn acquire(&self) -> Result<()> {
+        if let Some(rate_limit) = &self.rate_limit {
+            rate_limit.acquire().await?;
         }
+        Ok(())
+    }
+
+    /// Retries an idempotent request, acquiring the rate limit before every attempt.
+    async fn retry<T, E, F, Fut>(&self, mut f: F) -> Result<T>
+    where
+        E: Classify + fmt::Display,
+        Error: From<E>,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.retry
+            .retry(|| self.attempt(f()))
+            .await
+            .map_err(Attempt::into_report)
+    }
+
+    /// Retries a request which isn't idempotent, acquiring the rate limit before every attempt.
+    async fn retry_mutation<T, E, F, Fut>(&self, mut f: F) -> Result<T>
+    where
+        E: Classify + fmt::Display,
+        Error: From<E>,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.retry
+            .retry_mutation(|| self.attempt(f()))
+            .await
+            .map_err(Attempt::into_report)
+    }
+
+    async fn attempt<T, E>(
+        &self,
+        request: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, Attempt<E>> {
+        self.acquire().await.map_err(Attempt::Limited)?;
+        request.await.map_err(Attempt::Failed)
     }
 
     /// Returns all accounts.
@@ -336,6 +1125,14 @@ impl Client {
         }
     }
 
+    /// Creates a new account.
+    pub fn new_account(&self) -> NewAccountParamsBuilder<'_> {
+        NewAccountParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
     /// Returns budgets list with summary information.
     pub fn budgets(&self) -> GetBudgetsParamsBuilder<'_> {
         GetBudgetsParamsBuilder {
@@ -370,4 +1167,272 @@ impl Client {
             ..Default::default()
         }
     }
+
+    /// Returns a single transaction.
+    pub fn transaction(&self) -> GetTransactionParamsBuilder<'_> {
+        GetTransactionParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all transactions for a specified account.
+    pub fn account_transactions(&self) -> GetAccountTransactionsParamsBuilder<'_> {
+        GetAccountTransactionsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all transactions for a specified category.
+    pub fn category_transactions(&self) -> GetCategoryTransactionsParamsBuilder<'_> {
+        GetCategoryTransactionsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all transactions for a specified payee.
+    pub fn payee_transactions(&self) -> GetPayeeTransactionsParamsBuilder<'_> {
+        GetPayeeTransactionsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Deletes a transaction, returning the deleted transaction.
+    pub fn delete_transaction(&self) -> DeleteTransactionParamsBuilder<'_> {
+        DeleteTransactionParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all scheduled transactions.
+    pub fn scheduled_transactions(&self) -> GetScheduledTransactionsParamsBuilder<'_> {
+        GetScheduledTransactionsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all categories grouped by category group. Amounts (budgeted, activity, balance,
+    /// etc.) are specific to the current budget month (UTC).
+    pub fn categories(&self) -> GetCategoriesParamsBuilder<'_> {
+        GetCategoriesParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Updates the amount assigned to a category for a specific month.
+    pub fn update_month_category(&self) -> UpdateMonthCategoryParamsBuilder<'_> {
+        UpdateMonthCategoryParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all payees.
+    pub fn payees(&self) -> GetPayeesParamsBuilder<'_> {
+        GetPayeesParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns all budget months.
+    pub fn months(&self) -> GetMonthsParamsBuilder<'_> {
+        GetMonthsParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a single budget month.
+    pub fn month(&self) -> GetMonthParamsBuilder<'_> {
+        GetMonthParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns a single budget with all related entities. This resource is effectively a full
+    /// budget export.
+    pub fn budget(&self) -> GetBudgetParamsBuilder<'_> {
+        GetBudgetParamsBuilder {
+            client: Some(self),
+            ..Default::default()
+        }
+    }
+
+    /// Returns authenticated user information.
+    pub fn user(&self) -> GetUserParamsBuilder<'_> {
+        GetUserParamsBuilder { client: Some(self) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::cmd::fixture::BUDGET_ID;
+
+    const ACCOUNT_ID: &str = "9c1f3e7a-2b4d-4c6e-8f0a-1b3c5d7e9f2a";
+    const PAYEE_ID: &str = "0b6a6a9e-3f1d-4b7c-8e2a-5d4c3b2a1f0e";
+    const GROCERIES_ID: &str = "6f1e2d3c-4b5a-4968-8776-a5b4c3d2e1f0";
+    const SNACKS_ID: &str = "1a2b3c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d";
+
+    fn transaction(id: &str, category_id: &str) -> Value {
+        json!({
+            "id": id,
+            "date": "2023-12-02",
+            "amount": -57_840,
+            "memo": null,
+            "cleared": "cleared",
+            "approved": true,
+            "flag_color": null,
+            "account_id": ACCOUNT_ID,
+            "account_name": "Spending",
+            "payee_id": PAYEE_ID,
+            "payee_name": "7-Eleven",
+            "category_id": category_id,
+            "category_name": null,
+            "transfer_account_id": null,
+            "transfer_transaction_id": null,
+            "matched_transaction_id": null,
+            "import_id": null,
+            "import_payee_name": null,
+            "import_payee_name_original": null,
+            "debt_transaction_type": null,
+            "deleted": false,
+            "subtransactions": [],
+        })
+    }
+
+    #[tokio::test]
+    async fn delta_transactions() -> Result<()> {
+        let server = mock_server::MockServer::start()?;
+        server.seed_run("test/data/run")?;
+        server.add_ynab_transaction(&transaction("1", GROCERIES_ID))?;
+        server.add_ynab_transaction(&transaction("2", SNACKS_ID))?;
+        let client = Client::builder()
+            .api_token("token")
+            .http(HttpSettings {
+                ynab_base_url: Some(server.ynab_base_url()),
+                ..HttpSettings::default()
+            })
+            .build()?;
+        let ids = |ids: Vec<&str>| ids.into_iter().map(str::to_owned).collect::<Vec<_>>();
+
+        let account = client
+            .account_transactions()
+            .budget_id(BUDGET_ID)
+            .account_id(ACCOUNT_ID)
+            .send()
+            .await?;
+        let knowledge = account.server_knowledge;
+        assert_eq!(server.server_knowledge(), knowledge);
+        assert_eq!(
+            ids(vec!["1", "2"]),
+            account
+                .data
+                .iter()
+                .map(|x| x.id.clone())
+                .collect::<Vec<_>>()
+        );
+
+        let category = client
+            .category_transactions()
+            .budget_id(BUDGET_ID)
+            .category_id(GROCERIES_ID)
+            .send()
+            .await?;
+        assert_eq!(Some(knowledge), category.server_knowledge);
+        assert_eq!(
+            ids(vec!["1"]),
+            category
+                .data
+                .iter()
+                .map(|x| x.id.clone())
+                .collect::<Vec<_>>()
+        );
+
+        let payee = client
+            .payee_transactions()
+            .budget_id(BUDGET_ID)
+            .payee_id(PAYEE_ID)
+            .send()
+            .await?;
+        assert_eq!(2, payee.data.len());
+
+        let deleted = client
+            .delete_transaction()
+            .budget_id(BUDGET_ID)
+            .transaction_id("1")
+            .send()
+            .await?;
+        assert!(deleted.deleted);
+
+        // Only the deletion is returned since the last knowledge, and deleted transactions are
+        // left out of full requests.
+        let account = client
+            .account_transactions()
+            .budget_id(BUDGET_ID)
+            .account_id(ACCOUNT_ID)
+            .last_knowledge_of_server(knowledge)
+            .send()
+            .await?;
+        assert!(account.server_knowledge > knowledge);
+        assert_eq!(
+            vec![("1".to_owned(), true)],
+            account
+                .data
+                .iter()
+                .map(|x| (x.id.clone(), x.deleted))
+                .collect::<Vec<_>>()
+        );
+        let category = client
+            .category_transactions()
+            .budget_id(BUDGET_ID)
+            .category_id(GROCERIES_ID)
+            .send()
+            .await?;
+        assert!(category.data.is_empty());
+
+        let categories = client
+            .categories()
+            .budget_id(BUDGET_ID)
+            .last_knowledge_of_server(knowledge)
+            .send()
+            .await?;
+        assert_eq!(server.server_knowledge(), categories.server_knowledge);
+
+        assert!(client
+            .delete_transaction()
+            .budget_id(BUDGET_ID)
+            .transaction_id("1")
+            .send()
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn update_month_category_request() -> Result<()> {
+        let client = Client::builder().api_token("token").build()?;
+        let params = client
+            .update_month_category()
+            .budget_id("budget")
+            .month("current")
+            .category_id("category")
+            .budgeted(12_340)
+            .build()?
+            .into_api();
+
+        assert_eq!("current", params.month);
+        assert_eq!(12_340, params.data.category.budgeted);
+        Ok(())
+    }
 }
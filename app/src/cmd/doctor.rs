@@ -0,0 +1,212 @@
+use std::fs;
+
+use color_eyre::eyre::{eyre, Context, ContextCompat};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    cmd,
+    frontend::config::Config,
+    model::{Account, TransferRule, UpAccount, YnabAccount},
+    Error, Result,
+};
+
+/// The outcome of a single check, the detail describes what was found or why it failed.
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub result: Result<String>,
+}
+
+/// Checks the profile's tokens, budget, accounts, rules and runs directory, printing a checklist.
+pub async fn doctor(config: &Config) -> Result<()> {
+    info!("checking profile `{}`...", config.name);
+    let checks = checks(config).await;
+    for check in &checks {
+        match &check.result {
+            Ok(detail) => println!("[ok]   {}: {detail}", check.name),
+            Err(e) => println!("[fail] {}: {e:#}", check.name),
+        }
+    }
+
+    let num_failed = checks.iter().filter(|x| x.result.is_err()).count();
+    if num_failed > 0 {
+        return Err(eyre!("{num_failed} of {} checks failed", checks.len()));
+    }
+    info!("all {} checks passed", checks.len());
+    Ok(())
+}
+
+/// Runs every check, checks depending on a failed check are skipped.
+pub async fn checks(config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut check = |name: String, result: Result<String>| {
+        let is_ok = result.is_ok();
+        checks.push(Check { name, result });
+        is_ok
+    };
+
+    let mut up_ok = true;
+    for profile in &config.up {
+        let result = async {
+            let meta = config.up_client(profile)?.ping().send().await?;
+            Ok::<_, Error>(format!("token is valid {}", meta.status_emoji))
+        }
+        .await;
+        up_ok &= check(format!("up profile `{}`", profile.name()), result);
+    }
+
+    let result = async {
+        let user = config.ynab_client()?.user().send().await?;
+        Ok::<_, Error>(format!("token is valid for user `{}`", user.id))
+    }
+    .await;
+    let ynab_ok = check("ynab token".to_owned(), result);
+
+    let result = async {
+        let budget_id = config
+            .ynab
+            .budget_id
+            .as_deref()
+            .wrap_err("`budget_id` isn't set")?;
+        let budget_id =
+            Uuid::parse_str(budget_id).wrap_err(format!("invalid budget id `{budget_id}`"))?;
+        let budgets = cmd::get::budget::ynab(config).await?;
+        let budget = budgets
+            .iter()
+            .find(|x| x.id == budget_id)
+            .wrap_err(format!("budget `{budget_id}` not found or inaccessible"))?;
+        Ok::<_, Error>(format!("`{}`", budget.name))
+    }
+    .await;
+    let budget_ok = ynab_ok && check("ynab budget".to_owned(), result);
+
+    if up_ok && budget_ok {
+        let accounts = async {
+            Ok::<_, Error>((
+                cmd::get::account::up(config).await?,
+                cmd::get::account::ynab(config).await?,
+            ))
+        }
+        .await;
+        match accounts {
+            Ok((up_accounts, ynab_accounts)) => {
+                for (name, result) in account_checks(config, &up_accounts, &ynab_accounts) {
+                    check(name, result);
+                }
+            }
+            Err(e) => {
+                check("accounts".to_owned(), Err(e));
+            }
+        }
+    }
+
+    let runs_dir = config.runs_dir();
+    let result = (|| -> std::io::Result<String> {
+        let path = runs_dir.join(".doctor");
+        fs::create_dir_all(&runs_dir)?;
+        fs::write(&path, [])?;
+        fs::remove_file(&path)?;
+        Ok(format!("`{}` is writable", runs_dir.to_string_lossy()))
+    })()
+    .wrap_err(format!("`{}` isn't writable", runs_dir.to_string_lossy()));
+    check("runs directory".to_owned(), result);
+
+    checks
+}
+
+/// Checks account matches, account mappings and transfer rules.
+fn account_checks(
+    config: &Config,
+    up_accounts: &[UpAccount],
+    ynab_accounts: &[YnabAccount],
+) -> Vec<(String, Result<String>)> {
+    let mut checks = Vec::new();
+
+    match Account::identify(up_accounts, ynab_accounts, &config.accounts) {
+        Ok(accounts) => {
+            for up_account in up_accounts {
+                let name = up_account.attributes.display_name.trim();
+                let result = accounts
+                    .iter()
+                    .find(|x| x.up_id == up_account.id)
+                    .map(|_| format!("matches `{}`", up_account.ynab_name(&config.accounts)))
+                    .wrap_err(format!(
+                        "no ynab account named `{}` with a transfer payee",
+                        up_account.ynab_name(&config.accounts)
+                    ));
+                checks.push((format!("up account `{name}`"), result));
+            }
+        }
+        Err(e) => checks.push(("accounts".to_owned(), Err(e))),
+    }
+
+    for (up_name, ynab_name) in &config.accounts {
+        let result = if !up_accounts
+            .iter()
+            .any(|x| x.attributes.display_name.trim() == up_name.trim())
+        {
+            Err(eyre!("no up account named `{up_name}`"))
+        } else if !ynab_accounts
+            .iter()
+            .any(|x| x.name.trim() == ynab_name.trim())
+        {
+            Err(eyre!("no ynab account named `{ynab_name}`"))
+        } else {
+            Ok(format!("maps to `{ynab_name}`"))
+        };
+        checks.push((format!("account mapping `{up_name}`"), result));
+    }
+
+    for rule in &config.rules {
+        let result = TransferRule::identify(std::slice::from_ref(rule), ynab_accounts)
+            .map(|_| "valid".to_owned());
+        checks.push((format!("rule for `{}`", rule.account), result));
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cmd::fixture::Fixture;
+
+    fn failed(checks: &[Check]) -> Vec<&str> {
+        checks
+            .iter()
+            .filter(|x| x.result.is_err())
+            .map(|x| x.name.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn healthy_profile() -> Result<()> {
+        let fixture = Fixture::new("doctor_healthy_profile")?;
+        let checks = checks(&fixture.config).await;
+        assert!(failed(&checks).is_empty());
+        assert!(checks.iter().any(|x| x.name == "up account `Spending`"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn misconfigured_profile() -> Result<()> {
+        let fixture = Fixture::with_config(
+            "doctor_misconfigured_profile",
+            r#"
+            [accounts]
+            Savings = "Savings"
+
+            [[rules]]
+            description = "("
+            account = "Spending"
+            "#,
+        )?;
+        let checks = checks(&fixture.config).await;
+        assert_eq!(
+            vec!["account mapping `Savings`", "rule for `Spending`"],
+            failed(&checks)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+//! A mock server seeded with `test/data/run` and a profile using it, for end-to-end tests.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::ContextCompat;
+use figment::{
+    providers::{Format, Toml},
+    Figment,
+};
+use mock_server::{Method, MockServer, Service};
+
+use crate::{
+    cmd::sync::{self, Args},
+    frontend::config::Config,
+    Result,
+};
+
+pub const BUDGET_ID: &str = "4a0e2b2a-6d3c-4b8e-9f1a-2c3d4e5f6a7b";
+
+pub struct Fixture {
+    pub server: MockServer,
+    pub config: Config,
+    pub runs_path: PathBuf,
+}
+
+impl Fixture {
+    pub fn new(name: &str) -> Result<Self> {
+        Self::with_config(name, "")
+    }
+
+    /// Creates the fixture, appending `extra` to the profile's config.
+    pub fn with_config(name: &str, extra: &str) -> Result<Self> {
+        let runs_path = std::env::temp_dir().join(format!("up_ynab-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&runs_path);
+
+        let server = MockServer::start()?;
+        server.seed_run("test/data/run")?;
+        let toml = format!(
+            r#"
+            runs_path = "{}"
+
+            [up]
+            api_token = "up-token"
+
+            [ynab]
+            api_token = "ynab-token"
+            budget_id = "{BUDGET_ID}"
+
+            [retry]
+            initial_interval_ms = 1
+            max_interval_ms = 1
+
+            [http]
+            up_base_url = "{}"
+            ynab_base_url = "{}"
+
+            {extra}
+            "#,
+            runs_path.to_string_lossy(),
+            server.up_base_url(),
+            server.ynab_base_url(),
+        );
+        let config = Config::load(&Figment::from(Toml::string(&toml)))?
+            .into_values()
+            .next()
+            .wrap_err("missing profile")?;
+
+        Ok(Self {
+            server,
+            config,
+            runs_path,
+        })
+    }
+
+    pub async fn sync(&self) -> Result<()> {
+        sync::sync(&self.config, Self::args(None)).await
+    }
+
+    /// Resumes the sync of the run.
+    pub async fn resume(&self, run: &Path) -> Result<()> {
+        sync::sync(&self.config, Self::args(Some(run.to_path_buf()))).await
+    }
+
+    fn args(resume: Option<PathBuf>) -> Args {
+        Args {
+            since: None,
+            until: None,
+            in_path: None,
+            dry_run: false,
+            create_missing_accounts: false,
+            resume,
+        }
+    }
+
+    pub fn import_ids(&self) -> BTreeSet<String> {
+        self.server
+            .ynab_transactions()
+            .iter()
+            .filter_map(|x| x["import_id"].as_str().map(ToOwned::to_owned))
+            .collect()
+    }
+
+    pub fn num_requests(&self, service: Service, method: &Method, path: &str) -> usize {
+        self.server
+            .requests()
+            .iter()
+            .filter(|x| x.service == service && x.method == *method && x.path.ends_with(path))
+            .count()
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.runs_path);
+    }
+}
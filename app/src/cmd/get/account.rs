@@ -1,31 +1,53 @@
 use color_eyre::eyre::ContextCompat;
 use futures::StreamExt;
+use itertools::Itertools;
 use tracing::info;
 
 use crate::{
-    api::{up, ynab},
-    frontend::config::Config,
+    api::up,
+    cmd::get::format_milliunits,
+    frontend::{
+        config::Config,
+        output::{serialized_name, Tabular},
+    },
     model::{UpAccount, YnabAccount},
     Result,
 };
 
 pub async fn up(config: &Config) -> Result<Vec<UpAccount>> {
-    info!("fetching up accounts...");
-    let up_client = up::Client::new(&config.up.api_token);
-    let accounts = up_client
-        .accounts()
-        .send()?
-        .collect::<Vec<_>>()
-        .await
+    let owner = config.joint_account_owner()?;
+    let mut accounts = Vec::new();
+    for (i, profile) in config.up.iter().enumerate() {
+        info!(
+            "fetching up accounts for up profile `{}`...",
+            profile.name()
+        );
+        let up_client = config.up_client(profile)?;
+        // Joint accounts are only fetched by the profile that owns them.
+        let ownership = (i != owner).then_some(up::OwnershipKind::Individual);
+        accounts.extend(
+            up_client
+                .accounts()
+                .filter_ownership(ownership)
+                .send()?
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?,
+        );
+    }
+
+    let accounts = accounts
         .into_iter()
-        .collect::<Result<Vec<_>>>()?;
+        .unique_by(|x| x.id.clone())
+        .collect::<Vec<_>>();
     info!("fetched {} up accounts", accounts.len());
     Ok(accounts)
 }
 
 pub async fn ynab(config: &Config) -> Result<Vec<YnabAccount>> {
     info!("fetching ynab accounts...");
-    let ynab_client = ynab::Client::new(&config.ynab.api_token);
+    let ynab_client = config.ynab_client()?;
     let budget_id = config
         .ynab
         .budget_id
@@ -35,3 +57,93 @@ pub async fn ynab(config: &Config) -> Result<Vec<YnabAccount>> {
     info!("fetched {} ynab accounts", accounts.len());
     Ok(accounts)
 }
+
+impl Tabular for UpAccount {
+    const HEADERS: &'static [&'static str] =
+        &["id", "name", "type", "ownership", "balance", "created_at"];
+
+    fn cells(&self) -> Vec<String> {
+        let attributes = &self.attributes;
+        vec![
+            self.id.clone(),
+            attributes.display_name.clone(),
+            serialized_name(&attributes.account_type),
+            serialized_name(&attributes.ownership_type),
+            attributes.balance.value.clone(),
+            attributes.created_at.clone(),
+        ]
+    }
+}
+
+impl Tabular for YnabAccount {
+    const HEADERS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "type",
+        "on_budget",
+        "closed",
+        "balance",
+        "cleared_balance",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            serialized_name(&self.r#type),
+            self.on_budget.to_string(),
+            self.closed.to_string(),
+            format_milliunits(self.balance),
+            format_milliunits(self.cleared_balance),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn up_account_cells() -> Result<()> {
+        let account = serde_json::from_str::<UpAccount>(&fs::read_to_string(
+            "test/data/run/up_accounts/2be1c9de-7a89-4e8f-8077-f535150b588d.json",
+        )?)?;
+        assert_eq!(
+            vec![
+                "2be1c9de-7a89-4e8f-8077-f535150b588d",
+                "Spending",
+                "TRANSACTIONAL",
+                "INDIVIDUAL",
+                "1042.16",
+                "2021-07-01T10:00:00+10:00",
+            ],
+            account.cells()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ynab_account_cells() -> Result<()> {
+        let mut account = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/run/ynab_accounts/9c1f3e7a-2b4d-4c6e-8f0a-1b3c5d7e9f2a.json",
+        )?)?;
+        account["balance"] = (-1_042_160).into();
+        account["cleared_balance"] = 5_000.into();
+        let account = serde_json::from_value::<YnabAccount>(account)?;
+        assert_eq!(
+            vec![
+                "9c1f3e7a-2b4d-4c6e-8f0a-1b3c5d7e9f2a",
+                "Spending",
+                "checking",
+                "true",
+                "false",
+                "-1042.16",
+                "5.00",
+            ],
+            account.cells()
+        );
+        Ok(())
+    }
+}
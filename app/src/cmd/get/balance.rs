@@ -1,18 +1,46 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::{DateTime, FixedOffset};
+use color_eyre::eyre::ContextCompat;
 use fallible_iterator::{FallibleIterator, IteratorExt};
+use serde::Serialize;
 use tracing::info;
 
 use crate::{
-    frontend::{cli, Config, Run},
-    model::{balance, Account},
+    frontend::{
+        cli::{self, OutputArgs},
+        output,
+        output::Tabular,
+        Config, Run,
+    },
+    model::{balance, balance::Balance, Account, TransferRule},
     Result,
 };
 
 pub type UpArgs = cli::get::balance::up::Args;
 pub type YnabArgs = cli::get::balance::ynab::Args;
 
+/// A transaction with the balances of every account after it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BalanceRow {
+    pub time: DateTime<FixedOffset>,
+    pub id: String,
+    pub amount: String,
+    pub msg: Option<String>,
+    pub kind: String,
+    pub to: String,
+    pub from: String,
+    /// Balance of `to` after the transaction.
+    pub to_balance: String,
+    /// Balance of `from` after the transaction, for transfers between managed accounts.
+    pub from_balance: Option<String>,
+    /// Balances by account name.
+    pub balances: BTreeMap<String, String>,
+}
+
 pub async fn up(config: &Config, args: UpArgs) -> Result<()> {
     let run = if let Some(in_path) = args.in_path {
-        Run::read(in_path)?
+        Run::read(in_path, config.secret()?.as_ref())?
     } else {
         Run::fetch(config, args.since, args.until).await?
     };
@@ -20,6 +48,7 @@ pub async fn up(config: &Config, args: UpArgs) -> Result<()> {
     let accounts = Account::identify(
         &run.up_accounts.unwrap_or_default(),
         &run.ynab_accounts.unwrap_or_default(),
+        &config.accounts,
     )?;
 
     let transactions = run
@@ -32,30 +61,270 @@ pub async fn up(config: &Config, args: UpArgs) -> Result<()> {
         .collect::<Vec<_>>()?;
 
     let balances = balance::running_total(&transactions);
-    for balance in &balances {
-        if let Some(since) = args.since
-            && balance.transaction.timestamp <= since
-        {
-            continue;
-        }
+    write(
+        &balances,
+        args.since,
+        args.until,
+        &args.output,
+        args.out_path,
+    )
+}
 
-        if let Some(until) = args.until
-            && balance.transaction.timestamp >= until
-        {
-            continue;
-        }
+/// Lists the running balance of the run's YNAB transactions, in accounts with an Up counterpart.
+pub fn ynab(config: &Config, args: YnabArgs) -> Result<()> {
+    let run = Run::read(args.in_path, config.secret()?.as_ref())?;
+    let budget_id = config
+        .ynab
+        .budget_id
+        .as_deref()
+        .wrap_err("missing budget id")?;
+    let budget = run
+        .ynab_budgets
+        .unwrap_or_default()
+        .into_iter()
+        .find(|x| x.id.to_string() == budget_id)
+        .wrap_err(format!("failed to find budget with id: `{budget_id}`"))?;
 
-        info!("{balance}");
-    }
+    let ynab_accounts = run.ynab_accounts.unwrap_or_default();
+    let accounts = Account::identify(
+        &run.up_accounts.unwrap_or_default(),
+        &ynab_accounts,
+        &config.accounts,
+    )?;
+    let unmanaged_accounts = TransferRule::identify(&config.rules, &ynab_accounts)?
+        .into_iter()
+        .map(|x| x.account)
+        .collect::<Vec<_>>();
+
+    let transactions = run
+        .ynab_transactions
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|x| {
+            accounts
+                .iter()
+                .any(|account| account.ynab_id == x.account_id)
+        })
+        .map(|x| x.to_transaction(&budget, &accounts, &unmanaged_accounts))
+        .transpose_into_fallible()
+        .filter(|x| Ok(x.is_normalized()))
+        .collect::<Vec<_>>()?;
+
+    let balances = balance::running_total(&transactions);
+    write(
+        &balances,
+        args.since,
+        args.until,
+        &args.output,
+        args.out_path,
+    )
+}
+
+/// Writes the balances within the date range, and every balance to the CSV file if given.
+fn write(
+    balances: &[Balance],
+    since: Option<DateTime<FixedOffset>>,
+    until: Option<DateTime<FixedOffset>>,
+    output: &OutputArgs,
+    out_path: Option<PathBuf>,
+) -> Result<()> {
+    let rows = balances
+        .iter()
+        .filter(|x| since.map_or(true, |since| x.transaction.timestamp > since))
+        .filter(|x| until.map_or(true, |until| x.transaction.timestamp < until))
+        .map(BalanceRow::new)
+        .collect::<Vec<_>>();
+    output::write_to(output, &rows)?;
 
-    if let Some(out_path) = args.out_path {
+    if let Some(out_path) = out_path {
         info!("writing balance CSV to `{}`", out_path.to_string_lossy());
-        balance::write_csv(&balances, out_path)?;
+        balance::write_csv(balances, out_path)?;
     }
 
     Ok(())
 }
 
-pub async fn ynab(config: &Config, args: YnabArgs) -> Result<()> {
-    unimplemented!()
+impl BalanceRow {
+    #[must_use]
+    pub fn new(balance: &Balance) -> Self {
+        let transaction = balance.transaction;
+        let balance_of = |account: &Account| {
+            balance
+                .values
+                .get(account)
+                .map(|x| x.amount.to_string())
+                .unwrap_or_default()
+        };
+        Self {
+            time: transaction.timestamp,
+            id: transaction.id.clone(),
+            amount: transaction.amount.amount.to_string(),
+            msg: transaction.msg.clone(),
+            kind: transaction.kind.name().to_owned(),
+            to: transaction.to_name().to_owned(),
+            from: transaction.from_name().to_owned(),
+            to_balance: balance_of(transaction.to()),
+            from_balance: transaction.from().map(balance_of),
+            balances: balance
+                .values
+                .iter()
+                .map(|(k, v)| (k.name.clone(), v.amount.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Tabular for BalanceRow {
+    const HEADERS: &'static [&'static str] = &[
+        "time",
+        "id",
+        "amount",
+        "msg",
+        "kind",
+        "to",
+        "from",
+        "to_balance",
+        "from_balance",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.time.to_rfc3339(),
+            self.id.clone(),
+            self.amount.clone(),
+            self.msg.clone().unwrap_or_default(),
+            self.kind.clone(),
+            self.to.clone(),
+            self.from.clone(),
+            self.to_balance.clone(),
+            self.from_balance.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, str::FromStr};
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{cmd::fixture::Fixture, model::UpTransaction};
+
+    fn account(name: &str, up_id: &str, ynab_id: &str) -> Result<Account> {
+        Ok(Account {
+            name: name.to_owned(),
+            up_id: up_id.to_owned(),
+            ynab_id: Uuid::from_str(ynab_id)?,
+            ynab_transfer_id: Uuid::nil(),
+        })
+    }
+
+    #[test]
+    fn balance_rows() -> Result<()> {
+        let accounts = vec![
+            account(
+                "Home",
+                "328160b1-d7bc-41ee-9d7b-c7da4f2484b0",
+                "2b00a77e-9b3c-4277-9c6c-6944f7696705",
+            )?,
+            account(
+                "Spending",
+                "2be1c9de-7a89-4e8f-8077-f535150b588d",
+                "f6ca888b-327a-45d0-9775-830abdaa3a04",
+            )?,
+        ];
+        let transactions = serde_json::from_str::<Vec<UpTransaction>>(&fs::read_to_string(
+            "test/data/up_transfer_balance.json",
+        )?)?
+        .into_iter()
+        .map(|x| x.to_transaction(&accounts))
+        .transpose_into_fallible()
+        .filter(|x| Ok(x.is_normalized()))
+        .collect::<Vec<_>>()?;
+
+        let rows = balance::running_total(&transactions)
+            .iter()
+            .map(BalanceRow::new)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                BalanceRow {
+                    time: DateTime::parse_from_rfc3339("2022-03-15T13:52:00+11:00")?,
+                    id: "c86d3d61-106d-460b-99af-470912d17a56".to_owned(),
+                    amount: "900.00".to_owned(),
+                    msg: Some("Initial amount".to_owned()),
+                    kind: "external".to_owned(),
+                    to: "Spending".to_owned(),
+                    from: "Initial amount".to_owned(),
+                    to_balance: "900.00".to_owned(),
+                    from_balance: None,
+                    balances: BTreeMap::from([("Spending".to_owned(), "900.00".to_owned())]),
+                },
+                BalanceRow {
+                    time: DateTime::parse_from_rfc3339("2023-09-01T13:52:09+10:00")?,
+                    id: "0b519c3e-5958-49ec-bc48-86d792247a2f".to_owned(),
+                    amount: "500.00".to_owned(),
+                    msg: Some("Transfer from Spending".to_owned()),
+                    kind: "internal".to_owned(),
+                    to: "Home".to_owned(),
+                    from: "Spending".to_owned(),
+                    to_balance: "500.00".to_owned(),
+                    from_balance: Some("400.00".to_owned()),
+                    balances: BTreeMap::from([
+                        ("Home".to_owned(), "500.00".to_owned()),
+                        ("Spending".to_owned(), "400.00".to_owned()),
+                    ]),
+                },
+            ],
+            rows
+        );
+        assert_eq!(
+            vec![
+                "2023-09-01T13:52:09+10:00",
+                "0b519c3e-5958-49ec-bc48-86d792247a2f",
+                "500.00",
+                "Transfer from Spending",
+                "internal",
+                "Home",
+                "Spending",
+                "500.00",
+                "400.00",
+            ],
+            rows[1].cells()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ynab_balance_rows() -> Result<()> {
+        let fixture = Fixture::new("ynab_balance_rows")?;
+        fixture.sync().await?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+
+        let path = fixture.runs_path.join("balances.json");
+        let args = YnabArgs {
+            in_path: run.path,
+            out_path: None,
+            since: None,
+            until: None,
+            output: OutputArgs {
+                format: None,
+                output: Some(path.clone()),
+            },
+        };
+        ynab(&fixture.config, args)?;
+        let rows = serde_json::from_str::<Vec<serde_json::Value>>(&fs::read_to_string(path)?)?;
+        assert_eq!(
+            vec![
+                "5ce7c223-0188-4b68-8d19-227a7cc3464d",
+                "9f08959d-51d2-43a8-a45a-154373870094"
+            ],
+            rows.iter()
+                .filter_map(|x| x["id"].as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("-46.89", rows[1]["balances"]["Spending"]);
+        Ok(())
+    }
 }
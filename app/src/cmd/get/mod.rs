@@ -2,3 +2,11 @@ pub mod account;
 pub mod balance;
 pub mod budget;
 pub mod transaction;
+
+use crate::model::format_cents;
+
+/// Formats a YNAB amount in thousandths of a unit as a decimal amount, e.g. `-12.30`.
+#[must_use]
+pub fn format_milliunits(milliunits: i64) -> String {
+    format_cents(milliunits / 10)
+}
@@ -1,10 +1,18 @@
+use std::collections::HashSet;
+
 use color_eyre::eyre::ContextCompat;
 use futures::{StreamExt, TryStreamExt};
+use itertools::Itertools;
 use tracing::{error, info};
 
 use crate::{
-    api::{up, ynab},
-    frontend::{cli, Config},
+    api::up,
+    cmd::get::format_milliunits,
+    frontend::{
+        cli,
+        output::{serialized_name, Tabular},
+        Config,
+    },
     model::{UpTransaction, YnabTransaction},
     Result,
 };
@@ -13,25 +21,57 @@ pub type UpArgs = cli::get::transaction::up::Args;
 pub type YnabArgs = cli::get::transaction::ynab::Args;
 
 pub async fn up(config: &Config, args: UpArgs) -> Result<Vec<UpTransaction>> {
-    info!("fetching up transactions...");
-    let up_client = up::Client::new(&config.up.api_token);
-    let transactions = up_client
-        .transactions()
-        .filter_since(args.since)
-        .filter_until(args.until)
-        .send()?
-        .inspect_err(|e| error!("failed to fetch transaction: {e}"))
-        .collect::<Vec<_>>()
-        .await
+    let owner = config.joint_account_owner()?;
+    let mut transactions = Vec::new();
+    for (i, profile) in config.up.iter().enumerate() {
+        info!(
+            "fetching up transactions for up profile `{}`...",
+            profile.name()
+        );
+        let up_client = config.up_client(profile)?;
+
+        // Transactions in joint accounts are only synced by the profile that owns them.
+        let joint_account_ids = if i == owner {
+            HashSet::new()
+        } else {
+            up_client
+                .accounts()
+                .filter_ownership(up::OwnershipKind::Joint)
+                .send()?
+                .map_ok(|x| x.id.clone())
+                .try_collect::<HashSet<_>>()
+                .await?
+        };
+
+        transactions.extend(
+            up_client
+                .transactions()
+                .filter_since(args.since)
+                .filter_until(args.until)
+                .concurrency(config.fetch.concurrency)
+                .slice(config.fetch.slice_days)
+                .send()?
+                .inspect_err(|e| error!("failed to fetch transaction: {e}"))
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .filter_ok(|x| !joint_account_ids.contains(&x.relationships.account.data.id))
+                .collect::<Result<Vec<_>>>()?,
+        );
+    }
+
+    // Profiles sharing an account both see transfers into it.
+    let transactions = transactions
         .into_iter()
-        .collect::<Result<Vec<_>>>()?;
+        .unique_by(|x| x.id.clone())
+        .collect::<Vec<_>>();
     info!("fetched {} up transactions", transactions.len());
     Ok(transactions)
 }
 
 pub async fn ynab(config: &Config, args: YnabArgs) -> Result<Vec<YnabTransaction>> {
     info!("fetching ynab transactions...");
-    let ynab_client = ynab::Client::new(&config.ynab.api_token);
+    let ynab_client = config.ynab_client()?;
     let budget_id = config
         .ynab
         .budget_id
@@ -46,3 +86,185 @@ pub async fn ynab(config: &Config, args: YnabArgs) -> Result<Vec<YnabTransaction
     info!("fetched {} ynab transactions", transactions.len());
     Ok(transactions)
 }
+
+impl Tabular for UpTransaction {
+    const HEADERS: &'static [&'static str] = &[
+        "created_at",
+        "id",
+        "status",
+        "amount",
+        "description",
+        "message",
+        "account_id",
+        "transfer_account_id",
+        "category_id",
+        "tags",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let attributes = &self.attributes;
+        let relationships = &self.relationships;
+        vec![
+            attributes.created_at.clone(),
+            self.id.clone(),
+            serialized_name(&attributes.status),
+            attributes.amount.value.clone(),
+            attributes.description.clone(),
+            attributes.message.clone().unwrap_or_default(),
+            relationships.account.data.id.clone(),
+            relationships
+                .transfer_account
+                .data
+                .as_ref()
+                .map(|x| x.id.clone())
+                .unwrap_or_default(),
+            relationships
+                .category
+                .data
+                .as_ref()
+                .map(|x| x.id.clone())
+                .unwrap_or_default(),
+            relationships.tags.data.iter().map(|x| &x.id).join(","),
+        ]
+    }
+}
+
+impl Tabular for YnabTransaction {
+    const HEADERS: &'static [&'static str] = &[
+        "date",
+        "id",
+        "amount",
+        "payee",
+        "memo",
+        "account",
+        "category",
+        "cleared",
+        "approved",
+        "deleted",
+        "import_id",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.date.clone(),
+            self.id.clone(),
+            format_milliunits(self.amount),
+            self.payee_name.clone().flatten().unwrap_or_default(),
+            self.memo.clone().flatten().unwrap_or_default(),
+            self.account_name.clone(),
+            self.category_name.clone().flatten().unwrap_or_default(),
+            serialized_name(&self.cleared),
+            self.approved.to_string(),
+            self.deleted.to_string(),
+            self.import_id.clone().flatten().unwrap_or_default(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        cmd::{fixture::Fixture, get::account},
+        frontend::config::Up,
+    };
+
+    #[test]
+    fn up_transaction_cells() -> Result<()> {
+        let transaction = serde_json::from_str::<UpTransaction>(&fs::read_to_string(
+            "test/data/run/up_transactions/2023-12-02T13:44:15+11:00-5ce7c223-0188-4b68-8d19-227a7cc3464d.json",
+        )?)?;
+        assert_eq!(
+            vec![
+                "2023-12-02T13:44:15+11:00",
+                "5ce7c223-0188-4b68-8d19-227a7cc3464d",
+                "SETTLED",
+                "-57.84",
+                "7-Eleven",
+                "",
+                "2be1c9de-7a89-4e8f-8077-f535150b588d",
+                "",
+                "fuel",
+                "",
+            ],
+            transaction.cells()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ynab_transaction_cells() -> Result<()> {
+        let fixture = Fixture::new("ynab_transaction_cells")?;
+        fixture.sync().await?;
+        let transactions = ynab(&fixture.config, YnabArgs { since: None }).await?;
+        let transaction = transactions
+            .iter()
+            .find(|x| x.import_id == Some(Some("9f08959d-51d2-43a8-a45a-154373870094".to_owned())))
+            .wrap_err("missing transaction")?;
+        assert_eq!(
+            vec![
+                transaction.date.clone(),
+                transaction.id.clone(),
+                "10.95".to_owned(),
+                "Z KIDD-SMITH".to_owned(),
+                "pizza".to_owned(),
+                "Spending".to_owned(),
+                String::new(),
+                "cleared".to_owned(),
+                "true".to_owned(),
+                "false".to_owned(),
+                "9f08959d-51d2-43a8-a45a-154373870094".to_owned(),
+            ],
+            transaction.cells()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn joint_accounts_fetched_once() -> Result<()> {
+        let mut fixture = Fixture::new("joint_accounts_fetched_once")?;
+        let mut joint = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/run/up_accounts/2be1c9de-7a89-4e8f-8077-f535150b588d.json",
+        )?)?;
+        joint["id"] = "joint".into();
+        joint["attributes"]["displayName"] = "2Up".into();
+        joint["attributes"]["ownershipType"] = "JOINT".into();
+        fixture.server.add_up_account(&joint)?;
+        let mut transaction = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/up_expense.json",
+        )?)?;
+        transaction["id"] = "joint-expense".into();
+        transaction["relationships"]["account"]["data"]["id"] = "joint".into();
+        fixture.server.add_up_transaction(&transaction)?;
+
+        // Both people see the joint account, only its owner fetches it.
+        fixture.config.up[0].joint_accounts = true;
+        fixture.config.up.push(Up {
+            name: Some("partner".to_owned()),
+            api_token: "up-token".to_owned(),
+            joint_accounts: false,
+        });
+
+        let accounts = account::up(&fixture.config).await?;
+        let transactions = up(
+            &fixture.config,
+            UpArgs {
+                since: None,
+                until: None,
+            },
+        )
+        .await?;
+        assert_eq!(2, accounts.len());
+        assert_eq!(3, transactions.len());
+        assert_eq!(
+            1,
+            transactions
+                .iter()
+                .filter(|x| x.relationships.account.data.id == "joint")
+                .count()
+        );
+        Ok(())
+    }
+}
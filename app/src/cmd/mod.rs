@@ -1,2 +1,8 @@
+pub mod doctor;
+#[cfg(test)]
+pub(crate) mod fixture;
 pub mod get;
+pub mod query;
+pub mod run;
+pub mod store;
 pub mod sync;
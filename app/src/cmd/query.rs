@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{Context, Result};
+use itertools::Itertools;
+use tracing::info;
+
+use crate::{
+    cmd::get,
+    frontend::{
+        cli,
+        output::{self, Tabular},
+        Config, Run,
+    },
+    model::{format_cents, Filter, Record},
+};
+
+pub type Args = cli::query::Args;
+
+/// Prints the Up transactions of the run, or fetched, that match the filter, oldest first.
+pub async fn query(config: &Config, args: Args) -> Result<()> {
+    let filter = args
+        .filter
+        .as_deref()
+        .map(str::parse::<Filter>)
+        .transpose()
+        .wrap_err("invalid filter")?;
+
+    let (accounts, transactions) = if let Some(in_path) = &args.in_path {
+        let run = Run::read(in_path, config.secret()?.as_ref())?;
+        (
+            run.up_accounts.unwrap_or_default(),
+            run.up_transactions.unwrap_or_default(),
+        )
+    } else {
+        let transaction_args = get::transaction::UpArgs {
+            since: args.since,
+            until: args.until,
+        };
+        tokio::try_join!(
+            get::account::up(config),
+            get::transaction::up(config, transaction_args),
+        )?
+    };
+
+    let account_names = accounts
+        .iter()
+        .map(|x| (x.id.clone(), x.attributes.display_name.clone()))
+        .collect::<HashMap<_, _>>();
+    let records = transactions
+        .iter()
+        .map(|x| Record::new(x, &account_names))
+        .filter_ok(|x| filter.as_ref().map_or(true, |filter| filter.matches(x)))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .sorted_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)))
+        .collect::<Vec<_>>();
+    info!(
+        "{} of {} transactions match",
+        records.len(),
+        transactions.len()
+    );
+
+    output::write_to(&args.output, &records)
+}
+
+impl Tabular for Record {
+    const HEADERS: &'static [&'static str] = &[
+        "date", "amount", "payee", "account", "category", "tags", "status", "kind", "id",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.date.to_string(),
+            format_cents(self.amount),
+            self.payee.clone(),
+            self.account.clone(),
+            self.category.clone().unwrap_or_default(),
+            self.tags.join(","),
+            self.status.clone(),
+            self.kind.clone(),
+            self.id.clone(),
+        ]
+    }
+}
@@ -0,0 +1,707 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::eyre::{ensure, eyre, ContextCompat};
+use itertools::Itertools;
+use pretty_assertions::Comparison;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    cmd::get,
+    frontend::{cli, manifest::Manifest, retention, up_export, Config, Run},
+    model::{UpTransaction, YnabTransaction},
+    Result,
+};
+
+pub type InfoArgs = cli::run::info::Args;
+pub type PackArgs = cli::run::pack::Args;
+pub type EncryptArgs = cli::run::encrypt::Args;
+pub type DiffArgs = cli::run::diff::Args;
+pub type PruneArgs = cli::run::prune::Args;
+pub type ImportArgs = cli::run::import::Args;
+
+/// Prints the run's manifest, after checking its entries match it.
+pub fn info(config: &Config, args: &InfoArgs) -> Result<()> {
+    let (manifest, _) = Manifest::open(&args.path, config.secret()?.as_ref())?;
+    info!("run `{}` is valid", args.path.to_string_lossy());
+
+    let unknown = || "unknown".to_owned();
+    let unbounded = || "unbounded".to_owned();
+    println!("format version: {}", manifest.version);
+    println!("tool version:   {}", manifest.tool_version);
+    println!("layout:         {:?}", manifest.layout);
+    println!("encrypted:      {}", manifest.encryption.is_some());
+    println!(
+        "fetched at:     {}",
+        manifest.fetched_at.map_or_else(unknown, |x| x.to_rfc3339())
+    );
+    if let Some(parameters) = &manifest.parameters {
+        println!("profile:        {}", parameters.profile);
+        println!(
+            "budget id:      {}",
+            parameters.budget_id.as_deref().unwrap_or("unset")
+        );
+        println!(
+            "since:          {}",
+            parameters.since.map_or_else(unbounded, |x| x.to_rfc3339())
+        );
+        println!(
+            "until:          {}",
+            parameters.until.map_or_else(unbounded, |x| x.to_rfc3339())
+        );
+    } else {
+        println!("parameters:     unknown");
+    }
+    for (name, component) in &manifest.components {
+        println!(
+            "{name}: {} entries, sha256 {}",
+            component.count, component.checksum
+        );
+    }
+    Ok(())
+}
+
+/// Packs the given runs, or every run of the profile, into archives.
+pub fn pack(config: &Config, args: PackArgs) -> Result<()> {
+    let paths = if args.paths.is_empty() {
+        Run::list(config.runs_dir())?
+    } else {
+        args.paths
+    };
+
+    let secret = config.secret()?;
+    info!("packing {} runs...", paths.len());
+    for path in &paths {
+        let manifest = Run::pack(path, secret.as_ref())?;
+        let count = manifest.components.values().map(|x| x.count).sum::<usize>();
+        info!("packed `{}`, {count} entries", path.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Encrypts the given runs, or every run of the profile, in place.
+pub fn encrypt(config: &Config, args: EncryptArgs) -> Result<()> {
+    let secret = config
+        .secret()?
+        .wrap_err("set a passphrase or key file under `[encryption]` to encrypt runs")?;
+    let paths = if args.paths.is_empty() {
+        Run::list(config.runs_dir())?
+    } else {
+        args.paths
+    };
+
+    info!("encrypting {} runs...", paths.len());
+    for path in &paths {
+        Run::encrypt(path, &secret)?;
+        info!("encrypted and verified `{}`", path.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Removes the runs not kept by the profile's retention policy.
+pub fn prune(config: &Config, args: &PruneArgs) -> Result<()> {
+    if config.retention.is_empty() {
+        return Err(eyre!(
+            "no retention policy, set `keep_last`, `keep_daily` or `keep_monthly` under \
+             `[retention]`"
+        ));
+    }
+
+    let pruned = retention::prune(config.runs_dir(), &config.retention, args.dry_run)?;
+    if args.dry_run {
+        info!("would prune {} runs", pruned.len());
+    } else {
+        info!("pruned {} runs", pruned.len());
+    }
+    Ok(())
+}
+
+/// Writes a new run with the exported Up transactions, and the accounts and YNAB transactions
+/// of the base run or fetched.
+pub async fn import(config: &Config, args: ImportArgs) -> Result<()> {
+    let secret = config.secret()?;
+    let base = args
+        .base
+        .as_ref()
+        .map(|x| Run::read(x, secret.as_ref()))
+        .transpose()?;
+    let mut up_accounts = match &base {
+        Some(base) => base.up_accounts.clone().unwrap_or_default(),
+        None => get::account::up(config).await?,
+    };
+
+    // CSV exports belong to the named account, given an id if Up no longer lists it.
+    let account_id = args.account.as_deref().map(|name| {
+        up_accounts
+            .iter()
+            .find(|x| x.attributes.display_name.trim() == name.trim())
+            .map_or_else(|| up_export::account_id(name), |x| x.id.clone())
+    });
+    let mut up_transactions = Vec::new();
+    for path in &args.paths {
+        up_transactions.extend(up_export::read(path, account_id.as_deref(), args.timezone)?);
+    }
+    let up_transactions = up_transactions
+        .into_iter()
+        .unique_by(|x| x.id.clone())
+        .collect::<Vec<_>>();
+    let up_transactions = drop_known(config, base.as_ref(), up_transactions).await?;
+
+    let listed = up_accounts
+        .iter()
+        .map(|x| x.id.clone())
+        .collect::<HashSet<_>>();
+    let unlisted = up_transactions
+        .iter()
+        .map(|x| x.relationships.account.data.id.clone())
+        .filter(|x| !listed.contains(x))
+        .collect::<BTreeSet<_>>();
+    if let Some(id) = unlisted.first() {
+        let ids = unlisted.iter().join("`, `");
+        ensure!(
+            unlisted.len() == 1,
+            "transactions belong to several accounts up doesn't list: `{ids}`"
+        );
+        let name = args.account.as_deref().wrap_err(format!(
+            "transactions belong to account `{id}` up doesn't list, name it with `--account`"
+        ))?;
+        info!("adding account `{name}` up doesn't list");
+        up_accounts.push(up_export::account(id, name, args.saver, &up_transactions)?);
+    }
+
+    let mut run = Run::in_dir(config.runs_dir());
+    if let Some(base) = base {
+        run.ynab_accounts = base.ynab_accounts;
+        run.ynab_transactions = base.ynab_transactions;
+        run.ynab_budgets = base.ynab_budgets;
+    } else {
+        let since = up_transactions
+            .iter()
+            .filter_map(|x| DateTime::parse_from_rfc3339(&x.attributes.created_at).ok())
+            .min();
+        let (ynab_accounts, ynab_transactions, ynab_budgets) = tokio::try_join!(
+            get::account::ynab(config),
+            get::transaction::ynab(config, get::transaction::YnabArgs { since }),
+            get::budget::ynab(config),
+        )?;
+        run.ynab_accounts = Some(ynab_accounts);
+        run.ynab_transactions = Some(ynab_transactions);
+        run.ynab_budgets = Some(ynab_budgets);
+    }
+
+    let count = up_transactions.len();
+    run.up_accounts = Some(up_accounts);
+    run.up_transactions = Some(up_transactions);
+    run.save(config, Utc::now(), None, None)?;
+    info!(
+        "imported {count} up transactions into `{}`",
+        run.path.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// Drops transactions the base run, or the API when there's no base, has under another id. CSV
+/// rows get ids of their own, so they're matched by account, time and amount instead, and would
+/// otherwise be synced twice.
+async fn drop_known(
+    config: &Config,
+    base: Option<&Run>,
+    transactions: Vec<UpTransaction>,
+) -> Result<Vec<UpTransaction>> {
+    let times = transactions
+        .iter()
+        .filter_map(|x| DateTime::parse_from_rfc3339(&x.attributes.created_at).ok())
+        .collect::<Vec<_>>();
+    let (Some(since), Some(until)) = (times.iter().min(), times.iter().max()) else {
+        return Ok(transactions);
+    };
+    let known = if let Some(base) = base {
+        base.up_transactions.clone().unwrap_or_default()
+    } else {
+        let args = get::transaction::UpArgs {
+            since: Some(*since),
+            until: Some(*until + Duration::seconds(1)),
+        };
+        get::transaction::up(config, args).await?
+    };
+    let known = known
+        .iter()
+        .filter_map(|x| Some((key(x)?, x.id.as_str())))
+        .collect::<HashMap<_, _>>();
+
+    let (dropped, kept) = transactions.into_iter().partition::<Vec<_>, _>(|x| {
+        key(x)
+            .and_then(|key| known.get(&key))
+            .is_some_and(|id| *id != x.id)
+    });
+    if !dropped.is_empty() {
+        info!(
+            "dropping {} transactions already fetched from up",
+            dropped.len()
+        );
+    }
+    Ok(kept)
+}
+
+/// Identifies a transaction by its account, time and amount.
+fn key(transaction: &UpTransaction) -> Option<(String, i64, i64)> {
+    let created_at = DateTime::parse_from_rfc3339(&transaction.attributes.created_at).ok()?;
+    Some((
+        transaction.relationships.account.data.id.clone(),
+        created_at.timestamp(),
+        i64::from(transaction.attributes.amount.value_in_base_units),
+    ))
+}
+
+/// Prints the transactions and balances that changed between two runs.
+pub fn diff(config: &Config, args: &DiffArgs) -> Result<()> {
+    let secret = config.secret()?;
+    let before = Run::read(&args.before, secret.as_ref())?;
+    let after = Run::read(&args.after, secret.as_ref())?;
+
+    let up = changes(
+        before.up_transactions.as_deref().unwrap_or_default(),
+        after.up_transactions.as_deref().unwrap_or_default(),
+        |x| x.id.clone(),
+    )?;
+    print_changes("up transactions", &up, describe_up)?;
+
+    let ynab = changes(
+        before.ynab_transactions.as_deref().unwrap_or_default(),
+        after.ynab_transactions.as_deref().unwrap_or_default(),
+        |x| x.id.clone(),
+    )?;
+    print_changes("ynab transactions", &ynab, describe_ynab)?;
+
+    let balances = balance_changes(&balances(&before), &balances(&after));
+    println!("balances: {} changed", balances.len());
+    for change in &balances {
+        let units = |x: Option<i64>| x.map_or_else(|| "none".to_owned(), get::format_milliunits);
+        println!(
+            "  {}: {} -> {}",
+            change.account,
+            units(change.before),
+            units(change.after)
+        );
+    }
+    Ok(())
+}
+
+/// Entries added, changed and removed between two runs, matched by id and ordered by it.
+#[derive(Debug)]
+pub struct Changes<'a, T> {
+    pub added: Vec<&'a T>,
+    pub changed: Vec<(&'a T, &'a T)>,
+    pub removed: Vec<&'a T>,
+}
+
+/// An account balance in milliunits, missing when the account isn't in the run.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub account: String,
+    pub before: Option<i64>,
+    pub after: Option<i64>,
+}
+
+/// Matches entries by id, entries are changed when any field differs.
+pub fn changes<'a, T: Serialize, F: Fn(&T) -> String>(
+    before: &'a [T],
+    after: &'a [T],
+    id: F,
+) -> Result<Changes<'a, T>> {
+    let before = before
+        .iter()
+        .map(|x| (id(x), x))
+        .collect::<BTreeMap<_, _>>();
+    let after = after.iter().map(|x| (id(x), x)).collect::<BTreeMap<_, _>>();
+
+    let mut changes = Changes {
+        added: Vec::new(),
+        changed: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (id, a) in &after {
+        match before.get(id) {
+            None => changes.added.push(*a),
+            Some(b) if serde_json::to_value(b)? != serde_json::to_value(a)? => {
+                changes.changed.push((*b, *a));
+            }
+            Some(_) => {}
+        }
+    }
+    changes.removed = before
+        .iter()
+        .filter(|(id, _)| !after.contains_key(*id))
+        .map(|(_, x)| *x)
+        .collect();
+    Ok(changes)
+}
+
+/// Renders the fields that differ, by their dotted path.
+pub fn field_diff<T: Serialize>(before: &T, after: &T) -> Result<String> {
+    let mut before_fields = BTreeMap::new();
+    let mut after_fields = BTreeMap::new();
+    flatten("", serde_json::to_value(before)?, &mut before_fields);
+    flatten("", serde_json::to_value(after)?, &mut after_fields);
+
+    let keys = before_fields
+        .keys()
+        .chain(after_fields.keys())
+        .filter(|x| before_fields.get(*x) != after_fields.get(*x))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    before_fields.retain(|k, _| keys.contains(k));
+    after_fields.retain(|k, _| keys.contains(k));
+    Ok(Comparison::new(&Fields(before_fields), &Fields(after_fields)).to_string())
+}
+
+/// Changed balances of Up and YNAB accounts, matched by id.
+#[must_use]
+pub fn balance_changes(
+    before: &BTreeMap<String, (String, i64)>,
+    after: &BTreeMap<String, (String, i64)>,
+) -> Vec<BalanceChange> {
+    before
+        .keys()
+        .chain(after.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|id| {
+            let (b, a) = (before.get(id), after.get(id));
+            let account = a.or(b).map(|(name, _)| name.clone())?;
+            let change = BalanceChange {
+                account,
+                before: b.map(|(_, x)| *x),
+                after: a.map(|(_, x)| *x),
+            };
+            (change.before != change.after).then_some(change)
+        })
+        .collect()
+}
+
+/// Balances by account id, with the account's display name.
+fn balances(run: &Run) -> BTreeMap<String, (String, i64)> {
+    let up = run.up_accounts.iter().flatten().map(|x| {
+        let name = format!("up `{}`", x.attributes.display_name);
+        (x.id.clone(), (name, x.ynab_balance()))
+    });
+    let ynab = run.ynab_accounts.iter().flatten().map(|x| {
+        let name = format!("ynab `{}`", x.name);
+        (x.id.to_string(), (name, x.balance))
+    });
+    up.chain(ynab).collect()
+}
+
+fn print_changes<T: Serialize, F: Fn(&T) -> String>(
+    name: &str,
+    changes: &Changes<T>,
+    describe: F,
+) -> Result<()> {
+    println!(
+        "{name}: {} added, {} changed, {} removed",
+        changes.added.len(),
+        changes.changed.len(),
+        changes.removed.len()
+    );
+    for x in &changes.added {
+        println!("+ {}", describe(x));
+    }
+    for x in &changes.removed {
+        println!("- {}", describe(x));
+    }
+    for (before, after) in &changes.changed {
+        println!("~ {}", describe(after));
+        println!("{}", field_diff(before, after)?);
+    }
+    Ok(())
+}
+
+fn describe_up(x: &UpTransaction) -> String {
+    format!(
+        "{} {} `{}` {} {}",
+        x.attributes.created_at,
+        x.id,
+        x.attributes.description,
+        x.attributes.amount.value,
+        x.attributes.amount.currency_code
+    )
+}
+
+fn describe_ynab(x: &YnabTransaction) -> String {
+    format!(
+        "{} {} `{}` {}",
+        x.date,
+        x.id,
+        x.payee_name.clone().flatten().unwrap_or_default(),
+        get::format_milliunits(x.amount)
+    )
+}
+
+/// Nested objects are flattened into dotted paths, other values are kept as is.
+fn flatten(path: &str, value: Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let path = if path.is_empty() {
+                    k
+                } else {
+                    format!("{path}.{k}")
+                };
+                flatten(&path, v, fields);
+            }
+        }
+        value => {
+            fields.insert(path.to_owned(), value);
+        }
+    }
+}
+
+/// Debug formats fields as `path: json`, for readable comparisons.
+struct Fields(BTreeMap<String, Value>);
+
+impl fmt::Debug for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Raw<'a>(&'a str);
+        impl fmt::Debug for Raw<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+
+        let entries = self
+            .0
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.to_string()))
+            .collect::<Vec<_>>();
+        f.debug_map()
+            .entries(entries.iter().map(|(k, v)| (Raw(k), Raw(v))))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use indoc::indoc;
+    use serde_json::json;
+
+    use super::*;
+    use crate::cmd::{fixture::Fixture, sync};
+
+    fn import_args(export: &Path, account: &str, base: Option<PathBuf>) -> ImportArgs {
+        ImportArgs {
+            paths: vec![export.to_path_buf()],
+            account: Some(account.to_owned()),
+            saver: false,
+            timezone: chrono_tz::Tz::Australia__Sydney,
+            base,
+        }
+    }
+
+    #[tokio::test]
+    async fn import_csv_export() -> Result<()> {
+        let fixture = Fixture::new("import_csv_export")?;
+        let export = fixture.runs_path.with_extension("csv");
+        fs::write(
+            &export,
+            indoc! {r#"
+                Time,Payee,Description,Category,Tags,Subtotal (AUD),Settled Date
+                2023-11-02 09:15:00,Bakery,,Groceries,,-8.40,2023-11-03
+                2023-11-05 12:00:00,Employer,Salary,,,"1,000.00",2023-11-05
+            "#},
+        )?;
+
+        import(&fixture.config, import_args(&export, "Spending", None)).await?;
+        let imported = Run::list(fixture.config.runs_dir())?;
+        let run = Run::read(&imported[0], None)?;
+        let up_transactions = run.up_transactions.unwrap_or_default();
+        assert_eq!(2, up_transactions.len());
+        assert!(up_transactions
+            .iter()
+            .all(|x| x.relationships.account.data.id == "2be1c9de-7a89-4e8f-8077-f535150b588d"));
+
+        // The run syncs like a fetched one.
+        let sync_args = sync::Args {
+            since: None,
+            until: None,
+            in_path: Some(imported[0].clone()),
+            dry_run: false,
+            create_missing_accounts: false,
+            resume: None,
+        };
+        sync::sync(&fixture.config, sync_args).await?;
+        let ids = up_transactions
+            .iter()
+            .map(|x| x.id.clone())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(ids, fixture.import_ids());
+
+        // Accounts Up no longer lists are added to the run.
+        let base = Some(imported[0].clone());
+        import(&fixture.config, import_args(&export, "Old Saver", base)).await?;
+        let path = Run::list(fixture.config.runs_dir())?
+            .into_iter()
+            .find(|x| *x != imported[0])
+            .wrap_err("missing run")?;
+        let run = Run::read(path, None)?;
+        let id = up_export::account_id("Old Saver");
+        let account = run
+            .up_accounts
+            .unwrap_or_default()
+            .into_iter()
+            .find(|x| x.id == id)
+            .wrap_err("missing account")?;
+        assert_eq!(99_160, account.attributes.balance.value_in_base_units);
+        assert!(run.up_transactions.unwrap_or_default().iter().all(|x| x
+            .relationships
+            .account
+            .data
+            .id
+            == id));
+
+        fs::remove_file(export)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_drops_known_transactions() -> Result<()> {
+        let fixture = Fixture::new("import_drops_known_transactions")?;
+        let export = fixture.runs_path.with_extension("csv");
+        // The fuel purchase is also returned by the API.
+        fs::write(
+            &export,
+            indoc! {"
+                Time,Payee,Description,Category,Tags,Subtotal (AUD),Settled Date
+                2023-12-02 13:44:15,7-Eleven,,Fuel,,-57.84,2023-12-04
+                2023-12-03 09:15:00,Bakery,,Groceries,,-8.40,2023-12-04
+            "},
+        )?;
+        let descriptions = |path: &Path| {
+            Ok::<_, crate::Error>(
+                Run::read(path, None)?
+                    .up_transactions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|x| x.attributes.description.clone())
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        import(&fixture.config, import_args(&export, "Spending", None)).await?;
+        let imported = Run::list(fixture.config.runs_dir())?;
+        assert_eq!(vec!["Bakery"], descriptions(&imported[0])?);
+
+        // A base run's transactions are dropped too.
+        let base = Run::fetch(&fixture.config, None, None).await?;
+        import(
+            &fixture.config,
+            import_args(&export, "Spending", Some(base.path.clone())),
+        )
+        .await?;
+        let path = Run::list(fixture.config.runs_dir())?
+            .into_iter()
+            .find(|x| *x != imported[0] && *x != base.path)
+            .wrap_err("missing run")?;
+        assert_eq!(vec!["Bakery"], descriptions(&path)?);
+
+        fs::remove_file(export)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_drops_known_transactions_across_daylight_saving() -> Result<()> {
+        let fixture = Fixture::new("import_drops_known_transactions_across_daylight_saving")?;
+        // A purchase from before daylight saving started, at +10:00 rather than the fixture's
+        // +11:00.
+        let mut winter =
+            serde_json::from_str::<Value>(&fs::read_to_string("test/data/up_expense.json")?)?;
+        winter["id"] = "0d6c1e5b-2f3a-4c8d-9e7f-1a2b3c4d5e6f".into();
+        winter["attributes"]["createdAt"] = "2023-09-30T18:30:00+10:00".into();
+        fixture.server.add_up_transaction(&winter)?;
+
+        let export = fixture.runs_path.with_extension("csv");
+        fs::write(
+            &export,
+            indoc! {"
+                Time,Payee,Description,Category,Tags,Subtotal (AUD),Settled Date
+                2023-09-30 18:30:00,7-Eleven,,Fuel,,-57.84,2023-10-02
+                2023-10-14 09:15:00,Bakery,,Groceries,,-8.40,2023-10-16
+                2023-12-02 13:44:15,7-Eleven,,Fuel,,-57.84,2023-12-04
+            "},
+        )?;
+
+        import(&fixture.config, import_args(&export, "Spending", None)).await?;
+        let imported = Run::list(fixture.config.runs_dir())?;
+        let descriptions = Run::read(&imported[0], None)?
+            .up_transactions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x.attributes.description.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Bakery"], descriptions);
+
+        fs::remove_file(export)?;
+        Ok(())
+    }
+
+    #[test]
+    fn changes_by_id() -> Result<()> {
+        let before = vec![
+            json!({ "id": "a", "attributes": { "status": "HELD", "amount": 1 } }),
+            json!({ "id": "b", "attributes": { "status": "SETTLED", "amount": 2 } }),
+        ];
+        let after = vec![
+            json!({ "id": "c", "attributes": { "status": "HELD", "amount": 3 } }),
+            json!({ "id": "a", "attributes": { "status": "SETTLED", "amount": 1 } }),
+        ];
+
+        let changes = changes(&before, &after, |x| x["id"].to_string())?;
+        assert_eq!(vec![&after[0]], changes.added);
+        assert_eq!(vec![(&before[0], &after[1])], changes.changed);
+        assert_eq!(vec![&before[1]], changes.removed);
+
+        let diff = field_diff(&before[0], &after[1])?;
+        assert!(diff.contains("attributes.status"));
+        assert!(!diff.contains("attributes.amount"));
+        Ok(())
+    }
+
+    #[test]
+    fn changed_balances() {
+        let before = BTreeMap::from([
+            ("a".to_owned(), ("up `A`".to_owned(), 1000)),
+            ("b".to_owned(), ("up `B`".to_owned(), 2000)),
+        ]);
+        let after = BTreeMap::from([
+            ("a".to_owned(), ("up `A`".to_owned(), 1000)),
+            ("b".to_owned(), ("up `B`".to_owned(), -2500)),
+            ("c".to_owned(), ("ynab `C`".to_owned(), 0)),
+        ]);
+
+        assert_eq!(
+            vec![
+                BalanceChange {
+                    account: "up `B`".to_owned(),
+                    before: Some(2000),
+                    after: Some(-2500),
+                },
+                BalanceChange {
+                    account: "ynab `C`".to_owned(),
+                    before: None,
+                    after: Some(0),
+                },
+            ],
+            balance_changes(&before, &after)
+        );
+        assert_eq!("-2.50", get::format_milliunits(-2500));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{ContextCompat, Result};
+use futures::TryStreamExt;
+use itertools::Itertools;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    frontend::{cli, store::Store, Config, Run},
+    model::{YnabCategory, YnabTransaction},
+};
+
+pub type ImportArgs = cli::store::import::Args;
+pub type ExportArgs = cli::store::export::Args;
+
+/// Prints the store's location and the number of entries in each table.
+pub fn info(config: &Config) -> Result<()> {
+    let path = config.store_path();
+    let store = Store::open(&path, config.secret()?.as_ref())?;
+    println!("path:      {}", path.to_string_lossy());
+    println!("encrypted: {}", store.is_encrypted());
+    if store.is_encrypted() {
+        // Only the entries are sealed, the rest is needed to query them.
+        println!("           ids, accounts, categories, statuses, dates and tags are plain text");
+    }
+    for (table, count) in store.counts()? {
+        println!("{table}: {count} entries");
+    }
+    Ok(())
+}
+
+/// Imports the given runs, or every run of the profile, oldest first.
+pub fn import(config: &Config, args: ImportArgs) -> Result<()> {
+    let paths = if args.paths.is_empty() {
+        Run::list(config.runs_dir())?
+    } else {
+        args.paths
+    };
+
+    let budget_id = budget_id(config)?;
+    let secret = config.secret()?;
+    let mut store = Store::open(config.store_path(), secret.as_ref())?;
+    info!("importing {} runs...", paths.len());
+    for path in &paths {
+        let run = Run::read(path, secret.as_ref())?;
+        store.import_run(&run, budget_id, fetched_at(&run))?;
+        info!("imported `{}`", path.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Writes the store's entries for the profile's budget as a new run.
+pub fn export(config: &Config, args: &ExportArgs) -> Result<()> {
+    let store = Store::open(config.store_path(), config.secret()?.as_ref())?;
+    let mut run = store.export(
+        config.runs_dir(),
+        budget_id(config)?,
+        args.since,
+        args.until,
+    )?;
+    run.save(config, Utc::now(), args.since, args.until)?;
+    info!("exported store to `{}`", run.path.to_string_lossy());
+    Ok(())
+}
+
+/// Imports a sync's run, then refreshes categories and tags.
+pub async fn update(config: &Config, run: &Run, budget_id: Uuid) -> Result<()> {
+    let mut store = Store::open(config.store_path(), config.secret()?.as_ref())?;
+    store.import_run(run, budget_id, fetched_at(run))?;
+
+    info!("fetching categories and tags...");
+    let fetched_at = Utc::now();
+    let up = config.up.first().wrap_err("missing up profile")?;
+    let up_client = config.up_client(up)?;
+    let ynab_client = config.ynab_client()?;
+    let (up_categories, ynab_categories) = tokio::try_join!(
+        up_client.categories().send(),
+        ynab_client
+            .categories()
+            .budget_id(budget_id.to_string())
+            .send(),
+    )?;
+    let ynab_categories = ynab_categories
+        .data
+        .into_iter()
+        .flat_map(|x| x.categories)
+        .map(YnabCategory::new)
+        .collect::<Vec<_>>();
+
+    // Tags belong to each Up user.
+    let mut up_tags = Vec::new();
+    for profile in &config.up {
+        up_tags.extend(
+            config
+                .up_client(profile)?
+                .tags()
+                .send()?
+                .try_collect::<Vec<_>>()
+                .await?,
+        );
+    }
+    let up_tags = up_tags
+        .into_iter()
+        .unique_by(|x| x.id.clone())
+        .collect::<Vec<_>>();
+
+    store.import_categories(
+        budget_id,
+        &up_categories,
+        &up_tags,
+        &ynab_categories,
+        fetched_at,
+    )?;
+    info!("updated store `{}`", config.store_path().to_string_lossy());
+    Ok(())
+}
+
+/// Imports the YNAB transactions a sync created and updated, which its run was fetched before.
+pub fn import_synced(config: &Config, budget_id: Uuid, synced: &[YnabTransaction]) -> Result<()> {
+    let mut store = Store::open(config.store_path(), config.secret()?.as_ref())?;
+    store.import_ynab_transactions(budget_id, synced, Utc::now())
+}
+
+fn budget_id(config: &Config) -> Result<Uuid> {
+    Ok(Uuid::parse_str(
+        config
+            .ynab
+            .budget_id
+            .as_ref()
+            .wrap_err("missing budget id")?,
+    )?)
+}
+
+/// Runs written before manifests recorded it are dated by their directory name.
+fn fetched_at(run: &Run) -> DateTime<Utc> {
+    run.manifest
+        .as_ref()
+        .and_then(|x| x.fetched_at)
+        .or_else(|| {
+            let name = run.path.file_name()?.to_string_lossy();
+            DateTime::parse_from_rfc3339(&name)
+                .ok()
+                .map(|x| x.with_timezone(&Utc))
+        })
+        .unwrap_or_else(Utc::now)
+}
@@ -1,64 +1,206 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
-use color_eyre::eyre::{Context, ContextCompat, Result};
-use fallible_iterator::{FallibleIterator, IteratorExt};
-use tracing::info;
+use chrono::{NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
     api::ynab,
-    frontend::{cli, config::Config, run::Run},
-    model::{Account, Transaction},
+    cmd::{get::transaction, store},
+    frontend::{
+        cli,
+        config::Config,
+        retention::{self, LastSync},
+        run::Run,
+        sync_state::{Phase, Plan, SyncState},
+    },
+    model::{
+        format_cents, Account, NewYnabTransaction, OrphanPolicy, OrphanReason, Transaction,
+        TransferRule, Transfers, UpTransaction, UpdateYnabTransaction, YnabTransaction,
+    },
 };
 
 pub type Args = cli::sync::Args;
 
 pub async fn sync(config: &Config, args: Args) -> Result<()> {
-    let ynab_client = ynab::Client::new(&config.ynab.api_token);
-
-    info!("starting up to ynab sync...");
-    let run = if let Some(in_path) = args.in_path {
-        Run::read(in_path)?
-    } else {
-        Run::fetch(config, args.since, args.until).await?
-    };
-
+    let ynab_client = config.ynab_client()?;
     let budget_id = config
         .ynab
         .budget_id
         .as_ref()
         .map(|x| Uuid::parse_str(x))
         .wrap_err("missing budget id")??;
+    let interrupt = Interrupt::listen();
+
+    info!("starting up to ynab sync...");
+    let (mut run, mut state) = if let Some(resume) = &args.resume {
+        let run = Run::read(resume, config.secret()?.as_ref())?;
+        let state = SyncState::read(&run.path)?.wrap_err(format!(
+            "run `{}` has no sync to resume",
+            resume.to_string_lossy()
+        ))?;
+        if state.budget_id != budget_id {
+            return Err(eyre!(
+                "run was synced to budget `{}`, not `{budget_id}`",
+                state.budget_id
+            ));
+        }
+        if state.is_complete() {
+            info!("sync already complete!");
+            return Ok(());
+        }
+        info!("resuming sync after phase {:?}...", state.phase);
+        (run, state)
+    } else {
+        let run = if let Some(in_path) = &args.in_path {
+            Run::read(in_path, config.secret()?.as_ref())?
+        } else {
+            Run::fetch(config, args.since, args.until).await?
+        };
+        let mut state = SyncState::new(budget_id);
+        if !args.dry_run {
+            state.advance(&run.path, Phase::Fetched)?;
+            // The store is a cache, a failure to update it doesn't fail the sync.
+            if let Err(e) = store::update(config, &run, budget_id).await {
+                warn!("failed to update store: {e:#}");
+            }
+        }
+        (run, state)
+    };
+
+    let mut plan = if state.phase == Phase::Fetched {
+        interrupt.check()?;
+        let plan = plan(config, &ynab_client, budget_id, &mut run, &args).await?;
+        if args.dry_run {
+            info!("dry run, skipping...");
+            return Ok(());
+        }
+        plan.write(&run)?;
+        state.advance(&run.path, Phase::Planned)?;
+        plan
+    } else {
+        Plan::read(&run)?
+    };
+
+    if args.resume.is_some() {
+        skip_existing(config, &mut plan, &state).await?;
+    }
+
+    // Every phase is persisted once complete, so a stopped sync resumes after the last one.
+    let resume_hint = || {
+        format!(
+            "sync stopped, resume with `sync --resume {}`",
+            run.path.to_string_lossy()
+        )
+    };
+    create_transactions(
+        &ynab_client,
+        budget_id,
+        &plan.create,
+        &run,
+        &mut state,
+        &interrupt,
+    )
+    .await
+    .wrap_err_with(resume_hint)?;
+    update_transactions(
+        &ynab_client,
+        budget_id,
+        &plan.update,
+        &run,
+        &mut state,
+        &interrupt,
+    )
+    .await
+    .wrap_err_with(resume_hint)?;
+    let synced = verify_transactions(config, &plan, &run, &mut state)
+        .await
+        .wrap_err_with(resume_hint)?;
+
+    // The run was fetched before the sync, so the store maps the synced transactions from these.
+    if let Err(e) = store::import_synced(config, budget_id, &synced) {
+        warn!("failed to update store: {e:#}");
+    }
+
+    LastSync::write(config.runs_dir(), &run.path)?;
+    // Pruning is housekeeping, a failure doesn't fail the sync.
+    if !config.retention.is_empty()
+        && let Err(e) = retention::prune(config.runs_dir(), &config.retention, false)
+    {
+        warn!("failed to prune runs: {e:#}");
+    }
+
+    info!("done!");
+    Ok(())
+}
+
+/// Works out the transactions to create and update, batched by the configured batch size.
+async fn plan(
+    config: &Config,
+    ynab_client: &ynab::Client,
+    budget_id: Uuid,
+    run: &mut Run,
+    args: &Args,
+) -> Result<Plan> {
     let budget = run
         .ynab_budgets
+        .as_ref()
         .wrap_err("missing ynab budgets")?
-        .into_iter()
+        .iter()
         .find(|x| x.id == budget_id)
-        .wrap_err("failed to find budget with id: `{budget_id}`")?;
+        .wrap_err("failed to find budget with id: `{budget_id}`")?
+        .clone();
+
+    if args.create_missing_accounts {
+        create_missing_accounts(
+            config,
+            ynab_client,
+            &budget_id.to_string(),
+            run,
+            args.dry_run,
+        )
+        .await?;
+    }
 
     let accounts = Account::identify(
-        &run.up_accounts.unwrap_or_default(),
-        &run.ynab_accounts.unwrap_or_default(),
+        run.up_accounts.as_deref().unwrap_or_default(),
+        run.ynab_accounts.as_deref().unwrap_or_default(),
+        &config.accounts,
+    )?;
+
+    let rules = TransferRule::identify(
+        &config.rules,
+        run.ynab_accounts.as_deref().unwrap_or_default(),
     )?;
+    let unmanaged_accounts = rules.iter().map(|x| x.account.clone()).collect::<Vec<_>>();
+
+    // Dry runs and runs given with `--in-path` are only read.
+    let persist = !args.dry_run && args.in_path.is_none();
+    let up_transactions = run.up_transactions.take().unwrap_or_default();
+    let up_transactions =
+        resolve_transfers(config, run, up_transactions, &accounts, &rules, persist).await?;
 
+    // Transactions in YNAB accounts without an Up counterpart aren't synced.
     let ynab_transactions = run
         .ynab_transactions
-        .map(|x| {
-            x.into_iter()
-                .map(|x| x.to_transaction(&budget, &accounts))
-                .collect::<Result<Vec<_>>>()
-        })
-        .transpose()?
-        .unwrap_or_default();
-
-    let up_transactions = run
-        .up_transactions
+        .as_deref()
         .unwrap_or_default()
-        .into_iter()
-        .map(|x| x.to_transaction(&accounts))
-        .transpose_into_fallible()
-        .filter(|x| Ok(x.is_normalized()))
-        .collect::<Vec<_>>()?;
+        .iter()
+        .filter(|x| {
+            accounts
+                .iter()
+                .any(|account| account.ynab_id == x.account_id)
+        })
+        .map(|x| x.to_transaction(&budget, &accounts, &unmanaged_accounts))
+        .collect::<Result<Vec<_>>>()?;
 
     let missing_transactions = find_missing_transactions(&up_transactions, &ynab_transactions);
     let modified_transactions = find_modified_transactions(&up_transactions, &ynab_transactions);
@@ -70,26 +212,7 @@ pub async fn sync(config: &Config, args: Args) -> Result<()> {
             "creating {} missing up transactions in ynab...",
             missing_transactions.len()
         );
-
-        if args.dry_run {
-            info!("dry run, skipping...");
-        } else {
-            let new_ynab_transactions = missing_transactions
-                .into_iter()
-                .map(Transaction::to_new_ynab)
-                .collect::<Result<Vec<_>>>()?;
-
-            // TODO: check equality against returned transactions
-            ynab_client
-                .new_transactions()
-                .budget_id(budget_id)
-                .transactions(new_ynab_transactions)
-                .send()
-                .await
-                .wrap_err("failed to create ynab transactions")?;
-        }
     }
-
     if modified_transactions.is_empty() {
         info!("all up transactions unmodified in ynab!");
     } else {
@@ -97,30 +220,364 @@ pub async fn sync(config: &Config, args: Args) -> Result<()> {
             "updating {} modified up transactions in ynab...",
             modified_transactions.len()
         );
+    }
 
-        if args.dry_run {
-            info!("dry run, skipping...");
-        } else {
-            let updated_ynab_transactions = modified_transactions
-                .into_iter()
-                .map(Transaction::to_update_ynab)
-                .collect::<Result<Vec<_>>>()?;
+    let batch_size = config.sync.batch_size.max(1);
+    let create = missing_transactions
+        .into_iter()
+        .map(Transaction::to_new_ynab)
+        .collect::<Result<Vec<_>>>()?;
+    let update = modified_transactions
+        .into_iter()
+        .map(Transaction::to_update_ynab)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Plan {
+        create: create.chunks(batch_size).map(<[_]>::to_vec).collect(),
+        update: update.chunks(batch_size).map(<[_]>::to_vec).collect(),
+    })
+}
 
-            // TODO: check equality against returned transactions
+/// Drops unsent transactions that exist in YNAB. A batch may have been created without its
+/// response arriving, and sending it again would be rejected as duplicates.
+async fn skip_existing(config: &Config, plan: &mut Plan, state: &SyncState) -> Result<()> {
+    let sent = match state.phase {
+        Phase::Planned => 0,
+        Phase::Created { batches } => batches,
+        _ => return Ok(()),
+    };
+    let existing = existing_import_ids(config, plan.create.iter().flatten()).await?;
+    for batch in plan.create.iter_mut().skip(sent) {
+        batch.retain(|x| !import_id(x).is_some_and(|x| existing.contains(x)));
+    }
+    Ok(())
+}
+
+/// Sends the create batches after the last one sent.
+async fn create_transactions(
+    ynab_client: &ynab::Client,
+    budget_id: Uuid,
+    batches: &[Vec<NewYnabTransaction>],
+    run: &Run,
+    state: &mut SyncState,
+    interrupt: &Interrupt,
+) -> Result<()> {
+    let sent = match state.phase {
+        Phase::Planned => 0,
+        Phase::Created { batches } => batches,
+        _ => return Ok(()),
+    };
+
+    let num_transactions = batches.iter().map(Vec::len).sum::<usize>();
+    let mut created = batches.iter().take(sent).map(Vec::len).sum::<usize>();
+    for (i, batch) in batches.iter().enumerate().skip(sent) {
+        interrupt.check()?;
+        if !batch.is_empty() {
             ynab_client
-                .update_transactions()
+                .new_transactions()
                 .budget_id(budget_id)
-                .transactions(updated_ynab_transactions)
+                .transactions(batch.clone())
                 .send()
                 .await
-                .wrap_err("failed to update ynab transactions")?;
+                .wrap_err(format!(
+                    "failed to create ynab transactions, created {created} of {num_transactions}"
+                ))?;
         }
+        created += batch.len();
+        state.advance(&run.path, Phase::Created { batches: i + 1 })?;
+        info!("created {created} of {num_transactions} transactions");
+    }
+    if state.phase == Phase::Planned {
+        state.advance(&run.path, Phase::Created { batches: 0 })?;
     }
+    Ok(())
+}
 
-    info!("done!");
+/// Sends the update batches after the last one sent. Updates set fields to the planned values,
+/// so sending a batch again is harmless.
+async fn update_transactions(
+    ynab_client: &ynab::Client,
+    budget_id: Uuid,
+    batches: &[Vec<UpdateYnabTransaction>],
+    run: &Run,
+    state: &mut SyncState,
+    interrupt: &Interrupt,
+) -> Result<()> {
+    let sent = match state.phase {
+        Phase::Created { .. } => 0,
+        Phase::Updated { batches } => batches,
+        _ => return Ok(()),
+    };
+
+    let num_transactions = batches.iter().map(Vec::len).sum::<usize>();
+    let mut updated = batches.iter().take(sent).map(Vec::len).sum::<usize>();
+    for (i, batch) in batches.iter().enumerate().skip(sent) {
+        interrupt.check()?;
+        ynab_client
+            .update_transactions()
+            .budget_id(budget_id)
+            .transactions(batch.clone())
+            .send()
+            .await
+            .wrap_err(format!(
+                "failed to update ynab transactions, updated {updated} of {num_transactions}"
+            ))?;
+        updated += batch.len();
+        state.advance(&run.path, Phase::Updated { batches: i + 1 })?;
+        info!("updated {updated} of {num_transactions} transactions");
+    }
+    if matches!(state.phase, Phase::Created { .. }) {
+        state.advance(&run.path, Phase::Updated { batches: 0 })?;
+    }
     Ok(())
 }
 
+/// Checks every created transaction is in YNAB, returning the created and updated transactions.
+async fn verify_transactions(
+    config: &Config,
+    plan: &Plan,
+    run: &Run,
+    state: &mut SyncState,
+) -> Result<Vec<YnabTransaction>> {
+    let import_ids = plan
+        .create
+        .iter()
+        .flatten()
+        .filter_map(import_id)
+        .collect::<HashSet<_>>();
+    let update_ids = plan
+        .update
+        .iter()
+        .flatten()
+        .filter_map(|x| x.id.as_deref())
+        .collect::<HashSet<_>>();
+    let dates = plan
+        .create
+        .iter()
+        .flatten()
+        .map(|x| x.date.as_deref())
+        .chain(plan.update.iter().flatten().map(|x| x.date.as_deref()));
+    let synced = transactions_since(config, dates)
+        .await?
+        .into_iter()
+        .filter(|x| {
+            x.import_id
+                .as_ref()
+                .and_then(Option::as_deref)
+                .is_some_and(|x| import_ids.contains(x))
+                || update_ids.contains(x.id.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    if !import_ids.is_empty() {
+        let existing = synced
+            .iter()
+            .filter_map(|x| x.import_id.as_ref()?.as_deref())
+            .collect::<HashSet<_>>();
+        let missing = import_ids
+            .iter()
+            .filter(|x| !existing.contains(**x))
+            .count();
+        if missing > 0 {
+            return Err(eyre!(
+                "{missing} of {} created transactions are missing from ynab",
+                import_ids.len()
+            ));
+        }
+        info!("verified {} created transactions", import_ids.len());
+    }
+    state.advance(&run.path, Phase::Verified)?;
+    Ok(synced)
+}
+
+/// Returns the import ids in YNAB, fetching transactions since the earliest of the given ones.
+async fn existing_import_ids<'a>(
+    config: &Config,
+    transactions: impl Iterator<Item = &'a NewYnabTransaction> + Clone,
+) -> Result<HashSet<String>> {
+    Ok(
+        transactions_since(config, transactions.map(|x| x.date.as_deref()))
+            .await?
+            .into_iter()
+            .filter_map(|x| x.import_id.clone().flatten())
+            .collect(),
+    )
+}
+
+/// Returns the YNAB transactions since the earliest of the given dates, none without dates.
+async fn transactions_since<'a>(
+    config: &Config,
+    dates: impl Iterator<Item = Option<&'a str>> + Clone,
+) -> Result<Vec<YnabTransaction>> {
+    if dates.clone().next().is_none() {
+        return Ok(Vec::new());
+    }
+    let since = dates
+        .flatten()
+        .filter_map(|x| NaiveDate::parse_from_str(x, "%Y-%m-%d").ok())
+        .min()
+        .map(|x| x.and_time(NaiveTime::MIN).and_utc().fixed_offset());
+    transaction::ynab(config, transaction::YnabArgs { since }).await
+}
+
+fn import_id(transaction: &NewYnabTransaction) -> Option<&str> {
+    transaction.import_id.as_ref()?.as_deref()
+}
+
+/// Stops the sync before the next batch on the first Ctrl-C, the second exits immediately.
+struct Interrupt {
+    interrupted: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Interrupt {
+    fn listen() -> Self {
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&interrupted);
+        let handle = tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if flag.swap(true, Ordering::SeqCst) {
+                    std::process::exit(130);
+                }
+                warn!("interrupted, stopping after the current batch, press ctrl-c again to exit");
+            }
+        });
+        Self {
+            interrupted,
+            handle,
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.interrupted.load(Ordering::SeqCst) {
+            return Err(eyre!("interrupted"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Creates YNAB accounts for unmatched Up accounts, adding each to the run as it's created.
+async fn create_missing_accounts(
+    config: &Config,
+    ynab_client: &ynab::Client,
+    budget_id: &str,
+    run: &mut Run,
+    dry_run: bool,
+) -> Result<()> {
+    let ynab_accounts = run.ynab_accounts.as_deref().unwrap_or_default();
+    let up_transactions = run.up_transactions.as_deref().unwrap_or_default();
+    let missing_accounts = run
+        .up_accounts
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter(|x| x.find_ynab(ynab_accounts, &config.accounts).is_none())
+        // The account's synced transactions make up the rest of its current balance.
+        .map(|x| {
+            (
+                x.ynab_name(&config.accounts).to_owned(),
+                x.ynab_kind(),
+                x.ynab_starting_balance(up_transactions),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if missing_accounts.is_empty() {
+        info!("all up accounts exist in ynab!");
+        return Ok(());
+    }
+
+    for (name, kind, balance) in missing_accounts {
+        info!(
+            "creating ynab account `{name}` with starting balance {}...",
+            format_cents(balance / 10)
+        );
+        if dry_run {
+            info!("dry run, skipping...");
+            continue;
+        }
+
+        let account = ynab_client
+            .new_account()
+            .budget_id(budget_id)
+            .name(&name)
+            .kind(kind)
+            .balance(balance)
+            .send()
+            .await
+            .wrap_err(format!("failed to create ynab account `{name}`"))?;
+        // Written straight away, so a resumed sync doesn't create it again.
+        run.write_ynab_accounts(std::slice::from_ref(&account))?;
+        run.refresh_manifest()?;
+        run.ynab_accounts.get_or_insert_with(Vec::new).push(account);
+    }
+    Ok(())
+}
+
+/// Pairs transfers and applies the orphan policy, widening the fetch window if required. Fetched
+/// counterparts are only written to the run when `persist` is set.
+async fn resolve_transfers(
+    config: &Config,
+    run: &mut Run,
+    mut up_transactions: Vec<UpTransaction>,
+    accounts: &[Account],
+    rules: &[TransferRule],
+    persist: bool,
+) -> Result<Vec<Transaction>> {
+    let policy = config.sync.orphan_transfers;
+    let mut transfers = Transfers::pair(&up_transactions, accounts, rules)?;
+
+    let orphans = transfers
+        .orphans
+        .iter()
+        .filter(|x| x.reason == OrphanReason::MissingCounterpart)
+        .collect::<Vec<_>>();
+    if policy == OrphanPolicy::Widen && !orphans.is_empty() {
+        info!(
+            "widening fetch window for {} orphaned transfers...",
+            orphans.len()
+        );
+
+        let mut ids = up_transactions
+            .iter()
+            .map(|x| x.id.clone())
+            .collect::<HashSet<_>>();
+        let mut counterparts = Vec::new();
+        for orphan in orphans {
+            let Some((since, until)) = orphan.window() else {
+                continue;
+            };
+            let args = transaction::UpArgs {
+                since: Some(since),
+                until: Some(until),
+            };
+            counterparts.extend(
+                transaction::up(config, args)
+                    .await?
+                    .into_iter()
+                    .filter(|x| orphan.is_counterpart(x) && ids.insert(x.id.clone())),
+            );
+        }
+
+        info!("found {} transfer counterparts", counterparts.len());
+        if !counterparts.is_empty() {
+            if persist {
+                run.write_up_transactions(&counterparts)?;
+                run.refresh_manifest()?;
+            }
+            up_transactions.extend(counterparts);
+            transfers = Transfers::pair(&up_transactions, accounts, rules)?;
+        }
+    }
+
+    transfers.resolve(policy, accounts, rules)
+}
+
 fn find_missing_transactions<'a>(
     source_transactions: &'a [Transaction],
     remote_transactions: &'a [Transaction],
@@ -169,3 +626,358 @@ fn find_modified_transactions<'a>(
 
     not_eq_transactions
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::BTreeSet,
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use chrono::{DateTime, FixedOffset};
+    use color_eyre::eyre::ContextCompat;
+    use mock_server::{Failure, Method, Service, StatusCode};
+    use uuid::Uuid;
+
+    use crate::{
+        api::{self, rate_limit::RateLimiter},
+        cmd::fixture::{Fixture, BUDGET_ID},
+        frontend::{
+            retention::LastSync,
+            sync_state::{Phase, SyncState},
+            Run,
+        },
+        Error, Result,
+    };
+
+    fn up_ids() -> BTreeSet<String> {
+        BTreeSet::from([
+            "5ce7c223-0188-4b68-8d19-227a7cc3464d".to_owned(),
+            "9f08959d-51d2-43a8-a45a-154373870094".to_owned(),
+        ])
+    }
+
+    #[tokio::test]
+    async fn sync_creates_missing_transactions() -> Result<()> {
+        let fixture = Fixture::new("sync_creates_missing_transactions")?;
+        fixture.server.set_max_page_size(1);
+
+        fixture.sync().await?;
+        assert_eq!(up_ids(), fixture.import_ids());
+        assert_eq!(
+            2,
+            fixture.num_requests(Service::Up, &Method::GET, "/transactions")
+        );
+
+        // Created transactions are fetched from YNAB, so a second sync creates nothing.
+        fixture.sync().await?;
+        assert_eq!(2, fixture.server.ynab_transactions().len());
+        assert_eq!(
+            1,
+            fixture.num_requests(Service::Ynab, &Method::POST, "/transactions")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_prunes_runs() -> Result<()> {
+        let fixture = Fixture::with_config(
+            "sync_prunes_runs",
+            "
+            [retention]
+            keep_last = 1
+            ",
+        )?;
+
+        fixture.sync().await?;
+        fixture.sync().await?;
+        let runs = Run::list(fixture.config.runs_dir())?;
+        let last_sync = LastSync::read(fixture.config.runs_dir())?.wrap_err("missing last sync")?;
+        assert_eq!(1, runs.len());
+        assert_eq!(fs::canonicalize(&runs[0])?, last_sync.run);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_retries_rate_limited() -> Result<()> {
+        let fixture = Fixture::new("sync_retries_rate_limited")?;
+        fixture.server.fail_next(
+            Service::Ynab,
+            Failure::new(StatusCode::TOO_MANY_REQUESTS)
+                .retry_after(0)
+                .on(Method::POST),
+        );
+
+        fixture.sync().await?;
+        assert_eq!(up_ids(), fixture.import_ids());
+        assert_eq!(
+            2,
+            fixture.num_requests(Service::Ynab, &Method::POST, "/transactions")
+        );
+
+        // Retries count against the client-side rate limit too.
+        let policy = fixture.config.ynab.rate_limit.clone();
+        let max_requests = policy.max_requests;
+        let limiter = RateLimiter::new(
+            api::Service::Ynab,
+            policy,
+            fixture.config.ynab_requests_path(),
+        );
+        let num_requests = fixture
+            .server
+            .requests()
+            .iter()
+            .filter(|x| x.service == Service::Ynab)
+            .count();
+        assert_eq!(max_requests - num_requests, limiter.remaining()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_resumes_after_server_error() -> Result<()> {
+        let fixture = Fixture::new("sync_resumes_after_server_error")?;
+        fixture.server.fail_next(
+            Service::Ynab,
+            Failure::new(StatusCode::SERVICE_UNAVAILABLE).on(Method::POST),
+        );
+
+        // Creating transactions isn't idempotent, so it isn't retried after a server error.
+        let e = fixture.sync().await.err();
+        assert!(matches!(
+            e.as_ref()
+                .and_then(|e| e.chain().find_map(|x| x.downcast_ref::<api::Error>())),
+            Some(api::Error::Server { .. })
+        ));
+        assert!(fixture.import_ids().is_empty());
+
+        fixture.sync().await?;
+        assert_eq!(up_ids(), fixture.import_ids());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_resumes_from_last_phase() -> Result<()> {
+        let mut fixture = Fixture::new("sync_resumes_from_last_phase")?;
+        fixture.config.sync.batch_size = 1;
+        fixture.server.fail_next(
+            Service::Ynab,
+            Failure::new(StatusCode::SERVICE_UNAVAILABLE).on(Method::POST),
+        );
+
+        assert!(fixture.sync().await.is_err());
+        let run = Run::list(fixture.config.runs_dir())?
+            .pop()
+            .wrap_err("missing run")?;
+        let phase = |run| Ok::<_, Error>(SyncState::read(run)?.map(|x| x.phase));
+        assert_eq!(Some(Phase::Planned), phase(&run)?);
+
+        // The run isn't fetched or planned again.
+        fixture.resume(&run).await?;
+        assert_eq!(up_ids(), fixture.import_ids());
+        assert_eq!(Some(Phase::Verified), phase(&run)?);
+        assert_eq!(
+            1,
+            fixture.num_requests(Service::Up, &Method::GET, "/transactions")
+        );
+        assert_eq!(
+            3,
+            fixture.num_requests(Service::Ynab, &Method::POST, "/transactions")
+        );
+
+        // Transactions created without the response arriving aren't sent again.
+        let mut state = SyncState::read(&run)?.wrap_err("missing sync state")?;
+        state.advance(&run, Phase::Created { batches: 1 })?;
+        fixture.resume(&run).await?;
+        assert_eq!(2, fixture.server.ynab_transactions().len());
+        assert_eq!(
+            3,
+            fixture.num_requests(Service::Ynab, &Method::POST, "/transactions")
+        );
+
+        // A finished sync has nothing to resume.
+        fixture.resume(&run).await?;
+        assert_eq!(
+            3,
+            fixture.num_requests(Service::Ynab, &Method::POST, "/transactions")
+        );
+        Ok(())
+    }
+
+    /// Adds an Up saver account with a balance of $500, which has no YNAB account.
+    fn add_saver(fixture: &Fixture) -> Result<()> {
+        let mut saver = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/run/up_accounts/2be1c9de-7a89-4e8f-8077-f535150b588d.json",
+        )?)?;
+        saver["id"] = "saver".into();
+        saver["attributes"]["displayName"] = "Rainy Day".into();
+        saver["attributes"]["accountType"] = "SAVER".into();
+        saver["attributes"]["balance"] = serde_json::json!({
+            "currencyCode": "AUD",
+            "value": "500.00",
+            "valueInBaseUnits": 50000,
+        });
+        fixture.server.add_up_account(&saver)
+    }
+
+    #[tokio::test]
+    async fn sync_creates_missing_accounts() -> Result<()> {
+        let fixture = Fixture::new("sync_creates_missing_accounts")?;
+        add_saver(&fixture)?;
+        let expense = fs::read_to_string("test/data/up_expense.json")?;
+        for (id, value, cents) in [("interest", "100.00", 10000), ("deposit", "50.00", 5000)] {
+            let mut transaction = serde_json::from_str::<serde_json::Value>(&expense)?;
+            transaction["id"] = id.into();
+            transaction["attributes"]["amount"]["value"] = value.into();
+            transaction["attributes"]["amount"]["valueInBaseUnits"] = cents.into();
+            transaction["relationships"]["account"]["data"]["id"] = "saver".into();
+            fixture.server.add_up_transaction(&transaction)?;
+        }
+
+        let args = super::Args {
+            since: None,
+            until: None,
+            in_path: None,
+            dry_run: false,
+            create_missing_accounts: true,
+            resume: None,
+        };
+        super::sync(&fixture.config, args).await?;
+
+        let account = fixture
+            .server
+            .ynab_accounts()
+            .into_iter()
+            .find(|x| x["name"] == "Rainy Day")
+            .wrap_err("missing ynab account")?;
+        assert_eq!("savings", account["type"]);
+        // The synced transactions bring it to the Up balance.
+        assert_eq!(350_000, account["balance"]);
+
+        let synced = fixture
+            .server
+            .ynab_transactions()
+            .into_iter()
+            .filter(|x| x["account_id"] == account["id"])
+            .map(|x| x["amount"].as_i64().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(2, synced.len());
+        assert_eq!(500_000, 350_000 + synced.iter().sum::<i64>());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn created_accounts_keep_run_readable() -> Result<()> {
+        let fixture = Fixture::new("created_accounts_keep_run_readable")?;
+        add_saver(&fixture)?;
+
+        let mut run = Run::fetch(&fixture.config, None, None).await?;
+        let ynab_client = fixture.config.ynab_client()?;
+        super::create_missing_accounts(&fixture.config, &ynab_client, BUDGET_ID, &mut run, false)
+            .await?;
+
+        // A sync stopped before planning resumes from the run as it was left.
+        let run = Run::read(&run.path, None)?;
+        assert!(run
+            .ynab_accounts
+            .unwrap_or_default()
+            .iter()
+            .any(|x| x.name == "Rainy Day"));
+        Ok(())
+    }
+    const TRANSFER_ID: &str = "0b519c3e-5958-49ec-bc48-86d792247a2f";
+
+    /// Adds an Up and YNAB home account, and a transfer from spending whose legs are two minutes
+    /// apart, returning a time between them.
+    fn add_transfer(fixture: &Fixture) -> Result<DateTime<FixedOffset>> {
+        let mut home = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/run/up_accounts/2be1c9de-7a89-4e8f-8077-f535150b588d.json",
+        )?)?;
+        home["id"] = "328160b1-d7bc-41ee-9d7b-c7da4f2484b0".into();
+        home["attributes"]["displayName"] = "Home".into();
+        fixture.server.add_up_account(&home)?;
+
+        let mut ynab_home = serde_json::from_str::<serde_json::Value>(&fs::read_to_string(
+            "test/data/run/ynab_accounts/9c1f3e7a-2b4d-4c6e-8f0a-1b3c5d7e9f2a.json",
+        )?)?;
+        ynab_home["id"] = "2b00a77e-9b3c-4277-9c6c-6944f7696705".into();
+        ynab_home["name"] = "Home".into();
+        ynab_home["transfer_payee_id"] = "f9b0b92f-70f7-4015-b885-4e5807a78a44".into();
+        fixture.server.add_ynab_account(&ynab_home)?;
+
+        let transfer = serde_json::from_str::<Vec<serde_json::Value>>(&fs::read_to_string(
+            "test/data/up_transfer_balance.json",
+        )?)?;
+        for mut leg in transfer.into_iter().skip(1) {
+            if leg["attributes"]["amount"]["valueInBaseUnits"].as_i64() < Some(0) {
+                leg["attributes"]["createdAt"] = "2023-09-01T13:50:09+10:00".into();
+            }
+            fixture.server.add_up_transaction(&leg)?;
+        }
+        Ok(DateTime::parse_from_rfc3339("2023-09-01T13:51:00+10:00")?)
+    }
+
+    #[tokio::test]
+    async fn sync_widens_without_writing_read_only_runs() -> Result<()> {
+        let fixture = Fixture::with_config(
+            "sync_widens_without_writing_read_only_runs",
+            "
+            [sync]
+            orphan_transfers = \"widen\"
+            ",
+        )?;
+        let since = add_transfer(&fixture)?;
+        let num_up_transactions = |path: &Path| {
+            Ok::<_, Error>(
+                Run::read(path, None)?
+                    .up_transactions
+                    .unwrap_or_default()
+                    .len(),
+            )
+        };
+
+        // The counterpart before `since` is fetched, but not written to the dry run's snapshot.
+        let args = |in_path: Option<PathBuf>, dry_run| super::Args {
+            since: Some(since),
+            until: None,
+            in_path,
+            dry_run,
+            create_missing_accounts: false,
+            resume: None,
+        };
+        super::sync(&fixture.config, args(None, true)).await?;
+        let runs = Run::list(fixture.config.runs_dir())?;
+        assert_eq!(3, num_up_transactions(&runs[0])?);
+        assert_eq!(
+            2,
+            fixture.num_requests(Service::Up, &Method::GET, "/transactions")
+        );
+
+        // Nor to a run given to sync, though it's planned.
+        let mut run = Run::read(&runs[0], None)?;
+        let budget_id = Uuid::parse_str(BUDGET_ID)?;
+        let plan = super::plan(
+            &fixture.config,
+            &fixture.config.ynab_client()?,
+            budget_id,
+            &mut run,
+            &args(Some(runs[0].clone()), false),
+        )
+        .await?;
+        assert_eq!(3, num_up_transactions(&runs[0])?);
+        // The widened transfer is imported as one, rather than as an external orphan.
+        let transfer = plan
+            .create
+            .iter()
+            .flatten()
+            .find(|x| x.import_id.clone().flatten().as_deref() == Some(TRANSFER_ID))
+            .wrap_err("missing transfer")?;
+        assert_eq!(
+            Some(Some(Uuid::parse_str(
+                "89ddd9ef-2510-4b42-a889-e7a68cae291c"
+            )?)),
+            transfer.payee_id
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+//! Run components stored as a single zstd-compressed JSON Lines file, one entry per line.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::frontend::{atomic, crypto::Cipher};
+
+/// Compression level, zstd's default.
+const LEVEL: i32 = 3;
+
+/// Returns the path of a component's archive inside the run.
+#[must_use]
+pub fn path<P: AsRef<Path>>(run_path: P, component: &str) -> PathBuf {
+    run_path.as_ref().join(format!("{component}.jsonl.zst"))
+}
+
+/// Writes the entries in order, replacing the archive only once it's complete.
+pub fn write<T: Serialize, P: AsRef<Path>>(
+    path: P,
+    entries: &[T],
+    cipher: Option<&Cipher>,
+) -> Result<()> {
+    let path = path.as_ref();
+    let path_str = path.to_string_lossy();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut encoder = zstd::Encoder::new(Vec::new(), LEVEL)?;
+    for entry in entries {
+        serde_json::to_writer(&mut encoder, entry)
+            .wrap_err(format!("failed to write entry to `{path_str}`"))?;
+        encoder.write_all(b"\n")?;
+    }
+    let mut contents = encoder.finish()?;
+    if let Some(cipher) = cipher {
+        contents = cipher.seal(&file_name(path), &contents)?;
+    }
+
+    atomic::write(path, &contents)
+}
+
+/// Returns the archive's decompressed lines.
+pub fn read_lines<P: AsRef<Path>>(path: P, cipher: Option<&Cipher>) -> Result<Vec<String>> {
+    let path_str = path.as_ref().to_string_lossy();
+    let mut contents = fs::read(&path).wrap_err(format!("failed to open `{path_str}`"))?;
+    if let Some(cipher) = cipher {
+        contents = cipher.open(&file_name(path.as_ref()), &contents)?;
+    }
+    let decoder = zstd::Decoder::new(contents.as_slice())?;
+    BufReader::new(decoder)
+        .lines()
+        .filter(|x| !matches!(x, Ok(x) if x.is_empty()))
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err(format!("failed to decompress `{path_str}`"))
+}
+
+pub fn read<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+    cipher: Option<&Cipher>,
+) -> Result<Vec<T>> {
+    let path_str = path.as_ref().to_string_lossy();
+    read_lines(&path, cipher)?
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .wrap_err(format!("failed to parse line {} of `{path_str}`", i + 1))
+        })
+        .collect()
+}
+
+/// The name encrypted files are bound to.
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,17 @@
+//! Writes that never leave a partial file behind when the process stops midway.
+
+use std::{ffi::OsString, fs, path::Path};
+
+use color_eyre::eyre::{Context, Result};
+
+/// Writes to a temporary file beside the path first, then renames it over the path.
+pub fn write<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let path_str = path.to_string_lossy();
+    let mut tmp_name = path.file_name().map(OsString::from).unwrap_or_default();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    fs::write(&tmp_path, contents).wrap_err(format!("failed to create `{path_str}`"))?;
+    fs::rename(&tmp_path, path).wrap_err(format!("failed to write `{path_str}`"))?;
+    Ok(())
+}
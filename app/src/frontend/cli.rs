@@ -1,6 +1,9 @@
 use std::path::PathBuf;
 
 use chrono::{DateTime, FixedOffset};
+use chrono_tz::Tz;
+
+use crate::frontend::output::Format;
 
 #[derive(clap::Parser)]
 #[command(author, version, about, long_about = None)]
@@ -8,25 +11,60 @@ pub struct Cli {
     /// Config file path.
     #[arg(long, value_name = "FILE")]
     pub config: Option<PathBuf>,
+    /// Profile to run the command with.
+    #[arg(long, value_name = "NAME", conflicts_with = "all_profiles")]
+    pub profile: Option<String>,
+    /// Run the command with every profile.
+    #[arg(long, default_value_t = false)]
+    pub all_profiles: bool,
+    /// Record every API request and response into this directory, with tokens redacted.
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Replay API responses recorded with `--record` instead of using the network.
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
 
     #[command(subcommand)]
     pub command: Commands,
 }
 
-#[derive(clap::Subcommand)]
+#[derive(Clone, clap::Subcommand)]
 pub enum Commands {
     /// Syncs transactions from Up to YNAB.
     Sync(sync::Args),
 
+    /// Checks tokens, the budget, account matches, rules and the runs directory.
+    Doctor,
+
     #[command(subcommand)]
     Get(get::Cmd),
+
+    #[command(subcommand)]
+    Run(run::Cmd),
+
+    #[command(subcommand)]
+    Store(store::Cmd),
+
+    /// Prints the Up transactions matching a filter.
+    Query(query::Args),
+}
+
+/// Where and how a command writes its results.
+#[derive(Clone, Default, clap::Args)]
+pub struct OutputArgs {
+    /// Output format, inferred from the output file's extension or a table by default.
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+    /// Write to this file instead of stdout.
+    #[arg(long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
 }
 
 pub mod sync {
     use super::*;
 
     /// Fetches a resource.
-    #[derive(clap::Args)]
+    #[derive(Clone, clap::Args)]
     pub struct Args {
         /// Only sync transaction since this date.
         #[arg(long)]
@@ -40,6 +78,12 @@ pub mod sync {
         /// Run command without making any changes.
         #[arg(long, default_value_t = false)]
         pub dry_run: bool,
+        /// Create YNAB accounts for Up accounts without a matching YNAB account.
+        #[arg(long, default_value_t = false)]
+        pub create_missing_accounts: bool,
+        /// Resume an interrupted sync of this run from its last completed phase.
+        #[arg(long, value_name = "RUN", conflicts_with_all = ["since", "until", "in_path"])]
+        pub resume: Option<PathBuf>,
     }
 }
 
@@ -47,7 +91,7 @@ pub mod get {
     use super::*;
 
     /// Fetches a resource.
-    #[derive(clap::Subcommand)]
+    #[derive(Clone, clap::Subcommand)]
     pub enum Cmd {
         #[command(subcommand)]
         Account(account::Cmd),
@@ -58,13 +102,15 @@ pub mod get {
     }
 
     pub mod account {
+        use super::*;
+
         /// Fetches accounts.
-        #[derive(clap::Subcommand)]
+        #[derive(Clone, clap::Subcommand)]
         pub enum Cmd {
             /// Fetches Up accounts.
-            Up,
+            Up(OutputArgs),
             /// Fetches Ynab accounts.
-            Ynab,
+            Ynab(OutputArgs),
         }
     }
 
@@ -72,17 +118,29 @@ pub mod get {
         use super::*;
 
         /// Fetches transactions.
-        #[derive(clap::Subcommand)]
+        #[derive(Clone, clap::Subcommand)]
         pub enum Cmd {
-            Up(up::Args),
-            Ynab(ynab::Args),
+            /// Fetches Up transactions.
+            Up {
+                #[command(flatten)]
+                fetch: up::Args,
+                #[command(flatten)]
+                output: OutputArgs,
+            },
+            /// Fetches Ynab transactions.
+            Ynab {
+                #[command(flatten)]
+                fetch: ynab::Args,
+                #[command(flatten)]
+                output: OutputArgs,
+            },
         }
 
         pub mod up {
             use super::*;
 
             /// Fetches Up accounts.
-            #[derive(clap::Args)]
+            #[derive(Clone, clap::Args)]
             pub struct Args {
                 /// Only fetch transaction since this date.
                 #[arg(long)]
@@ -97,7 +155,7 @@ pub mod get {
             use super::*;
 
             /// Fetches Ynab accounts.
-            #[derive(clap::Args)]
+            #[derive(Clone, clap::Args)]
             pub struct Args {
                 /// Only fetch transaction since this date.
                 #[arg(long)]
@@ -110,7 +168,7 @@ pub mod get {
         use super::*;
 
         /// List running balance.
-        #[derive(clap::Subcommand)]
+        #[derive(Clone, clap::Subcommand)]
         pub enum Cmd {
             Up(up::Args),
             Ynab(ynab::Args),
@@ -120,12 +178,12 @@ pub mod get {
             use super::*;
 
             /// List running Up balance.
-            #[derive(clap::Args)]
+            #[derive(Clone, clap::Args)]
             pub struct Args {
                 /// Run input path.
                 #[arg(long, value_name = "FILE")]
                 pub in_path: Option<PathBuf>,
-                /// CSV output path.
+                /// Write every balance to this CSV file, with a column per account.
                 #[arg(long, value_name = "FILE")]
                 pub out_path: Option<PathBuf>,
                 /// Only list balances since this date.
@@ -134,6 +192,8 @@ pub mod get {
                 /// Only list balances until this date.
                 #[arg(long)]
                 pub until: Option<DateTime<FixedOffset>>,
+                #[command(flatten)]
+                pub output: OutputArgs,
             }
         }
 
@@ -141,12 +201,12 @@ pub mod get {
             use super::*;
 
             /// List running Ynab balance.
-            #[derive(clap::Args)]
+            #[derive(Clone, clap::Args)]
             pub struct Args {
                 /// Previous run path.
                 #[arg(long, value_name = "FILE")]
                 pub in_path: PathBuf,
-                /// CSV output path.
+                /// Write every balance to this CSV file, with a column per account.
                 #[arg(long, value_name = "FILE")]
                 pub out_path: Option<PathBuf>,
                 /// Only list balances since this date.
@@ -155,7 +215,178 @@ pub mod get {
                 /// Only list balances until this date.
                 #[arg(long)]
                 pub until: Option<DateTime<FixedOffset>>,
+                #[command(flatten)]
+                pub output: OutputArgs,
             }
         }
     }
 }
+
+pub mod run {
+    use super::*;
+
+    /// Inspects and maintains runs.
+    #[derive(Clone, clap::Subcommand)]
+    pub enum Cmd {
+        Info(info::Args),
+        Pack(pack::Args),
+        Encrypt(encrypt::Args),
+        Diff(diff::Args),
+        Prune(prune::Args),
+        Import(import::Args),
+    }
+
+    pub mod info {
+        use super::*;
+
+        /// Validates a run and prints its manifest.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Run path.
+            #[arg(value_name = "PATH")]
+            pub path: PathBuf,
+        }
+    }
+
+    pub mod pack {
+        use super::*;
+
+        /// Converts directory runs into compressed archives.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Run paths, every run in the profile's runs directory by default.
+            #[arg(value_name = "PATH")]
+            pub paths: Vec<PathBuf>,
+        }
+    }
+
+    pub mod encrypt {
+        use super::*;
+
+        /// Encrypts runs in place with the key configured under `[encryption]`, verifying each.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Run paths, every run in the profile's runs directory by default.
+            #[arg(value_name = "PATH")]
+            pub paths: Vec<PathBuf>,
+        }
+    }
+
+    pub mod diff {
+        use super::*;
+
+        /// Prints the transactions and balances that changed between two runs.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Earlier run path.
+            #[arg(value_name = "BEFORE")]
+            pub before: PathBuf,
+            /// Later run path.
+            #[arg(value_name = "AFTER")]
+            pub after: PathBuf,
+        }
+    }
+
+    pub mod prune {
+        /// Removes the profile's runs not kept by its retention policy. The run used by the last
+        /// sync is always kept.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Lists the runs that would be removed without removing them.
+            #[arg(long, default_value_t = false)]
+            pub dry_run: bool,
+        }
+    }
+
+    pub mod import {
+        use super::*;
+
+        /// Writes a new run from Up CSV exports or JSON dumps of Up transactions, to backfill
+        /// history the API no longer returns.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// CSV exports from the Up app, or JSON dumps of Up transactions.
+            #[arg(value_name = "PATH", required = true)]
+            pub paths: Vec<PathBuf>,
+            /// Name of the Up account the CSV exports belong to, or of the one account the dumps
+            /// reference that Up no longer lists.
+            #[arg(long, value_name = "NAME")]
+            pub account: Option<String>,
+            /// Create the account as a saver when Up no longer lists it.
+            #[arg(long, default_value_t = false)]
+            pub saver: bool,
+            /// IANA time zone of the times in CSV exports, observing its daylight saving.
+            #[arg(long, value_name = "ZONE", default_value = "Australia/Sydney")]
+            pub timezone: Tz,
+            /// Take accounts and YNAB transactions from this run instead of fetching them.
+            #[arg(long, value_name = "RUN")]
+            pub base: Option<PathBuf>,
+        }
+    }
+}
+
+pub mod store {
+    use super::*;
+
+    /// Inspects and maintains the store of Up and YNAB data.
+    #[derive(Clone, clap::Subcommand)]
+    pub enum Cmd {
+        Info,
+        Import(import::Args),
+        Export(export::Args),
+    }
+
+    pub mod import {
+        use super::*;
+
+        /// Imports runs into the store.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Run paths, every run in the profile's runs directory by default.
+            #[arg(value_name = "PATH")]
+            pub paths: Vec<PathBuf>,
+        }
+    }
+
+    pub mod export {
+        use super::*;
+
+        /// Exports the store's entries for the profile's budget as a new run.
+        #[derive(Clone, clap::Args)]
+        pub struct Args {
+            /// Only export Up transactions created since this date, and YNAB transactions dated
+            /// since it.
+            #[arg(long)]
+            pub since: Option<DateTime<FixedOffset>>,
+            /// Only export Up transactions created before this date.
+            #[arg(long)]
+            pub until: Option<DateTime<FixedOffset>>,
+        }
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    /// Filters Up transactions, e.g. `payee ~ uber and amount < -30 and date >= 2024-03-01`.
+    ///
+    /// Fields are date, amount, payee, account, category, tags, status and kind, compared with
+    /// `=`, `!=`, `<`, `<=`, `>`, `>=` or `~` (contains) and joined with `and`, `or` and `not`.
+    #[derive(Clone, clap::Args)]
+    pub struct Args {
+        /// Filter expression, every transaction by default.
+        #[arg(value_name = "FILTER")]
+        pub filter: Option<String>,
+        /// Query this run instead of fetching transactions.
+        #[arg(long, value_name = "RUN", conflicts_with_all = ["since", "until"])]
+        pub in_path: Option<PathBuf>,
+        /// Only fetch transaction since this date.
+        #[arg(long)]
+        pub since: Option<DateTime<FixedOffset>>,
+        /// Only fetch transaction until this date.
+        #[arg(long)]
+        pub until: Option<DateTime<FixedOffset>>,
+        #[command(flatten)]
+        pub output: OutputArgs,
+    }
+}
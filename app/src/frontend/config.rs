@@ -1,16 +1,624 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Context, Result};
+use figment::Figment;
+use serde::{de::Error as _, Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api::{
+        http::HttpSettings,
+        rate_limit::{RateLimitPolicy, RateLimiter},
+        retry::RetryPolicy,
+        up, ynab, Service,
+    },
+    frontend::{crypto::Secret, retention::Retention, run::Layout},
+    model::OrphanPolicy,
+};
+
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct Up {
+    /// Name of the profile, used in logs.
+    #[serde(default)]
+    pub name: Option<String>,
     pub api_token: String,
+    /// Whether joint (2Up) accounts are synced through this profile. Exactly one profile must set
+    /// this when there are multiple Up profiles.
+    #[serde(default)]
+    pub joint_accounts: bool,
 }
 
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct Ynab {
     pub api_token: String,
     pub budget_id: Option<String>,
+    /// Client-side rate limit, tracked across runs.
+    #[serde(default)]
+    pub rate_limit: RateLimitPolicy,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct Sync {
+    /// What to do with transfers missing their counterpart.
+    pub orphan_transfers: OrphanPolicy,
+    /// The maximum number of transactions created or updated per request.
+    pub batch_size: usize,
+}
+
+/// How Up transactions are fetched.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct Fetch {
+    /// Fetches each account's transactions concurrently, with at most this many pages in flight.
+    /// Transactions are fetched page by page across all accounts when unset.
+    pub concurrency: Option<usize>,
+    /// Splits the fetched date range into slices of this many days, fetched concurrently. The
+    /// range is only split when it has a start, such as `--since`.
+    #[serde(deserialize_with = "days")]
+    pub slice_days: Option<Duration>,
+}
+
+/// Encrypts new runs with a key derived from either a passphrase or the contents of a key file.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Encryption {
+    pub passphrase: Option<String>,
+    pub key_file: Option<PathBuf>,
 }
 
+/// Turns matching external Up transactions into transfers to a YNAB account without an Up
+/// counterpart. Every pattern given must match.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct Rule {
+    /// Regex matched against the Up transaction description.
+    pub description: Option<String>,
+    /// Regex matched against the Up transaction raw text.
+    pub raw_text: Option<String>,
+    /// Name of the YNAB account to transfer to.
+    pub account: String,
+}
+
+/// The longest slice transactions can be fetched in, about 100 years.
+const MAX_SLICE_DAYS: i64 = 36_500;
+
+/// Name of the profile defined by the top-level `up` and `ynab` sections.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// A sync profile, syncing one or more Up tokens into a single YNAB budget.
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct Config {
-    pub up: Up,
+    /// Name of the profile, set when loading.
+    #[serde(skip)]
+    pub name: String,
+    /// Up profiles, either a single `[up]` table or multiple `[[up]]` tables.
+    #[serde(deserialize_with = "one_or_many")]
+    pub up: Vec<Up>,
     pub ynab: Ynab,
+    #[serde(default)]
+    pub sync: Sync,
+    #[serde(default)]
+    pub fetch: Fetch,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Maps Up account names to YNAB account names, for accounts whose names differ.
+    #[serde(default)]
+    pub accounts: HashMap<String, String>,
+    /// Policy used to retry failed API requests.
+    #[serde(default, deserialize_with = "retry_policy")]
+    pub retry: RetryPolicy,
+    /// HTTP client settings.
+    #[serde(default)]
+    pub http: HttpSettings,
+    /// Directory runs are stored in, `runs` by default.
+    #[serde(default)]
+    pub runs_path: Option<PathBuf>,
+    /// How new runs are stored, `directory` by default.
+    #[serde(default)]
+    pub run_layout: Layout,
+    /// Encrypts new runs and the store when set, and is required to read them. The store only
+    /// seals its entries, the ids, accounts, categories, statuses, dates and tags it queries them
+    /// by are kept in plain text.
+    #[serde(default)]
+    pub encryption: Option<Encryption>,
+    /// Which runs `run prune` and sync keep, every run is kept by default.
+    #[serde(default)]
+    pub retention: Retention,
+    /// Store updated by every sync, `store.sqlite` in the runs directory by default.
+    #[serde(default)]
+    pub store_path: Option<PathBuf>,
+}
+
+impl Default for Sync {
+    fn default() -> Self {
+        Self {
+            orphan_transfers: OrphanPolicy::default(),
+            batch_size: 100,
+        }
+    }
+}
+
+impl Up {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+}
+
+impl Config {
+    /// Loads every sync profile. The top-level `up` and `ynab` sections form the `default`
+    /// profile, named profiles live under `profiles.<name>`.
+    pub fn load(figment: &Figment) -> Result<BTreeMap<String, Config>> {
+        let mut profiles = if figment.find_value("profiles").is_ok() {
+            figment
+                .extract_inner::<BTreeMap<String, Config>>("profiles")
+                .wrap_err("failed to parse profiles")?
+        } else {
+            BTreeMap::new()
+        };
+
+        if figment.find_value("up").is_ok() || figment.find_value("ynab").is_ok() {
+            if profiles.contains_key(DEFAULT_PROFILE) {
+                return Err(eyre!(
+                    "profile `{DEFAULT_PROFILE}` is already defined by the top-level config"
+                ));
+            }
+            let config = figment
+                .extract::<Config>()
+                .wrap_err("failed to parse config")?;
+            profiles.insert(DEFAULT_PROFILE.to_owned(), config);
+        }
+
+        for (name, config) in &mut profiles {
+            // The name is joined onto the runs and cassette directories.
+            let mut components = Path::new(name).components();
+            if !matches!(
+                (components.next(), components.next()),
+                (Some(Component::Normal(x)), None) if x == name.as_str()
+            ) {
+                return Err(eyre!(
+                    "profile name `{name}` must be a single path component"
+                ));
+            }
+            config.name.clone_from(name);
+        }
+
+        if profiles.is_empty() {
+            return Err(eyre!("config has no profiles"));
+        }
+        Ok(profiles)
+    }
+
+    /// Selects the profiles to run with. Without a profile name, the `default` profile or the only
+    /// profile is selected.
+    pub fn select(
+        mut profiles: BTreeMap<String, Config>,
+        name: Option<&str>,
+        all: bool,
+    ) -> Result<Vec<Config>> {
+        if all {
+            return Ok(profiles.into_values().collect());
+        }
+
+        let name = match name {
+            Some(name) => name.to_owned(),
+            None if profiles.len() == 1 => profiles.keys().next().cloned().unwrap_or_default(),
+            None if profiles.contains_key(DEFAULT_PROFILE) => DEFAULT_PROFILE.to_owned(),
+            None => {
+                return Err(eyre!(
+                    "multiple profiles defined, select one with `--profile` or use `--all-profiles`"
+                ))
+            }
+        };
+
+        profiles
+            .remove(&name)
+            .map(|x| vec![x])
+            .ok_or_else(|| eyre!("missing profile `{name}`"))
+    }
+
+    /// Returns the secret run keys are derived from, if encryption is configured.
+    pub fn secret(&self) -> Result<Option<Secret>> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(None);
+        };
+        match (&encryption.passphrase, &encryption.key_file) {
+            (Some(passphrase), None) => Ok(Some(Secret::new(passphrase.as_bytes().to_vec()))),
+            (None, Some(key_file)) => {
+                fs::read(key_file)
+                    .map(|x| Some(Secret::new(x)))
+                    .wrap_err(format!(
+                        "failed to read key file `{}`",
+                        key_file.to_string_lossy()
+                    ))
+            }
+            _ => Err(eyre!(
+                "`encryption` requires exactly one of `passphrase` or `key_file`"
+            )),
+        }
+    }
+
+    /// Returns the directory runs for this profile are stored in.
+    #[must_use]
+    pub fn runs_dir(&self) -> PathBuf {
+        let runs_path = self.runs_root();
+        if self.name.is_empty() || self.name == DEFAULT_PROFILE {
+            runs_path
+        } else {
+            runs_path.join(&self.name)
+        }
+    }
+
+    /// Returns where the YNAB requests made with this profile's token are tracked, shared by
+    /// every profile using the same token.
+    #[must_use]
+    pub fn ynab_requests_path(&self) -> PathBuf {
+        let hash = Sha256::digest(self.ynab.api_token.as_bytes());
+        self.runs_root()
+            .join(format!(".ynab_requests-{hash:x}.json"))
+    }
+
+    fn runs_root(&self) -> PathBuf {
+        self.runs_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("runs"))
+    }
+
+    /// Returns the path of this profile's store.
+    #[must_use]
+    pub fn store_path(&self) -> PathBuf {
+        self.store_path
+            .clone()
+            .unwrap_or_else(|| self.runs_dir().join("store.sqlite"))
+    }
+
+    /// Creates an Up client for the given Up profile.
+    pub fn up_client(&self, up: &Up) -> Result<up::Client> {
+        up::Client::builder()
+            .api_token(&up.api_token)
+            .http(self.http.clone())
+            .retry(self.retry.clone())
+            .build()
+    }
+
+    /// Creates a YNAB client, tracking requests in a file under the runs root keyed by a hash of
+    /// the token, so every profile using the token shares its rate limit.
+    pub fn ynab_client(&self) -> Result<ynab::Client> {
+        let rate_limit = RateLimiter::new(
+            Service::Ynab,
+            self.ynab.rate_limit.clone(),
+            self.ynab_requests_path(),
+        );
+        ynab::Client::builder()
+            .api_token(&self.ynab.api_token)
+            .http(self.http.clone())
+            .retry(self.retry.clone())
+            .rate_limit(rate_limit)
+            .build()
+    }
+
+    /// Returns the index of the Up profile which syncs joint accounts.
+    pub fn joint_account_owner(&self) -> Result<usize> {
+        if let [_] = self.up.as_slice() {
+            return Ok(0);
+        }
+
+        let owners = self
+            .up
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.joint_accounts)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        match owners.as_slice() {
+            [owner] => Ok(*owner),
+            [] => Err(eyre!("no up profile has `joint_accounts` set")),
+            _ => Err(eyre!("multiple up profiles have `joint_accounts` set")),
+        }
+    }
+}
+
+fn one_or_many<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Vec<T>, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(x) => vec![x],
+        OneOrMany::Many(x) => x,
+    })
+}
+
+/// Parses a positive number of days, at most [`MAX_SLICE_DAYS`].
+fn days<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let days = i64::deserialize(deserializer)?;
+    if !(1..=MAX_SLICE_DAYS).contains(&days) {
+        return Err(D::Error::custom(format!(
+            "`slice_days` must be between 1 and {MAX_SLICE_DAYS}, got {days}"
+        )));
+    }
+    Duration::try_days(days)
+        .map(Some)
+        .ok_or_else(|| D::Error::custom(format!("`slice_days` of {days} is out of range")))
+}
+
+/// Parses a retry policy whose delays grow and stay within `max_interval_ms`.
+fn retry_policy<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RetryPolicy, D::Error> {
+    let policy = RetryPolicy::deserialize(deserializer)?;
+    if !policy.multiplier.is_finite() || policy.multiplier < 1.0 {
+        return Err(D::Error::custom(format!(
+            "`multiplier` must be a number of at least 1, got {}",
+            policy.multiplier
+        )));
+    }
+    if policy.initial_interval_ms > policy.max_interval_ms {
+        return Err(D::Error::custom(format!(
+            "`initial_interval_ms` of {} is greater than `max_interval_ms` of {}",
+            policy.initial_interval_ms, policy.max_interval_ms
+        )));
+    }
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod test {
+    use figment::providers::{Format, Toml};
+
+    use super::*;
+
+    fn load(toml: &str) -> Result<BTreeMap<String, Config>> {
+        Config::load(&Figment::from(Toml::string(toml)))
+    }
+
+    fn profile(up: &str) -> Result<Config> {
+        let toml = format!(
+            r#"
+            {up}
+
+            [ynab]
+            api_token = "ynab-token"
+            "#
+        );
+        load(&toml)?
+            .remove(DEFAULT_PROFILE)
+            .ok_or_else(|| eyre!("missing profile"))
+    }
+
+    const PROFILES: &str = r#"
+        [up]
+        api_token = "up-token"
+        [ynab]
+        api_token = "ynab-token"
+
+        [profiles.work.up]
+        api_token = "work-up-token"
+        [profiles.work.ynab]
+        api_token = "work-ynab-token"
+        budget_id = "work-budget"
+    "#;
+
+    fn names(profiles: &[Config]) -> Vec<&str> {
+        profiles.iter().map(|x| x.name.as_str()).collect()
+    }
+
+    fn error<T>(result: Result<T>) -> Option<String> {
+        result.err().map(|e| e.to_string())
+    }
+
+    #[test]
+    fn load_profiles() -> Result<()> {
+        let profiles = load(PROFILES)?;
+        assert_eq!(
+            vec!["default", "work"],
+            profiles.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert_eq!("up-token", profiles["default"].up[0].api_token);
+        assert_eq!(
+            Some("work-budget"),
+            profiles["work"].ynab.budget_id.as_deref()
+        );
+
+        let named = load(
+            r#"
+            [profiles.home.up]
+            api_token = "up-token"
+            [profiles.home.ynab]
+            api_token = "ynab-token"
+            "#,
+        )?;
+        assert_eq!(vec!["home"], named.keys().collect::<Vec<_>>());
+
+        let duplicate = format!(
+            "{PROFILES}
+            [profiles.default.up]
+            api_token = \"up-token\"
+            [profiles.default.ynab]
+            api_token = \"ynab-token\"
+            "
+        );
+        assert_eq!(
+            Some("profile `default` is already defined by the top-level config".to_owned()),
+            error(load(&duplicate))
+        );
+        assert_eq!(
+            Some("config has no profiles".to_owned()),
+            error(load("runs_path = \"runs\""))
+        );
+
+        for name in ["..", ".", "../x", "a/b", "/a", "a/"] {
+            let profile = format!(
+                "[profiles.\"{name}\".up]
+                api_token = \"up-token\"
+                [profiles.\"{name}\".ynab]
+                api_token = \"ynab-token\"
+                "
+            );
+            assert_eq!(
+                Some(format!(
+                    "profile name `{name}` must be a single path component"
+                )),
+                error(load(&profile)),
+                "{name}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn select_profiles() -> Result<()> {
+        assert_eq!(
+            vec!["default"],
+            names(&Config::select(load(PROFILES)?, None, false)?)
+        );
+        assert_eq!(
+            vec!["work"],
+            names(&Config::select(load(PROFILES)?, Some("work"), false)?)
+        );
+        assert_eq!(
+            vec!["default", "work"],
+            names(&Config::select(load(PROFILES)?, None, true)?)
+        );
+        assert_eq!(
+            Some("missing profile `home`".to_owned()),
+            error(Config::select(load(PROFILES)?, Some("home"), false))
+        );
+
+        // Without a default, the only profile is selected and several are ambiguous.
+        let mut named = load(PROFILES)?;
+        named.remove(DEFAULT_PROFILE);
+        assert_eq!(
+            vec!["work"],
+            names(&Config::select(named.clone(), None, false)?)
+        );
+        let mut home = named["work"].clone();
+        home.name = "home".to_owned();
+        named.insert("home".to_owned(), home);
+        assert_eq!(
+            Some(
+                "multiple profiles defined, select one with `--profile` or use `--all-profiles`"
+                    .to_owned()
+            ),
+            error(Config::select(named, None, false))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn profile_runs_dirs() -> Result<()> {
+        let mut profiles = load(PROFILES)?;
+        assert_eq!(PathBuf::from("runs"), profiles["default"].runs_dir());
+        assert_eq!(PathBuf::from("runs/work"), profiles["work"].runs_dir());
+
+        let work = profiles
+            .get_mut("work")
+            .ok_or_else(|| eyre!("missing profile"))?;
+        work.runs_path = Some(PathBuf::from("/tmp/up_ynab"));
+        assert_eq!(PathBuf::from("/tmp/up_ynab/work"), work.runs_dir());
+        assert_eq!(
+            PathBuf::from("/tmp/up_ynab/work/store.sqlite"),
+            work.store_path()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ynab_requests_shared_by_token() -> Result<()> {
+        let mut profiles = load(PROFILES)?;
+        let default = profiles["default"].ynab_requests_path();
+        assert_eq!(Some(std::path::Path::new("runs")), default.parent());
+        assert_ne!(default, profiles["work"].ynab_requests_path());
+
+        let work = profiles
+            .get_mut("work")
+            .ok_or_else(|| eyre!("missing profile"))?;
+        work.ynab.api_token = "ynab-token".to_owned();
+        assert_eq!(default, work.ynab_requests_path());
+        Ok(())
+    }
+
+    #[test]
+    fn slice_days() -> Result<()> {
+        let fetch = |days| {
+            profile(&format!(
+                "[up]\napi_token = \"a\"\n[fetch]\nslice_days = {days}"
+            ))
+        };
+        assert_eq!(Some(Duration::days(7)), fetch("7")?.fetch.slice_days);
+        for days in ["0", "-1", "36501", "9223372036854775807"] {
+            assert!(fetch(days).is_err(), "{days}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn retry_policy() -> Result<()> {
+        let retry = |policy: &str| profile(&format!("[up]\napi_token = \"a\"\n[retry]\n{policy}"));
+        assert!(retry("multiplier = 1.5").is_ok());
+        assert_eq!(
+            1_000,
+            retry("initial_interval_ms = 1000\nmax_interval_ms = 1000")?
+                .retry
+                .max_interval_ms
+        );
+        for policy in [
+            "multiplier = -2.0",
+            "multiplier = 0.5",
+            "multiplier = nan",
+            "multiplier = inf",
+            "initial_interval_ms = 2000\nmax_interval_ms = 1000",
+        ] {
+            assert!(retry(policy).is_err(), "{policy}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn joint_account_owner() -> Result<()> {
+        let single = profile("[up]\napi_token = \"a\"")?;
+        assert_eq!(0, single.joint_account_owner()?);
+
+        let owner = profile(
+            "
+            [[up]]
+            api_token = \"a\"
+            [[up]]
+            api_token = \"b\"
+            joint_accounts = true
+            [[up]]
+            api_token = \"c\"
+            ",
+        )?;
+        assert_eq!(1, owner.joint_account_owner()?);
+
+        let none = profile("[[up]]\napi_token = \"a\"\n[[up]]\napi_token = \"b\"")?;
+        assert_eq!(
+            Some("no up profile has `joint_accounts` set".to_owned()),
+            none.joint_account_owner().err().map(|e| e.to_string())
+        );
+
+        let many = profile(
+            "
+            [[up]]
+            api_token = \"a\"
+            joint_accounts = true
+            [[up]]
+            api_token = \"b\"
+            joint_accounts = true
+            ",
+        )?;
+        assert_eq!(
+            Some("multiple up profiles have `joint_accounts` set".to_owned()),
+            many.joint_account_owner().err().map(|e| e.to_string())
+        );
+        Ok(())
+    }
 }
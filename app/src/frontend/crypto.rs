@@ -0,0 +1,145 @@
+//! Authenticated encryption of run files, with a key derived from a passphrase or key file.
+
+use std::fmt;
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use color_eyre::eyre::{eyre, Context, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// The only supported key derivation function.
+const KDF: &str = "argon2id";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Sealed with the key, so a wrong key is reported before any entry is read.
+const CHECK: &[u8] = b"up_ynab";
+
+/// The passphrase or key file contents keys are derived from.
+#[derive(Clone)]
+pub struct Secret(Vec<u8>);
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+impl Secret {
+    #[must_use]
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self(secret)
+    }
+}
+
+/// Describes how a run's key is derived, stored in its manifest.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub kdf: String,
+    /// Hex encoded.
+    pub salt: String,
+    /// The check value sealed with the key, hex encoded.
+    pub check: String,
+}
+
+impl Header {
+    /// Creates a header with a random salt, returning it with its cipher.
+    pub fn new(secret: &Secret) -> Result<(Self, Cipher)> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = Cipher::derive(secret, &salt)?;
+        let header = Self {
+            kdf: KDF.to_owned(),
+            salt: hex::encode(salt),
+            check: hex::encode(cipher.seal("", CHECK)?),
+        };
+        Ok((header, cipher))
+    }
+
+    /// Derives the run's cipher, checking the secret is the one the run was encrypted with.
+    pub fn cipher(&self, secret: &Secret) -> Result<Cipher> {
+        if self.kdf != KDF {
+            return Err(eyre!("unsupported key derivation function `{}`", self.kdf));
+        }
+        let salt = hex::decode(&self.salt).wrap_err("invalid salt")?;
+        let cipher = Cipher::derive(secret, &salt)?;
+        let check = hex::decode(&self.check).wrap_err("invalid check")?;
+        cipher
+            .open("", &check)
+            .wrap_err("wrong passphrase or key file")?;
+        Ok(cipher)
+    }
+}
+
+#[derive(Clone)]
+pub struct Cipher(XChaCha20Poly1305);
+
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cipher(..)")
+    }
+}
+
+impl Cipher {
+    fn derive(secret: &Secret, salt: &[u8]) -> Result<Self> {
+        let mut key = [0; 32];
+        Argon2::default()
+            .hash_password_into(&secret.0, salt, &mut key)
+            .map_err(|e| eyre!("failed to derive key: {e}"))?;
+        Ok(Self(XChaCha20Poly1305::new(&key.into())))
+    }
+
+    /// Encrypts a file's contents, bound to its name so files can't be swapped. The random nonce
+    /// is prepended to the ciphertext.
+    pub fn seal(&self, name: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: plaintext,
+            aad: name.as_bytes(),
+        };
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, payload)
+            .map_err(|_| eyre!("failed to encrypt `{name}`"))?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub fn open(&self, name: &str, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return Err(eyre!("`{name}` is too short to be encrypted"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: name.as_bytes(),
+        };
+        self.0
+            .decrypt(XNonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                eyre!("failed to decrypt `{name}`, it's corrupt or was encrypted with another key")
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seal_open() -> Result<()> {
+        let (header, cipher) = Header::new(&Secret::new(b"hunter2".to_vec()))?;
+        let sealed = cipher.seal("a.json", b"{}")?;
+        assert_eq!(b"{}".to_vec(), cipher.open("a.json", &sealed)?);
+        assert!(cipher.open("b.json", &sealed).is_err());
+
+        let cipher = header.cipher(&Secret::new(b"hunter2".to_vec()))?;
+        assert_eq!(b"{}".to_vec(), cipher.open("a.json", &sealed)?);
+        assert!(header.cipher(&Secret::new(b"hunter3".to_vec())).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::frontend::{
+    archive, atomic,
+    crypto::{Cipher, Header, Secret},
+    run::{Layout, Run},
+};
+
+/// The current run format version. Runs written before manifests existed are version 0, version 2
+/// added the layout and version 3 encryption.
+pub const VERSION: u64 = 3;
+
+/// Name of the manifest file inside a run directory.
+pub const FILE_NAME: &str = "manifest.json";
+
+/// The directories a run stores its entries in.
+pub const COMPONENTS: [&str; 5] = [
+    "up_accounts",
+    "up_transactions",
+    "ynab_accounts",
+    "ynab_transactions",
+    "ynab_budgets",
+];
+
+/// Describes how a run was produced and what it contains.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u64,
+    /// Version of the tool that wrote the run.
+    pub tool_version: String,
+    /// Runs before version 2 are always directories.
+    #[serde(default)]
+    pub layout: Layout,
+    /// Set for encrypted runs.
+    #[serde(default)]
+    pub encryption: Option<Header>,
+    /// Unknown for migrated runs.
+    pub fetched_at: Option<DateTime<Utc>>,
+    /// Unknown for migrated runs.
+    pub parameters: Option<Parameters>,
+    pub components: BTreeMap<String, Component>,
+}
+
+/// The parameters a run was fetched with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parameters {
+    pub profile: String,
+    pub budget_id: Option<String>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Component {
+    pub count: usize,
+    /// SHA-256 of a directory's file names and contents in file name order, or of an archive's
+    /// decompressed lines.
+    pub checksum: String,
+}
+
+impl Manifest {
+    /// Creates a manifest describing the entries already written to the run.
+    pub fn new(
+        run: &Run,
+        encryption: Option<Header>,
+        fetched_at: DateTime<Utc>,
+        parameters: Parameters,
+    ) -> Result<Self> {
+        Ok(Self {
+            version: VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            layout: run.layout,
+            encryption,
+            fetched_at: Some(fetched_at),
+            parameters: Some(parameters),
+            components: Self::components(&run.path, run.layout, run.cipher.as_ref())?,
+        })
+    }
+
+    /// Reads and validates the run's manifest, returning it with the cipher for encrypted runs.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        secret: Option<&Secret>,
+    ) -> Result<(Self, Option<Cipher>)> {
+        let manifest = Self::read(&path)?;
+        let cipher = manifest.cipher(secret)?;
+        manifest.verify(&path, cipher.as_ref()).wrap_err(format!(
+            "run `{}` is corrupt",
+            path.as_ref().to_string_lossy()
+        ))?;
+        Ok((manifest, cipher))
+    }
+
+    /// Reads the run's manifest without validating it, migrating runs written by older versions.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let manifest_path = path.join(FILE_NAME);
+        let path_str = manifest_path.to_string_lossy();
+
+        let manifest = if manifest_path.exists() {
+            let value = File::open(&manifest_path)
+                .map(BufReader::new)
+                .map(serde_json::from_reader::<_, serde_json::Value>)
+                .wrap_err(format!("failed to open `{path_str}`"))?
+                .wrap_err(format!("failed to parse `{path_str}`"))?;
+            let version = value["version"]
+                .as_u64()
+                .wrap_err(format!("`{path_str}` is missing its version"))?;
+            if version > VERSION {
+                return Err(eyre!(
+                    "run format version {version} is newer than the supported version {VERSION}, \
+                     update to read it"
+                ));
+            }
+            serde_json::from_value::<Self>(value)
+                .wrap_err(format!("failed to parse `{path_str}`"))?
+        } else {
+            Self::migrate(path)?
+        };
+        Ok(manifest)
+    }
+
+    /// Derives the cipher for encrypted runs.
+    pub fn cipher(&self, secret: Option<&Secret>) -> Result<Option<Cipher>> {
+        match (&self.encryption, secret) {
+            (None, _) => Ok(None),
+            (Some(header), Some(secret)) => header.cipher(secret).map(Some),
+            (Some(_), None) => Err(eyre!(
+                "run is encrypted, set a passphrase or key file under `[encryption]`"
+            )),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref().join(FILE_NAME);
+        let contents = serde_json::to_vec_pretty(self)
+            .wrap_err(format!("failed to serialize `{}`", path.to_string_lossy()))?;
+        atomic::write(path, &contents)
+    }
+
+    /// Checks every component matches its count and checksum.
+    pub fn verify<P: AsRef<Path>>(&self, path: P, cipher: Option<&Cipher>) -> Result<()> {
+        let components = Self::components(path, self.layout, cipher)?;
+        for (name, expected) in &self.components {
+            let found = components
+                .get(name)
+                .wrap_err(format!("unknown component `{name}`"))?;
+            if found != expected {
+                return Err(eyre!(
+                    "component `{name}` has {} entries with checksum `{}`, expected {} with `{}`",
+                    found.count,
+                    found.checksum,
+                    expected.count,
+                    expected.checksum
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a manifest for a run written before manifests existed. Its contents are trusted as
+    /// is, since there's nothing to validate them against.
+    fn migrate(path: &Path) -> Result<Self> {
+        if !path.is_dir() {
+            return Err(eyre!("run missing at path `{}`", path.to_string_lossy()));
+        }
+        info!(
+            "migrating run `{}` to format version {VERSION}...",
+            path.to_string_lossy()
+        );
+
+        let manifest = Self {
+            version: VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_owned(),
+            layout: Layout::Directory,
+            encryption: None,
+            fetched_at: None,
+            parameters: None,
+            components: Self::components(path, Layout::Directory, None)?,
+        };
+        // Runs can be read from read-only locations, they're migrated again next time.
+        if let Err(e) = manifest.write(path) {
+            warn!("failed to write migrated manifest: {e:#}");
+        }
+        Ok(manifest)
+    }
+
+    /// Counts and hashes the run's components as they are on disk. Encrypted directories are
+    /// hashed as is, encrypted archives are decrypted.
+    pub fn components(
+        path: impl AsRef<Path>,
+        layout: Layout,
+        cipher: Option<&Cipher>,
+    ) -> Result<BTreeMap<String, Component>> {
+        COMPONENTS
+            .iter()
+            .map(|name| {
+                let component = Component::read(path.as_ref(), name, layout, cipher)
+                    .wrap_err(format!("failed to read run component `{name}`"))?;
+                Ok(((*name).to_owned(), component))
+            })
+            .collect()
+    }
+}
+
+impl Component {
+    /// Counts and hashes a component's entries, a missing component has none.
+    fn read(run_path: &Path, name: &str, layout: Layout, cipher: Option<&Cipher>) -> Result<Self> {
+        let mut hasher = Sha256::new();
+        let archive_path = archive::path(run_path, name);
+        if layout == Layout::Archive && archive_path.exists() {
+            let lines = archive::read_lines(archive_path, cipher)?;
+            for line in &lines {
+                hasher.update(line);
+                hasher.update(b"\n");
+            }
+            return Ok(Self {
+                count: lines.len(),
+                checksum: format!("{:x}", hasher.finalize()),
+            });
+        }
+
+        let path = run_path.join(name);
+        if !path.exists() {
+            return Ok(Self {
+                count: 0,
+                checksum: format!("{:x}", hasher.finalize()),
+            });
+        }
+
+        let mut paths = fs::read_dir(&path)?
+            .map(|x| Ok(x?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        for path in &paths {
+            let file_name = path.file_name().wrap_err("missing file name")?;
+            hasher.update(file_name.as_encoded_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(path)?);
+        }
+
+        Ok(Self {
+            count: paths.len(),
+            checksum: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cmd::fixture::Fixture, frontend::Run};
+
+    fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+            } else {
+                fs::copy(entry.path(), to.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetch_writes_manifest() -> Result<()> {
+        let fixture = Fixture::new("manifest_fetch_writes_manifest")?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+
+        let manifest = Run::read(&run.path, None)?
+            .manifest
+            .wrap_err("missing manifest")?;
+        assert_eq!(run.manifest, Some(manifest.clone()));
+        assert_eq!(VERSION, manifest.version);
+        assert_eq!(
+            Some("default"),
+            manifest.parameters.as_ref().map(|x| x.profile.as_str())
+        );
+        assert_eq!(2, manifest.components["up_transactions"].count);
+        assert_eq!(1, manifest.components["up_accounts"].count);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupt_run() -> Result<()> {
+        let fixture = Fixture::new("manifest_corrupt_run")?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+
+        let entry = fs::read_dir(run.path.join("up_transactions"))?
+            .next()
+            .wrap_err("missing entry")??;
+        fs::write(entry.path(), "{}")?;
+        assert!(Manifest::open(&run.path, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_legacy_run() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("up_ynab-{}-legacy-run", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        copy_dir(Path::new("test/data/run"), &path)?;
+        assert!(!path.join(FILE_NAME).exists());
+
+        let (manifest, _) = Manifest::open(&path, None)?;
+        assert!(manifest.parameters.is_none());
+        assert_eq!(2, manifest.components["up_transactions"].count);
+        assert_eq!(0, manifest.components["ynab_transactions"].count);
+        assert!(path.join(FILE_NAME).exists());
+
+        let mut newer = manifest;
+        newer.version = VERSION + 1;
+        newer.write(&path)?;
+        assert!(Manifest::open(&path, None).is_err());
+
+        fs::remove_dir_all(&path)?;
+        Ok(())
+    }
+}
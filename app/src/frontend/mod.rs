@@ -1,5 +1,14 @@
+pub mod archive;
+pub mod atomic;
 pub mod cli;
 pub mod config;
+pub mod crypto;
+pub mod manifest;
+pub mod output;
+pub mod retention;
 pub mod run;
+pub mod store;
+pub mod sync_state;
+pub mod up_export;
 
 pub use self::{cli::Cli, config::Config, run::Run};
@@ -0,0 +1,203 @@
+//! Writes command results as a table, JSON or CSV.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use color_eyre::eyre::{Context, Result};
+use serde::Serialize;
+
+use crate::frontend::cli::OutputArgs;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Aligned columns.
+    #[default]
+    Table,
+    /// A pretty-printed JSON array.
+    Json,
+    /// A JSON object per line.
+    Jsonl,
+    /// CSV with a header row.
+    Csv,
+}
+
+impl Format {
+    /// Guesses the format from a file's extension.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Rows that can be written as a table or CSV, with the same columns whatever the rows.
+pub trait Tabular {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Writes the rows to the output file, or stdout.
+pub fn write_to<T: Serialize + Tabular>(args: &OutputArgs, rows: &[T]) -> Result<()> {
+    let Some(path) = &args.output else {
+        let format = args.format.unwrap_or_default();
+        return write(io::stdout().lock(), rows, format);
+    };
+
+    let format = args
+        .format
+        .or_else(|| Format::from_path(path))
+        .unwrap_or_default();
+    let path_str = path.to_string_lossy();
+    let file = File::create(path).wrap_err(format!("failed to create `{path_str}`"))?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer, rows, format).wrap_err(format!("failed to write `{path_str}`"))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Names an enum value the way it's serialized, e.g. `SETTLED`.
+pub fn serialized_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(x)) => x,
+        Ok(x) => x.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+pub fn write<T: Serialize + Tabular, W: Write>(
+    mut writer: W,
+    rows: &[T],
+    format: Format,
+) -> Result<()> {
+    match format {
+        Format::Table => write_table(writer, rows)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut writer, rows)?;
+            writeln!(writer)?;
+        }
+        Format::Jsonl => {
+            for row in rows {
+                serde_json::to_writer(&mut writer, row)?;
+                writeln!(writer)?;
+            }
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            writer.write_record(T::HEADERS)?;
+            for row in rows {
+                writer.write_record(row.cells())?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_table<T: Tabular, W: Write>(mut writer: W, rows: &[T]) -> Result<()> {
+    let headers = T::HEADERS.iter().map(|x| (*x).to_owned()).collect();
+    let rows = [headers]
+        .into_iter()
+        .chain(rows.iter().map(Tabular::cells))
+        .collect::<Vec<_>>();
+    let mut widths = vec![0; T::HEADERS.len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        name: &'static str,
+        amount: i64,
+    }
+
+    impl Tabular for Row {
+        const HEADERS: &'static [&'static str] = &["name", "amount"];
+
+        fn cells(&self) -> Vec<String> {
+            vec![self.name.to_owned(), self.amount.to_string()]
+        }
+    }
+
+    fn written(format: Format) -> Result<String> {
+        let rows = [
+            Row {
+                name: "Uber Eats",
+                amount: -4550,
+            },
+            Row {
+                name: "Salary, March",
+                amount: 500_000,
+            },
+        ];
+        let mut buffer = Vec::new();
+        write(&mut buffer, &rows, format)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    #[test]
+    fn write_formats() -> Result<()> {
+        assert_eq!(
+            indoc! {"
+                name           amount
+                Uber Eats      -4550
+                Salary, March  500000
+            "},
+            written(Format::Table)?
+        );
+        assert_eq!(
+            indoc! {r#"
+                name,amount
+                Uber Eats,-4550
+                "Salary, March",500000
+            "#},
+            written(Format::Csv)?
+        );
+        let json = serde_json::from_str::<serde_json::Value>(&written(Format::Json)?)?;
+        assert_eq!(
+            serde_json::json!({"name": "Uber Eats", "amount": -4550}),
+            json[0]
+        );
+        assert_eq!(
+            indoc! {r#"
+                {"name":"Uber Eats","amount":-4550}
+                {"name":"Salary, March","amount":500000}
+            "#},
+            written(Format::Jsonl)?
+        );
+        assert_eq!(
+            Some(Format::Jsonl),
+            Format::from_path(Path::new("a/b.jsonl"))
+        );
+        assert_eq!(None, Format::from_path(Path::new("a/b")));
+        Ok(())
+    }
+}
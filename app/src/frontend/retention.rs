@@ -0,0 +1,225 @@
+//! Which runs are kept when pruning the runs directory.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Datelike, Duration, Utc};
+use color_eyre::eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::frontend::{run::Run, sync_state::SyncState};
+
+/// Name of the file recording the last sync, inside the runs directory.
+const LAST_SYNC: &str = "last_sync.json";
+
+/// Runs matching any rule are kept. Every run is kept when no rule is set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Keeps this many of the newest runs.
+    pub keep_last: usize,
+    /// Keeps the newest run of each day, for this many days including today.
+    pub keep_daily: u32,
+    /// Keeps the newest run of each month, for this many months including this month.
+    pub keep_monthly: u32,
+}
+
+/// The run the last sync used, which is never pruned.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastSync {
+    pub run: PathBuf,
+    pub synced_at: DateTime<Utc>,
+}
+
+impl Retention {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the runs to keep, given the runs and when they were created.
+    #[must_use]
+    pub fn keep(&self, runs: &[(PathBuf, DateTime<Utc>)], now: DateTime<Utc>) -> BTreeSet<PathBuf> {
+        if self.is_empty() {
+            return runs.iter().map(|(path, _)| path.clone()).collect();
+        }
+
+        let mut runs = runs.iter().collect::<Vec<_>>();
+        runs.sort_by_key(|(_, date)| std::cmp::Reverse(*date));
+
+        let mut keep = runs
+            .iter()
+            .take(self.keep_last)
+            .map(|(path, _)| path.clone())
+            .collect::<BTreeSet<_>>();
+
+        // Runs are newest first, so the first run seen in each period is its newest.
+        let today = now.date_naive();
+        let first_day = today - Duration::days(i64::from(self.keep_daily));
+        let mut days = BTreeSet::new();
+        for (path, date) in &runs {
+            let day = date.date_naive();
+            if day > first_day && days.insert(day) {
+                keep.insert(path.clone());
+            }
+        }
+
+        // `month0` is below 12, so the cast never wraps.
+        #[allow(clippy::cast_possible_wrap)]
+        let month_index = |x: DateTime<Utc>| x.year() * 12 + x.month0() as i32;
+        let first_month = month_index(now) - i32::try_from(self.keep_monthly).unwrap_or(i32::MAX);
+        let mut months = BTreeSet::new();
+        for (path, date) in &runs {
+            let month = month_index(*date);
+            if month > first_month && months.insert(month) {
+                keep.insert(path.clone());
+            }
+        }
+
+        keep
+    }
+}
+
+impl LastSync {
+    pub fn read<P: AsRef<Path>>(runs_dir: P) -> Result<Option<Self>> {
+        let path = runs_dir.as_ref().join(LAST_SYNC);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let path_str = path.to_string_lossy();
+        let last_sync = File::open(&path)
+            .map(BufReader::new)
+            .map(serde_json::from_reader)
+            .wrap_err(format!("failed to open `{path_str}`"))?
+            .wrap_err(format!("failed to parse `{path_str}`"))?;
+        Ok(Some(last_sync))
+    }
+
+    /// Records the run as the last synced one.
+    pub fn write<P: AsRef<Path>>(runs_dir: P, run: &Path) -> Result<()> {
+        let last_sync = Self {
+            run: fs::canonicalize(run)?,
+            synced_at: Utc::now(),
+        };
+        fs::create_dir_all(&runs_dir)?;
+        let path = runs_dir.as_ref().join(LAST_SYNC);
+        let file = File::create(&path)
+            .wrap_err(format!("failed to create `{}`", path.to_string_lossy()))?;
+        serde_json::to_writer_pretty(file, &last_sync)?;
+        Ok(())
+    }
+}
+
+/// Removes the runs the policy doesn't keep, returning them. The last synced run and runs with an
+/// unfinished sync are always kept.
+pub fn prune<P: AsRef<Path>>(
+    runs_dir: P,
+    retention: &Retention,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>> {
+    let runs = Run::list(&runs_dir)?
+        .into_iter()
+        .filter_map(|path| {
+            let date = path
+                .file_name()
+                .and_then(|x| DateTime::parse_from_rfc3339(&x.to_string_lossy()).ok())?;
+            Some((path, date.with_timezone(&Utc)))
+        })
+        .collect::<Vec<_>>();
+    let keep = retention.keep(&runs, Utc::now());
+    let last_sync = LastSync::read(&runs_dir)?.map(|x| x.run);
+
+    let mut pruned = Vec::new();
+    for (path, _) in runs {
+        if keep.contains(&path) || last_sync.as_ref() == fs::canonicalize(&path).ok().as_ref() {
+            continue;
+        }
+        // Unfinished syncs are kept so they can be resumed.
+        if SyncState::read(&path)?.is_some_and(|x| !x.is_complete()) {
+            continue;
+        }
+        if dry_run {
+            info!("would prune `{}`", path.to_string_lossy());
+        } else {
+            info!("pruning `{}`", path.to_string_lossy());
+            if let Err(e) = fs::remove_dir_all(&path) {
+                warn!("failed to prune `{}`: {e}", path.to_string_lossy());
+                continue;
+            }
+        }
+        pruned.push(path);
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn run(year: i32, month: u32, day: u32, hour: u32) -> (PathBuf, DateTime<Utc>) {
+        let date = Utc
+            .with_ymd_and_hms(year, month, day, hour, 0, 0)
+            .single()
+            .unwrap_or_default();
+        (PathBuf::from(date.to_rfc3339()), date)
+    }
+
+    fn kept(retention: &Retention, runs: &[(PathBuf, DateTime<Utc>)]) -> Vec<usize> {
+        let now = run(2024, 3, 10, 12).1;
+        let keep = retention.keep(runs, now);
+        (0..runs.len())
+            .filter(|i| keep.contains(&runs[*i].0))
+            .collect()
+    }
+
+    #[test]
+    fn keep_runs() {
+        let runs = [
+            run(2023, 2, 1, 0),
+            run(2023, 4, 1, 0),
+            run(2023, 4, 2, 0),
+            run(2024, 2, 20, 0),
+            run(2024, 3, 9, 0),
+            run(2024, 3, 9, 6),
+            run(2024, 3, 10, 0),
+            run(2024, 3, 10, 6),
+        ];
+
+        assert_eq!(
+            (0..runs.len()).collect::<Vec<_>>(),
+            kept(&Retention::default(), &runs)
+        );
+
+        let keep_last = Retention {
+            keep_last: 3,
+            ..Retention::default()
+        };
+        assert_eq!(vec![5, 6, 7], kept(&keep_last, &runs));
+
+        let keep_daily = Retention {
+            keep_daily: 2,
+            ..Retention::default()
+        };
+        assert_eq!(vec![5, 7], kept(&keep_daily, &runs));
+
+        let keep_monthly = Retention {
+            keep_monthly: 12,
+            ..Retention::default()
+        };
+        assert_eq!(vec![2, 3, 7], kept(&keep_monthly, &runs));
+
+        let all = Retention {
+            keep_last: 1,
+            keep_daily: 30,
+            keep_monthly: 12,
+        };
+        assert_eq!(vec![2, 3, 5, 7], kept(&all, &runs));
+    }
+}
@@ -1,17 +1,24 @@
 use std::{
     fs::{self, File},
-    io::BufReader,
+    io::{BufReader, Write},
     path::{Path, PathBuf},
 };
 
 use chrono::{DateTime, FixedOffset, Utc};
 use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use itertools::Itertools;
 use serde::{de::DeserializeOwned, Serialize};
 use tracing::{debug, error, info};
 
 use crate::{
     cmd,
-    frontend::config::Config,
+    frontend::{
+        archive,
+        config::Config,
+        crypto::{Cipher, Header, Secret},
+        manifest::{Manifest, Parameters, COMPONENTS, VERSION},
+        sync_state::SyncState,
+    },
     model::{UpAccount, UpTransaction, YnabAccount, YnabTransaction},
     YnabBudget,
 };
@@ -24,12 +31,40 @@ pub struct Run {
     pub ynab_transactions: Option<Vec<YnabTransaction>>,
     pub ynab_accounts: Option<Vec<YnabAccount>>,
     pub ynab_budgets: Option<Vec<YnabBudget>>,
+    pub manifest: Option<Manifest>,
+    pub layout: Layout,
+    /// Set for encrypted runs.
+    pub cipher: Option<Cipher>,
+}
+
+/// How a run's components are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// A directory per component, with a pretty-printed JSON file per entry.
+    #[default]
+    Directory,
+    /// A zstd-compressed JSON Lines file per component.
+    Archive,
 }
 
 impl Default for Run {
     fn default() -> Self {
+        Self::in_dir("runs")
+    }
+}
+
+impl Run {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new run inside the given runs directory.
+    #[must_use]
+    pub fn in_dir<P: AsRef<Path>>(dir: P) -> Self {
         let date = Utc::now().to_rfc3339();
-        let path = PathBuf::from(format!("runs/{date}"));
+        let path = dir.as_ref().join(date);
         info!("starting new run at `{}`", path.to_string_lossy());
 
         Self {
@@ -39,23 +74,18 @@ impl Default for Run {
             ynab_transactions: None,
             ynab_accounts: None,
             ynab_budgets: None,
+            manifest: None,
+            layout: Layout::default(),
+            cipher: None,
         }
     }
-}
-
-impl Run {
-    #[must_use]
-    pub fn new() -> Self {
-        Self::default()
-    }
 
     pub async fn fetch(
         config: &Config,
         since: Option<DateTime<FixedOffset>>,
         until: Option<DateTime<FixedOffset>>,
     ) -> Result<Self> {
-        let mut run = Self::new();
-
+        let fetched_at = Utc::now();
         let (up_accounts, up_transactions, ynab_accounts, ynab_transactions, ynab_budgets) = tokio::try_join!(
             cmd::get::account::up(config),
             cmd::get::transaction::up(config, cmd::get::transaction::UpArgs { since, until }),
@@ -64,19 +94,57 @@ impl Run {
             cmd::get::budget::ynab(config),
         )?;
 
-        run.write_up_transactions(&up_transactions)?;
-        run.write_ynab_accounts(&ynab_accounts)?;
-        run.write_ynab_transactions(&ynab_transactions)?;
-        run.write_ynab_budgets(&ynab_budgets)?;
-
+        let mut run = Self::in_dir(config.runs_dir());
         run.up_accounts = Some(up_accounts);
         run.up_transactions = Some(up_transactions);
         run.ynab_accounts = Some(ynab_accounts);
         run.ynab_transactions = Some(ynab_transactions);
         run.ynab_budgets = Some(ynab_budgets);
+        run.save(config, fetched_at, since, until)?;
         Ok(run)
     }
 
+    /// Writes a new run's entries in the profile's layout and encryption, then its manifest.
+    pub fn save(
+        &mut self,
+        config: &Config,
+        fetched_at: DateTime<Utc>,
+        since: Option<DateTime<FixedOffset>>,
+        until: Option<DateTime<FixedOffset>>,
+    ) -> Result<()> {
+        self.layout = config.run_layout;
+        let encryption = config
+            .secret()?
+            .map(|secret| Header::new(&secret))
+            .transpose()?
+            .map(|(header, cipher)| {
+                self.cipher = Some(cipher);
+                header
+            });
+
+        self.write()?;
+        self.ynab_budgets
+            .as_ref()
+            .map(|x| Self::write_ynab_budgets(self, x))
+            .transpose()?;
+
+        // The manifest is written last, describing the entries on disk.
+        let manifest = Manifest::new(
+            self,
+            encryption,
+            fetched_at,
+            Parameters {
+                profile: config.name.clone(),
+                budget_id: config.ynab.budget_id.clone(),
+                since,
+                until,
+            },
+        )?;
+        manifest.write(&self.path)?;
+        self.manifest = Some(manifest);
+        Ok(())
+    }
+
     pub fn write(&self) -> Result<()> {
         self.up_accounts
             .as_ref()
@@ -102,114 +170,333 @@ impl Run {
     }
 
     pub fn write_up_transactions(&self, transactions: &[UpTransaction]) -> Result<()> {
-        let path = self.path.join("up_transactions");
-        Self::write_entries::<UpTransaction, _, _>(&path, transactions, |x| {
-            PathBuf::from(&format!("{}-{}.json", x.attributes.created_at, x.id))
-        })?;
-        debug!("wrote up transactions to {}", path.to_string_lossy());
-        Ok(())
+        self.write_component("up_transactions", transactions, |x| {
+            format!("{}-{}.json", x.attributes.created_at, x.id)
+        })
     }
 
     pub fn write_up_accounts(&self, accounts: &[UpAccount]) -> Result<()> {
-        let path = self.path.join("up_accounts");
-        Self::write_entries::<UpAccount, _, _>(&path, accounts, |x| {
-            PathBuf::from(&format!("{}.json", x.id))
-        })?;
-        debug!("wrote up accounts to {}", path.to_string_lossy());
-        Ok(())
+        self.write_component("up_accounts", accounts, |x| format!("{}.json", x.id))
     }
 
     pub fn write_ynab_accounts(&self, accounts: &[YnabAccount]) -> Result<()> {
-        let path = self.path.join("ynab_accounts");
-        Self::write_entries::<YnabAccount, _, _>(&path, accounts, |x| {
-            PathBuf::from(&format!("{}.json", x.id))
-        })?;
-        debug!("wrote ynab accounts to {}", path.to_string_lossy());
-        Ok(())
+        self.write_component("ynab_accounts", accounts, |x| format!("{}.json", x.id))
     }
 
     pub fn write_ynab_transactions(&self, transactions: &[YnabTransaction]) -> Result<()> {
-        let path = self.path.join("ynab_transactions");
-        Self::write_entries::<YnabTransaction, _, _>(&path, transactions, |x| {
-            PathBuf::from(&format!("{}-{}.json", x.date, x.id))
-        })?;
-        debug!("wrote ynab transactions to {}", path.to_string_lossy());
-        Ok(())
+        self.write_component("ynab_transactions", transactions, |x| {
+            format!("{}-{}.json", x.date, x.id)
+        })
     }
 
     pub fn write_ynab_budgets(&self, budgets: &[YnabBudget]) -> Result<()> {
-        let path = self.path.join("ynab_budgets");
-        Self::write_entries::<YnabBudget, _, _>(&path, budgets, |x| {
-            PathBuf::from(&format!("{}.json", x.id))
-        })?;
-        debug!("wrote ynab transactions to {}", path.to_string_lossy());
+        self.write_component("ynab_budgets", budgets, |x| format!("{}.json", x.id))
+    }
+
+    /// Writes a component in the run's layout, adding to the entries already written. Entries are
+    /// named by `f`, archives store them in name order.
+    fn write_component<T: Serialize + DeserializeOwned, F: Fn(&T) -> String>(
+        &self,
+        name: &str,
+        entries: &[T],
+        f: F,
+    ) -> Result<()> {
+        match self.layout {
+            Layout::Directory => {
+                let path = self.path.join(name);
+                Self::write_entries::<T, _, _>(&path, entries, self.cipher.as_ref(), |x| {
+                    PathBuf::from(f(x))
+                })?;
+                debug!("wrote {name} to {}", path.to_string_lossy());
+            }
+            Layout::Archive => {
+                let path = archive::path(&self.path, name);
+                let existing = if path.exists() {
+                    archive::read::<T, _>(&path, self.cipher.as_ref())?
+                } else {
+                    Vec::new()
+                };
+                let entries = existing
+                    .iter()
+                    .chain(entries)
+                    .sorted_by_cached_key(|x| f(x))
+                    .collect::<Vec<_>>();
+                // Entries are never replaced, as in the directory layout.
+                if let Some(key) = entries.iter().map(|x| f(x)).duplicates().next() {
+                    return Err(eyre!(
+                        "entry `{key}` already exists in `{}`",
+                        path.to_string_lossy()
+                    ));
+                }
+                archive::write(&path, &entries, self.cipher.as_ref())?;
+                debug!("wrote {name} to {}", path.to_string_lossy());
+            }
+        }
         Ok(())
     }
 
-    fn read_up_transactions<P: AsRef<Path>>(path: P) -> Result<Vec<UpTransaction>> {
-        Self::read_entries::<UpTransaction, _>(path.as_ref().join("up_transactions"))
+    /// Updates the manifest to describe the entries written since it was created.
+    pub fn refresh_manifest(&mut self) -> Result<()> {
+        if let Some(manifest) = &mut self.manifest {
+            manifest.components =
+                Manifest::components(&self.path, self.layout, self.cipher.as_ref())?;
+            manifest.write(&self.path)?;
+        }
+        Ok(())
     }
 
-    fn read_up_accounts<P: AsRef<Path>>(path: P) -> Result<Vec<UpAccount>> {
-        Self::read_entries::<UpAccount, _>(path.as_ref().join("up_accounts"))
+    fn read_component<T: DeserializeOwned, P: AsRef<Path>>(
+        path: P,
+        name: &str,
+        layout: Layout,
+        cipher: Option<&Cipher>,
+    ) -> Result<Vec<T>> {
+        match layout {
+            Layout::Directory => Self::read_entries(path.as_ref().join(name), cipher),
+            Layout::Archive => {
+                let path = archive::path(&path, name);
+                if !path.exists() {
+                    error!("run component `{name}` missing, skipping...");
+                    return Ok(Vec::new());
+                }
+                archive::read(path, cipher)
+            }
+        }
     }
 
-    fn read_ynab_transactions<P: AsRef<Path>>(path: P) -> Result<Vec<YnabTransaction>> {
-        Self::read_entries::<YnabTransaction, _>(path.as_ref().join("ynab_transactions"))
+    /// Converts a directory run into archives. The manifest is switched to the archive layout
+    /// once every archive is written, so an interrupted pack leaves the directories in use.
+    pub fn pack<P: AsRef<Path>>(path: P, secret: Option<&Secret>) -> Result<Manifest> {
+        let path = path.as_ref();
+        let (mut manifest, cipher) = Manifest::open(path, secret)?;
+        if manifest.layout == Layout::Directory {
+            for name in COMPONENTS {
+                let entries =
+                    Self::read_entries::<serde_json::Value, _>(path.join(name), cipher.as_ref())?;
+                archive::write(archive::path(path, name), &entries, cipher.as_ref())?;
+            }
+
+            let components = Manifest::components(path, Layout::Archive, cipher.as_ref())?;
+            for (name, component) in &components {
+                let count = manifest.components.get(name).map(|x| x.count);
+                if count.is_some_and(|x| x != component.count) {
+                    return Err(eyre!(
+                        "packed `{name}` has {} entries, expected {count:?}",
+                        component.count
+                    ));
+                }
+            }
+            manifest.layout = Layout::Archive;
+            manifest.components = components;
+            manifest.write(path)?;
+        }
+
+        for name in COMPONENTS {
+            let dir = path.join(name);
+            if dir.exists() {
+                fs::remove_dir_all(&dir)
+                    .wrap_err(format!("failed to remove `{}`", dir.to_string_lossy()))?;
+            }
+        }
+        Ok(manifest)
     }
 
-    fn read_ynab_accounts<P: AsRef<Path>>(path: P) -> Result<Vec<YnabAccount>> {
-        Self::read_entries::<YnabAccount, _>(path.as_ref().join("ynab_accounts"))
+    /// Lists the runs in the runs directory, oldest first.
+    pub fn list<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>> {
+        let dir_str = dir.as_ref().to_string_lossy();
+        if !dir.as_ref().exists() {
+            return Ok(Vec::new());
+        }
+
+        // Profile directories share the default profile's runs directory, runs are named by date.
+        let mut runs = fs::read_dir(&dir)
+            .wrap_err(format!("failed to list runs in `{dir_str}`"))?
+            .map(|x| Ok(x?.path()))
+            .filter_ok(|x| {
+                x.is_dir()
+                    && x.file_name()
+                        .and_then(|x| x.to_str())
+                        .is_some_and(|x| DateTime::parse_from_rfc3339(x).is_ok())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        runs.sort();
+        Ok(runs)
     }
 
-    fn read_ynab_budgets<P: AsRef<Path>>(path: P) -> Result<Vec<YnabBudget>> {
-        Self::read_entries::<YnabBudget, _>(path.as_ref().join("ynab_budgets"))
+    /// Reads a run, the secret is required for encrypted runs.
+    pub fn read<P: AsRef<Path>>(path: P, secret: Option<&Secret>) -> Result<Run> {
+        info!("opening run: `{}`", path.as_ref().to_string_lossy());
+        let path = path.as_ref();
+        let (manifest, cipher) = Manifest::open(path, secret)?;
+        let layout = manifest.layout;
+
+        Ok(Run {
+            path: path.to_path_buf(),
+            up_transactions: Some(Self::read_component(
+                path,
+                "up_transactions",
+                layout,
+                cipher.as_ref(),
+            )?),
+            up_accounts: Some(Self::read_component(
+                path,
+                "up_accounts",
+                layout,
+                cipher.as_ref(),
+            )?),
+            ynab_transactions: Some(Self::read_component(
+                path,
+                "ynab_transactions",
+                layout,
+                cipher.as_ref(),
+            )?),
+            ynab_accounts: Some(Self::read_component(
+                path,
+                "ynab_accounts",
+                layout,
+                cipher.as_ref(),
+            )?),
+            ynab_budgets: Some(Self::read_component(
+                path,
+                "ynab_budgets",
+                layout,
+                cipher.as_ref(),
+            )?),
+            layout,
+            manifest: Some(manifest),
+            cipher,
+        })
     }
 
-    pub fn read<P: AsRef<Path>>(path: P) -> Result<Run> {
-        info!("opening run: `{}", path.as_ref().to_string_lossy());
-        if !path.as_ref().exists() {
+    /// Encrypts a run in place. The encrypted copy is written next to the run and fully read back
+    /// before it replaces the run.
+    pub fn encrypt<P: AsRef<Path>>(path: P, secret: &Secret) -> Result<Manifest> {
+        let path = path.as_ref();
+        let (manifest, _) = Manifest::open(path, Some(secret))?;
+        if manifest.encryption.is_some() {
+            info!("`{}` is already encrypted", path.to_string_lossy());
+            return Ok(manifest);
+        }
+        // The sync's plan is stored in plain text, and would be left behind.
+        if SyncState::read(path)?.is_some_and(|x| !x.is_complete()) {
             return Err(eyre!(
-                "run missing at path `{}`",
-                path.as_ref().to_string_lossy()
+                "`{}` has an unfinished sync, resume it before encrypting",
+                path.to_string_lossy()
             ));
         }
 
-        Ok(Run {
-            path: path.as_ref().to_path_buf(),
-            up_transactions: Some(Self::read_up_transactions(path.as_ref())?),
-            up_accounts: Some(Self::read_up_accounts(path.as_ref())?),
-            ynab_transactions: Some(Self::read_ynab_transactions(path.as_ref())?),
-            ynab_accounts: Some(Self::read_ynab_accounts(path.as_ref())?),
-            ynab_budgets: Some(Self::read_ynab_budgets(path.as_ref())?),
-        })
+        let name = archive::file_name(path);
+        let encrypted_path = path.with_file_name(format!("{name}.encrypting"));
+        let plain_path = path.with_file_name(format!("{name}.plain"));
+        if encrypted_path.exists() {
+            fs::remove_dir_all(&encrypted_path)?;
+        }
+
+        // Files are sealed as they are, so the layout and contents are kept.
+        let (header, cipher) = Header::new(secret)?;
+        for component in COMPONENTS {
+            let files = match manifest.layout {
+                Layout::Directory => {
+                    let dir = path.join(component);
+                    if dir.exists() {
+                        fs::read_dir(&dir)?
+                            .map(|x| Ok(x?.path()))
+                            .collect::<Result<Vec<_>>>()?
+                    } else {
+                        Vec::new()
+                    }
+                }
+                Layout::Archive => {
+                    let path = archive::path(path, component);
+                    if path.exists() {
+                        vec![path]
+                    } else {
+                        Vec::new()
+                    }
+                }
+            };
+            for file in files {
+                let relative = file.strip_prefix(path)?;
+                let sealed = cipher.seal(&archive::file_name(&file), &fs::read(&file)?)?;
+                let to = encrypted_path.join(relative);
+                fs::create_dir_all(to.parent().wrap_err("unable to get parent")?)?;
+                fs::write(&to, sealed)
+                    .wrap_err(format!("failed to write `{}`", to.to_string_lossy()))?;
+            }
+        }
+
+        let encrypted = Manifest {
+            version: VERSION,
+            encryption: Some(header),
+            components: Manifest::components(&encrypted_path, manifest.layout, Some(&cipher))?,
+            ..manifest.clone()
+        };
+        let counts = |x: &Manifest| x.components.values().map(|x| x.count).collect::<Vec<_>>();
+        if counts(&encrypted) != counts(&manifest) {
+            return Err(eyre!("encrypted run's entries don't match the original's"));
+        }
+        encrypted.write(&encrypted_path)?;
+        Self::read(&encrypted_path, Some(secret))
+            .wrap_err("failed to verify encrypted run, the original is unchanged")?;
+
+        fs::rename(path, &plain_path)?;
+        fs::rename(&encrypted_path, path)?;
+        fs::remove_dir_all(&plain_path).wrap_err(format!(
+            "failed to remove `{}`",
+            plain_path.to_string_lossy()
+        ))?;
+        Ok(encrypted)
     }
 
-    fn write_entry<T: Serialize, P: AsRef<Path>>(path: P, entry: &T) -> Result<()> {
+    fn write_entry<T: Serialize, P: AsRef<Path>>(
+        path: P,
+        entry: &T,
+        cipher: Option<&Cipher>,
+    ) -> Result<()> {
         let path_str = path.as_ref().to_string_lossy();
         fs::create_dir_all(path.as_ref().parent().wrap_err("unable to get parent")?)?;
-        let file = File::create_new(&path)
+        let mut file = File::create_new(&path)
             .wrap_err(format!("failed to create directory for `{path_str}`"))?;
-        serde_json::to_writer_pretty(file, &entry)
-            .wrap_err(format!("failed to write entry to {path_str}"))?;
+        if let Some(cipher) = cipher {
+            let contents = serde_json::to_vec_pretty(&entry)?;
+            let sealed = cipher.seal(&archive::file_name(path.as_ref()), &contents)?;
+            file.write_all(&sealed)
+                .wrap_err(format!("failed to write entry to {path_str}"))?;
+        } else {
+            serde_json::to_writer_pretty(file, &entry)
+                .wrap_err(format!("failed to write entry to {path_str}"))?;
+        }
         Ok(())
     }
 
     fn write_entries<T: Serialize, P: AsRef<Path>, F: Fn(&T) -> PathBuf>(
         path: P,
         entries: &[T],
+        cipher: Option<&Cipher>,
         f: F,
     ) -> Result<()> {
         let path_str = path.as_ref().to_string_lossy();
         entries
             .iter()
-            .try_for_each(|entry| Self::write_entry::<T, _>(path.as_ref().join(f(entry)), entry))
+            .try_for_each(|entry| {
+                Self::write_entry::<T, _>(path.as_ref().join(f(entry)), entry, cipher)
+            })
             .wrap_err(format!("failed to write `{path_str}`"))?;
         Ok(())
     }
 
-    fn read_entry<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    fn read_entry<T: DeserializeOwned, P: AsRef<Path>>(
+        path: P,
+        cipher: Option<&Cipher>,
+    ) -> Result<T> {
         let path_str = path.as_ref().to_string_lossy();
+        if let Some(cipher) = cipher {
+            let sealed = fs::read(&path).wrap_err(format!("failed to open `{path_str}`"))?;
+            let contents = cipher.open(&archive::file_name(path.as_ref()), &sealed)?;
+            return serde_json::from_slice(&contents)
+                .wrap_err(format!("failed to parse `{path_str}`"));
+        }
+
         let entry = File::open(&path)
             .map(BufReader::new)
             .map(serde_json::from_reader::<_, T>)
@@ -217,7 +504,10 @@ impl Run {
         Ok(entry)
     }
 
-    fn read_entries<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<Vec<T>> {
+    fn read_entries<T: DeserializeOwned, P: AsRef<Path>>(
+        path: P,
+        cipher: Option<&Cipher>,
+    ) -> Result<Vec<T>> {
         let path_str = path.as_ref().to_string_lossy();
         if !path.as_ref().exists() {
             error!(
@@ -230,11 +520,124 @@ impl Run {
             return Ok(Vec::new());
         }
 
+        // Entries are read in file name order, which is the order archives store them in.
         let entries = fs::read_dir(&path)
             .wrap_err(format!("failed to load run `{path_str}`",))?
-            .map(|file_path| Self::read_entry::<T, _>(file_path?.path()))
+            .map(|x| Ok(x?.path()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .sorted()
+            .map(|x| Self::read_entry::<T, _>(x, cipher))
             .collect::<Result<Vec<_>>>()
             .wrap_err(format!("failed to parse `{path_str}`"))?;
         Ok(entries)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cmd::fixture::Fixture;
+
+    fn ids(run: &Run) -> Vec<String> {
+        run.up_transactions
+            .iter()
+            .flatten()
+            .map(|x| x.id.clone())
+            .sorted()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn archive_layout() -> Result<()> {
+        let mut fixture = Fixture::new("run_archive_layout")?;
+        fixture.config.run_layout = Layout::Archive;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+
+        assert!(archive::path(&run.path, "up_transactions").exists());
+        assert!(!run.path.join("up_transactions").exists());
+        let read = Run::read(&run.path, None)?;
+        assert_eq!(Layout::Archive, read.layout);
+        assert_eq!(ids(&run), ids(&read));
+
+        // Written entries can't be written again.
+        let up_transactions = read
+            .up_transactions
+            .iter()
+            .flatten()
+            .take(1)
+            .cloned()
+            .collect_vec();
+        assert!(read.write_up_transactions(&up_transactions).is_err());
+        assert_eq!(ids(&run), ids(&Run::read(&run.path, None)?));
+
+        assert_eq!(
+            run.ynab_accounts.map(|x| x.len()),
+            read.ynab_accounts.map(|x| x.len())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pack_run() -> Result<()> {
+        let fixture = Fixture::new("run_pack_run")?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+        assert_eq!(
+            vec![run.path.clone()],
+            Run::list(fixture.config.runs_dir())?
+        );
+
+        let manifest = Run::pack(&run.path, None)?;
+        assert_eq!(Layout::Archive, manifest.layout);
+        assert!(COMPONENTS.iter().all(|x| !run.path.join(x).exists()));
+        assert_eq!(ids(&run), ids(&Run::read(&run.path, None)?));
+
+        // Packing is idempotent.
+        assert_eq!(manifest, Run::pack(&run.path, None)?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encrypted_run() -> Result<()> {
+        let fixture = Fixture::with_config(
+            "run_encrypted_run",
+            r#"
+            [encryption]
+            passphrase = "hunter2"
+            "#,
+        )?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+        let secret = fixture.config.secret()?;
+
+        let entry = fs::read_dir(run.path.join("up_transactions"))?
+            .next()
+            .wrap_err("missing entry")??;
+        assert!(serde_json::from_slice::<serde_json::Value>(&fs::read(entry.path())?).is_err());
+        assert_eq!(ids(&run), ids(&Run::read(&run.path, secret.as_ref())?));
+        assert!(Run::read(&run.path, None).is_err());
+        assert!(Run::read(&run.path, Some(&Secret::new(b"hunter3".to_vec()))).is_err());
+
+        // Encrypted runs can be packed.
+        Run::pack(&run.path, secret.as_ref())?;
+        assert_eq!(ids(&run), ids(&Run::read(&run.path, secret.as_ref())?));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encrypt_run() -> Result<()> {
+        let fixture = Fixture::new("run_encrypt_run")?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+        let secret = Secret::new(b"hunter2".to_vec());
+
+        let manifest = Run::encrypt(&run.path, &secret)?;
+        assert!(manifest.encryption.is_some());
+        assert_eq!(
+            vec![run.path.clone()],
+            Run::list(fixture.config.runs_dir())?
+        );
+        assert!(Run::read(&run.path, None).is_err());
+        assert_eq!(ids(&run), ids(&Run::read(&run.path, Some(&secret))?));
+        assert_eq!(manifest, Run::encrypt(&run.path, &secret)?);
+        Ok(())
+    }
+}
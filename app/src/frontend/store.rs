@@ -0,0 +1,804 @@
+//! Store of Up and YNAB data in an embedded database, updated by every sync. Runs are imported
+//! into it and exported from it.
+
+use std::{fs, path::Path, time::Duration};
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use color_eyre::eyre::{eyre, Context, Result};
+use indoc::indoc;
+use rusqlite::{params, types::Value, Connection, OptionalExtension, Params};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    frontend::{
+        crypto::{Cipher, Header, Secret},
+        run::Run,
+    },
+    model::{UpCategory, UpTag, UpTransaction, YnabCategory, YnabTransaction},
+};
+
+/// Each migration upgrades the schema from the version before it. The schema version is kept in
+/// `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[indoc! {"
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE up_accounts (
+        id TEXT PRIMARY KEY,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+
+    CREATE TABLE up_transactions (
+        id TEXT PRIMARY KEY,
+        account_id TEXT NOT NULL,
+        category_id TEXT,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+    CREATE INDEX up_transactions_created_at ON up_transactions (created_at);
+
+    CREATE TABLE up_transaction_statuses (
+        transaction_id TEXT NOT NULL REFERENCES up_transactions (id) ON DELETE CASCADE,
+        status TEXT NOT NULL,
+        seen_at TEXT NOT NULL,
+        PRIMARY KEY (transaction_id, status)
+    );
+
+    CREATE TABLE up_categories (
+        id TEXT PRIMARY KEY,
+        parent_id TEXT,
+        data BLOB,
+        fetched_at TEXT
+    );
+
+    CREATE TABLE up_tags (
+        id TEXT PRIMARY KEY
+    );
+
+    CREATE TABLE up_transaction_tags (
+        transaction_id TEXT NOT NULL REFERENCES up_transactions (id) ON DELETE CASCADE,
+        tag_id TEXT NOT NULL REFERENCES up_tags (id),
+        PRIMARY KEY (transaction_id, tag_id)
+    );
+
+    CREATE TABLE ynab_budgets (
+        id TEXT PRIMARY KEY,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+
+    CREATE TABLE ynab_accounts (
+        id TEXT PRIMARY KEY,
+        budget_id TEXT NOT NULL,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+
+    CREATE TABLE ynab_transactions (
+        id TEXT PRIMARY KEY,
+        budget_id TEXT NOT NULL,
+        account_id TEXT NOT NULL,
+        date TEXT NOT NULL,
+        import_id TEXT,
+        deleted INTEGER NOT NULL,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+    CREATE INDEX ynab_transactions_import_id ON ynab_transactions (import_id);
+
+    CREATE TABLE ynab_categories (
+        id TEXT PRIMARY KEY,
+        budget_id TEXT NOT NULL,
+        group_id TEXT NOT NULL,
+        data BLOB NOT NULL,
+        fetched_at TEXT NOT NULL
+    );
+
+    -- Synced YNAB transactions are imported with their Up transaction's id.
+    CREATE VIEW transaction_mappings AS
+    SELECT u.id AS up_transaction_id, y.id AS ynab_transaction_id, y.budget_id
+    FROM up_transactions u
+    JOIN ynab_transactions y ON y.import_id = u.id
+    WHERE NOT y.deleted;
+"}];
+
+/// Tables holding entries as JSON, sealed when the store is encrypted. Only the entries are
+/// sealed, the ids, accounts, categories, statuses, dates and tags they're queried by are not.
+const DATA_TABLES: [&str; 7] = [
+    "up_accounts",
+    "up_transactions",
+    "up_categories",
+    "ynab_budgets",
+    "ynab_accounts",
+    "ynab_transactions",
+    "ynab_categories",
+];
+
+/// Every table, in the order `store info` lists them.
+pub const TABLES: [&str; 10] = [
+    "up_accounts",
+    "up_transactions",
+    "up_transaction_statuses",
+    "up_categories",
+    "up_tags",
+    "up_transaction_tags",
+    "ynab_budgets",
+    "ynab_accounts",
+    "ynab_transactions",
+    "ynab_categories",
+];
+
+pub struct Store {
+    conn: Connection,
+    /// Set for encrypted stores.
+    cipher: Option<Cipher>,
+}
+
+/// When an Up transaction was first seen with a status.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusChange {
+    pub status: String,
+    pub seen_at: DateTime<Utc>,
+}
+
+/// A YNAB transaction synced from an Up transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    pub up_transaction_id: String,
+    pub ynab_transaction_id: String,
+    pub budget_id: Uuid,
+}
+
+impl Store {
+    /// Opens the store, creating it if missing. The secret is required once the store is
+    /// encrypted, and encrypts a plain store's entries the first time it's given.
+    pub fn open<P: AsRef<Path>>(path: P, secret: Option<&Secret>) -> Result<Self> {
+        let path = path.as_ref();
+        let path_str = path.to_string_lossy();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path).wrap_err(format!("failed to open store `{path_str}`"))?;
+        // Profiles sharing a store may update it at the same time.
+        conn.busy_timeout(Duration::from_secs(10))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let mut store = Self { conn, cipher: None };
+        store
+            .migrate()
+            .wrap_err(format!("failed to migrate store `{path_str}`"))?;
+        store.cipher = store.cipher(secret)?;
+        Ok(store)
+    }
+
+    fn migrate(&mut self) -> Result<()> {
+        let version = self
+            .conn
+            .query_row("PRAGMA user_version", [], |x| x.get::<_, usize>(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(eyre!(
+                "store version {version} is newer than the supported version {}, update to open it",
+                MIGRATIONS.len()
+            ));
+        }
+
+        let tx = self.conn.transaction()?;
+        for migration in &MIGRATIONS[version..] {
+            tx.execute_batch(migration)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn cipher(&mut self, secret: Option<&Secret>) -> Result<Option<Cipher>> {
+        let header = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'encryption'", [], |x| {
+                x.get::<_, String>(0)
+            })
+            .optional()?
+            .map(|x| serde_json::from_str::<Header>(&x))
+            .transpose()
+            .wrap_err("failed to parse store encryption header")?;
+
+        match (header, secret) {
+            (None, None) => Ok(None),
+            (Some(header), Some(secret)) => header.cipher(secret).map(Some),
+            (Some(_), None) => Err(eyre!(
+                "store is encrypted, set a passphrase or key file under `[encryption]`"
+            )),
+            (None, Some(secret)) => {
+                let (header, cipher) = Header::new(secret)?;
+                self.encrypt(&header, &cipher)?;
+                Ok(Some(cipher))
+            }
+        }
+    }
+
+    /// Seals every entry, recording the header in the same transaction.
+    fn encrypt(&mut self, header: &Header, cipher: &Cipher) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for table in DATA_TABLES {
+            let rows = tx
+                .prepare(&format!(
+                    "SELECT id, data FROM {table} WHERE data IS NOT NULL"
+                ))?
+                .query_map([], |x| Ok((x.get::<_, String>(0)?, x.get::<_, Value>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (id, data) in rows {
+                let sealed = cipher.seal(&aad(table, &id), &bytes(data))?;
+                tx.execute(
+                    &format!("UPDATE {table} SET data = ?1 WHERE id = ?2"),
+                    params![sealed, id],
+                )?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES ('encryption', ?1)",
+            [serde_json::to_string(header)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Adds the run's entries, its YNAB entries belonging to the budget. Entries fetched after
+    /// `fetched_at` are kept, so runs can be imported in any order.
+    pub fn import_run(
+        &mut self,
+        run: &Run,
+        budget_id: Uuid,
+        fetched_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let cipher = self.cipher.as_ref();
+        let fetched_at = timestamp(fetched_at);
+        let budget_id = budget_id.to_string();
+        let tx = self.conn.transaction()?;
+
+        for account in run.up_accounts.iter().flatten() {
+            tx.execute(
+                "INSERT INTO up_accounts (id, data, fetched_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET data = excluded.data, fetched_at = excluded.fetched_at
+                 WHERE excluded.fetched_at >= fetched_at",
+                params![account.id, encode(cipher, "up_accounts", &account.id, account)?, fetched_at],
+            )?;
+        }
+
+        for transaction in run.up_transactions.iter().flatten() {
+            import_up_transaction(&tx, cipher, transaction, &fetched_at)?;
+        }
+
+        for budget in run.ynab_budgets.iter().flatten() {
+            tx.execute(
+                "INSERT INTO ynab_budgets (id, data, fetched_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (id) DO UPDATE SET data = excluded.data, fetched_at = excluded.fetched_at
+                 WHERE excluded.fetched_at >= fetched_at",
+                params![
+                    budget.id.to_string(),
+                    encode(cipher, "ynab_budgets", &budget.id.to_string(), budget)?,
+                    fetched_at
+                ],
+            )?;
+        }
+
+        for account in run.ynab_accounts.iter().flatten() {
+            tx.execute(
+                "INSERT INTO ynab_accounts (id, budget_id, data, fetched_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO UPDATE SET data = excluded.data, fetched_at = excluded.fetched_at
+                 WHERE excluded.fetched_at >= fetched_at",
+                params![
+                    account.id.to_string(),
+                    budget_id,
+                    encode(cipher, "ynab_accounts", &account.id.to_string(), account)?,
+                    fetched_at
+                ],
+            )?;
+        }
+
+        for transaction in run.ynab_transactions.iter().flatten() {
+            import_ynab_transaction(&tx, cipher, &budget_id, transaction, &fetched_at)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Adds the budget's YNAB transactions, such as those created or updated by a sync.
+    pub fn import_ynab_transactions(
+        &mut self,
+        budget_id: Uuid,
+        transactions: &[YnabTransaction],
+        fetched_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let cipher = self.cipher.as_ref();
+        let fetched_at = timestamp(fetched_at);
+        let budget_id = budget_id.to_string();
+        let tx = self.conn.transaction()?;
+        for transaction in transactions {
+            import_ynab_transaction(&tx, cipher, &budget_id, transaction, &fetched_at)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Adds Up categories and tags, and the budget's YNAB categories.
+    pub fn import_categories(
+        &mut self,
+        budget_id: Uuid,
+        up_categories: &[UpCategory],
+        up_tags: &[UpTag],
+        ynab_categories: &[YnabCategory],
+        fetched_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let cipher = self.cipher.as_ref();
+        let fetched_at = timestamp(fetched_at);
+        let tx = self.conn.transaction()?;
+
+        for category in up_categories {
+            tx.execute(
+                "INSERT INTO up_categories (id, parent_id, data, fetched_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (id) DO UPDATE SET
+                     parent_id = excluded.parent_id,
+                     data = excluded.data,
+                     fetched_at = excluded.fetched_at",
+                params![
+                    category.id,
+                    category.relationships.parent.data.as_ref().map(|x| &x.id),
+                    encode(cipher, "up_categories", &category.id, category)?,
+                    fetched_at
+                ],
+            )?;
+        }
+
+        for tag in up_tags {
+            tx.execute("INSERT OR IGNORE INTO up_tags (id) VALUES (?1)", [&tag.id])?;
+        }
+
+        for category in ynab_categories {
+            tx.execute(
+                "INSERT INTO ynab_categories (id, budget_id, group_id, data, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (id) DO UPDATE SET
+                     group_id = excluded.group_id,
+                     data = excluded.data,
+                     fetched_at = excluded.fetched_at",
+                params![
+                    category.id.to_string(),
+                    budget_id.to_string(),
+                    category.category_group_id.to_string(),
+                    encode(
+                        cipher,
+                        "ynab_categories",
+                        &category.id.to_string(),
+                        category
+                    )?,
+                    fetched_at
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Reads the store into a new run in the runs directory, without writing it. Up transactions
+    /// are filtered by when they were created, YNAB transactions by their date.
+    pub fn export<P: AsRef<Path>>(
+        &self,
+        runs_dir: P,
+        budget_id: Uuid,
+        since: Option<DateTime<FixedOffset>>,
+        until: Option<DateTime<FixedOffset>>,
+    ) -> Result<Run> {
+        let budget_id = budget_id.to_string();
+        let mut run = Run::in_dir(runs_dir);
+        run.up_accounts = Some(self.entries(
+            "up_accounts",
+            "SELECT id, data FROM up_accounts ORDER BY id",
+            [],
+        )?);
+        run.up_transactions = Some(self.entries(
+            "up_transactions",
+            "SELECT id, data FROM up_transactions
+             WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at < ?2)
+             ORDER BY created_at, id",
+            params![
+                since.map(|x| timestamp(x.with_timezone(&Utc))),
+                until.map(|x| timestamp(x.with_timezone(&Utc)))
+            ],
+        )?);
+        run.ynab_budgets = Some(self.entries(
+            "ynab_budgets",
+            "SELECT id, data FROM ynab_budgets ORDER BY id",
+            [],
+        )?);
+        run.ynab_accounts = Some(self.entries(
+            "ynab_accounts",
+            "SELECT id, data FROM ynab_accounts WHERE budget_id = ?1 ORDER BY id",
+            [&budget_id],
+        )?);
+        run.ynab_transactions = Some(self.entries(
+            "ynab_transactions",
+            "SELECT id, data FROM ynab_transactions
+             WHERE budget_id = ?1 AND (?2 IS NULL OR date >= ?2) AND (?3 IS NULL OR date < ?3)
+             ORDER BY date, id",
+            params![
+                budget_id,
+                since.map(|x| x.date_naive().to_string()),
+                until.map(|x| x.date_naive().to_string())
+            ],
+        )?);
+        Ok(run)
+    }
+
+    /// Returns the statuses the transaction was seen with, oldest first.
+    pub fn status_history(&self, transaction_id: &str) -> Result<Vec<StatusChange>> {
+        self.conn
+            .prepare(
+                "SELECT status, seen_at FROM up_transaction_statuses
+                 WHERE transaction_id = ?1 ORDER BY seen_at, status",
+            )?
+            .query_map([transaction_id], |x| {
+                Ok((x.get::<_, String>(0)?, x.get::<_, String>(1)?))
+            })?
+            .map(|x| {
+                let (status, seen_at) = x?;
+                Ok(StatusChange {
+                    status,
+                    seen_at: DateTime::parse_from_rfc3339(&seen_at)?.with_timezone(&Utc),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the budget's YNAB transactions synced from Up transactions.
+    pub fn mappings(&self, budget_id: Uuid) -> Result<Vec<Mapping>> {
+        self.conn
+            .prepare(
+                "SELECT up_transaction_id, ynab_transaction_id FROM transaction_mappings
+                 WHERE budget_id = ?1 ORDER BY up_transaction_id",
+            )?
+            .query_map([budget_id.to_string()], |x| {
+                Ok(Mapping {
+                    up_transaction_id: x.get(0)?,
+                    ynab_transaction_id: x.get(1)?,
+                    budget_id,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(Into::into)
+    }
+
+    /// Returns the number of rows in each table.
+    pub fn counts(&self) -> Result<Vec<(&'static str, usize)>> {
+        TABLES
+            .into_iter()
+            .map(|table| {
+                let count =
+                    self.conn
+                        .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |x| {
+                            x.get::<_, usize>(0)
+                        })?;
+                Ok((table, count))
+            })
+            .collect()
+    }
+
+    /// Whether entries are sealed, the columns they're queried by are always plain text.
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    fn entries<T: DeserializeOwned, P: Params>(
+        &self,
+        table: &str,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<T>> {
+        self.conn
+            .prepare(sql)?
+            .query_map(params, |x| {
+                Ok((x.get::<_, String>(0)?, x.get::<_, Value>(1)?))
+            })?
+            .map(|x| {
+                let (id, data) = x?;
+                decode(self.cipher.as_ref(), table, &id, data)
+            })
+            .collect()
+    }
+}
+
+/// Adds or updates the transaction, recording its status and tags.
+fn import_up_transaction(
+    tx: &Connection,
+    cipher: Option<&Cipher>,
+    transaction: &UpTransaction,
+    fetched_at: &str,
+) -> Result<()> {
+    let attributes = &transaction.attributes;
+    let relationships = &transaction.relationships;
+    let status = serde_json::to_value(attributes.status)?
+        .as_str()
+        .map(ToOwned::to_owned)
+        .unwrap_or_default();
+    let created_at = DateTime::parse_from_rfc3339(&attributes.created_at)
+        .wrap_err(format!(
+            "invalid creation date for up transaction `{}`",
+            transaction.id
+        ))?
+        .with_timezone(&Utc);
+    let category_id = relationships.category.data.as_ref().map(|x| &x.id);
+
+    // Categories are known by id until they're fetched.
+    if let Some(category_id) = category_id {
+        tx.execute(
+            "INSERT OR IGNORE INTO up_categories (id) VALUES (?1)",
+            [category_id],
+        )?;
+    }
+    let updated = tx.execute(
+        "INSERT INTO up_transactions
+             (id, account_id, category_id, status, created_at, data, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (id) DO UPDATE SET
+             account_id = excluded.account_id,
+             category_id = excluded.category_id,
+             status = excluded.status,
+             data = excluded.data,
+             fetched_at = excluded.fetched_at
+         WHERE excluded.fetched_at >= fetched_at",
+        params![
+            transaction.id,
+            relationships.account.data.id,
+            category_id,
+            status,
+            timestamp(created_at),
+            encode(cipher, "up_transactions", &transaction.id, transaction)?,
+            fetched_at
+        ],
+    )?;
+    tx.execute(
+        "INSERT INTO up_transaction_statuses (transaction_id, status, seen_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (transaction_id, status) DO UPDATE SET seen_at = min(seen_at, excluded.seen_at)",
+        params![transaction.id, status, fetched_at],
+    )?;
+
+    // Tags are only replaced by a newer fetch.
+    if updated > 0 {
+        tx.execute(
+            "DELETE FROM up_transaction_tags WHERE transaction_id = ?1",
+            [&transaction.id],
+        )?;
+        for tag in &relationships.tags.data {
+            tx.execute("INSERT OR IGNORE INTO up_tags (id) VALUES (?1)", [&tag.id])?;
+            tx.execute(
+                "INSERT INTO up_transaction_tags (transaction_id, tag_id) VALUES (?1, ?2)",
+                [&transaction.id, &tag.id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds or updates the budget's YNAB transaction, unless a newer fetch is stored.
+fn import_ynab_transaction(
+    tx: &Connection,
+    cipher: Option<&Cipher>,
+    budget_id: &str,
+    transaction: &YnabTransaction,
+    fetched_at: &str,
+) -> Result<()> {
+    tx.execute(
+        "INSERT INTO ynab_transactions
+             (id, budget_id, account_id, date, import_id, deleted, data, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+             account_id = excluded.account_id,
+             date = excluded.date,
+             import_id = excluded.import_id,
+             deleted = excluded.deleted,
+             data = excluded.data,
+             fetched_at = excluded.fetched_at
+         WHERE excluded.fetched_at >= fetched_at",
+        params![
+            transaction.id,
+            budget_id,
+            transaction.account_id.to_string(),
+            transaction.date,
+            transaction.import_id.clone().flatten(),
+            transaction.deleted,
+            encode(cipher, "ynab_transactions", &transaction.id, transaction)?,
+            fetched_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// Timestamps are stored in UTC with a fixed precision, so they sort as text.
+fn timestamp(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Entries are stored as JSON text, or sealed bytes in encrypted stores.
+fn encode<T: Serialize>(
+    cipher: Option<&Cipher>,
+    table: &str,
+    id: &str,
+    entry: &T,
+) -> Result<Value> {
+    let json = serde_json::to_string(entry)?;
+    Ok(match cipher {
+        Some(cipher) => Value::Blob(cipher.seal(&aad(table, id), json.as_bytes())?),
+        None => Value::Text(json),
+    })
+}
+
+fn decode<T: DeserializeOwned>(
+    cipher: Option<&Cipher>,
+    table: &str,
+    id: &str,
+    data: Value,
+) -> Result<T> {
+    let mut contents = bytes(data);
+    if let Some(cipher) = cipher {
+        contents = cipher
+            .open(&aad(table, id), &contents)
+            .wrap_err(format!("failed to decrypt `{id}` in `{table}`"))?;
+    }
+    serde_json::from_slice(&contents).wrap_err(format!("failed to parse `{id}` in `{table}`"))
+}
+
+/// Sealed entries are bound to their row, so they can't be moved to another one.
+fn aad(table: &str, id: &str) -> String {
+    format!("{table}/{id}")
+}
+
+fn bytes(data: Value) -> Vec<u8> {
+    match data {
+        Value::Text(x) => x.into_bytes(),
+        Value::Blob(x) => x,
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Duration;
+    use color_eyre::eyre::ContextCompat;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::cmd::fixture::{Fixture, BUDGET_ID};
+
+    fn budget_id() -> Uuid {
+        Uuid::parse_str(BUDGET_ID).unwrap_or_default()
+    }
+
+    fn ids(transactions: Option<&Vec<UpTransaction>>) -> Vec<String> {
+        transactions
+            .into_iter()
+            .flatten()
+            .map(|x| x.id.clone())
+            .sorted()
+            .collect()
+    }
+
+    /// Returns the transaction with its status set to `HELD`.
+    fn held(transaction: &UpTransaction) -> Result<UpTransaction> {
+        let mut value = serde_json::to_value(transaction)?;
+        value["attributes"]["status"] = "HELD".into();
+        Ok(serde_json::from_value(value)?)
+    }
+
+    #[tokio::test]
+    async fn sync_updates_store() -> Result<()> {
+        let fixture = Fixture::new("store_sync_updates_store")?;
+        fixture.sync().await?;
+
+        // The transactions created by the sync are mapped straight away.
+        let store = Store::open(fixture.config.store_path(), None)?;
+        let mappings = store.mappings(budget_id())?;
+        assert_eq!(2, mappings.len());
+
+        fixture.sync().await?;
+        assert_eq!(mappings, store.mappings(budget_id())?);
+
+        // YNAB transactions are exported by date.
+        let export = |until| store.export(fixture.config.runs_dir(), budget_id(), None, until);
+        let until = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+00:00")?;
+        assert_eq!(Some(2), export(None)?.ynab_transactions.map(|x| x.len()));
+        assert_eq!(
+            Some(0),
+            export(Some(until))?.ynab_transactions.map(|x| x.len())
+        );
+        let history = store.status_history(&mappings[0].up_transaction_id)?;
+        assert_eq!(
+            vec!["SETTLED"],
+            history.iter().map(|x| &x.status).collect_vec()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_export_run() -> Result<()> {
+        let fixture = Fixture::new("store_import_export_run")?;
+        let mut run = Run::fetch(&fixture.config, None, None).await?;
+        let mut store = Store::open(fixture.runs_path.join("import.sqlite"), None)?;
+        let fetched_at = Utc::now();
+        store.import_run(&run, budget_id(), fetched_at)?;
+
+        let exported = store.export(fixture.config.runs_dir(), budget_id(), None, None)?;
+        assert_eq!(
+            ids(run.up_transactions.as_ref()),
+            ids(exported.up_transactions.as_ref())
+        );
+        assert_eq!(
+            run.ynab_accounts.as_ref().map(Vec::len),
+            exported.ynab_accounts.as_ref().map(Vec::len)
+        );
+
+        // An older run adds to the status history without replacing newer entries.
+        let transaction = run
+            .up_transactions
+            .as_ref()
+            .and_then(|x| x.first())
+            .wrap_err("missing transaction")?;
+        let id = transaction.id.clone();
+        run.up_transactions = Some(vec![held(transaction)?]);
+        store.import_run(&run, budget_id(), fetched_at - Duration::hours(1))?;
+        let history = store.status_history(&id)?;
+        assert_eq!(
+            vec!["HELD", "SETTLED"],
+            history.iter().map(|x| &x.status).collect_vec()
+        );
+        let exported = store.export(fixture.config.runs_dir(), budget_id(), None, None)?;
+        assert!(exported
+            .up_transactions
+            .iter()
+            .flatten()
+            .all(|x| serde_json::to_value(x.attributes.status).ok() == Some("SETTLED".into())));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encrypted_store() -> Result<()> {
+        let fixture = Fixture::new("store_encrypted_store")?;
+        let run = Run::fetch(&fixture.config, None, None).await?;
+        let path = fixture.runs_path.join("encrypted.sqlite");
+        Store::open(&path, None)?.import_run(&run, budget_id(), Utc::now())?;
+
+        // Existing entries are encrypted when a secret is first given.
+        let secret = Secret::new(b"hunter2".to_vec());
+        let store = Store::open(&path, Some(&secret))?;
+        assert!(store.is_encrypted());
+        assert!(Store::open(&path, None).is_err());
+        assert!(Store::open(&path, Some(&Secret::new(b"hunter3".to_vec()))).is_err());
+
+        let store = Store::open(&path, Some(&secret))?;
+        let exported = store.export(fixture.config.runs_dir(), budget_id(), None, None)?;
+        assert_eq!(
+            ids(run.up_transactions.as_ref()),
+            ids(exported.up_transactions.as_ref())
+        );
+
+        // Sealed entries only open in the row they were written to.
+        let exported_ids = ids(exported.up_transactions.as_ref());
+        store.conn.execute(
+            "UPDATE up_transactions SET data = (SELECT data FROM up_transactions WHERE id = ?1)
+             WHERE id = ?2",
+            [&exported_ids[0], &exported_ids[1]],
+        )?;
+        assert!(store
+            .export(fixture.config.runs_dir(), budget_id(), None, None)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+//! Progress of a sync, persisted in its run so an interrupted sync can resume where it stopped.
+
+use std::{fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    frontend::{atomic, run::Run},
+    model::{NewYnabTransaction, UpdateYnabTransaction},
+};
+
+/// Name of the state file inside the run, kept in plain text so runs can be pruned without a key.
+const STATE_FILE: &str = "sync_state.json";
+
+/// Name of the plan file inside the run, encrypted with the run.
+const PLAN_FILE: &str = "sync_plan.json";
+
+/// The last completed phase of a sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum Phase {
+    /// The run was fetched or read.
+    Fetched,
+    /// The mutations were planned and the plan written.
+    Planned,
+    /// This many create batches were sent.
+    Created { batches: usize },
+    /// Every create batch and this many update batches were sent.
+    Updated { batches: usize },
+    /// The created transactions were found in YNAB.
+    Verified,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    #[serde(flatten)]
+    pub phase: Phase,
+    pub budget_id: Uuid,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The transactions to create and update, in the batches they're sent in.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub create: Vec<Vec<NewYnabTransaction>>,
+    pub update: Vec<Vec<UpdateYnabTransaction>>,
+}
+
+impl SyncState {
+    #[must_use]
+    pub fn new(budget_id: Uuid) -> Self {
+        Self {
+            phase: Phase::Fetched,
+            budget_id,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.phase == Phase::Verified
+    }
+
+    pub fn read<P: AsRef<Path>>(run_path: P) -> Result<Option<Self>> {
+        let path = run_path.as_ref().join(STATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        parse(&path, &fs::read(&path)?).map(Some)
+    }
+
+    /// Moves to the phase, persisting it before returning.
+    pub fn advance<P: AsRef<Path>>(&mut self, run_path: P, phase: Phase) -> Result<()> {
+        self.phase = phase;
+        self.updated_at = Utc::now();
+        atomic::write(
+            run_path.as_ref().join(STATE_FILE),
+            &serde_json::to_vec_pretty(self)?,
+        )
+    }
+}
+
+impl Plan {
+    pub fn read(run: &Run) -> Result<Self> {
+        let path = run.path.join(PLAN_FILE);
+        let mut contents =
+            fs::read(&path).wrap_err(format!("failed to open `{}`", path.to_string_lossy()))?;
+        if let Some(cipher) = &run.cipher {
+            contents = cipher.open(PLAN_FILE, &contents)?;
+        }
+        parse(&path, &contents)
+    }
+
+    pub fn write(&self, run: &Run) -> Result<()> {
+        let mut contents = serde_json::to_vec_pretty(self)?;
+        if let Some(cipher) = &run.cipher {
+            contents = cipher.seal(PLAN_FILE, &contents)?;
+        }
+        atomic::write(run.path.join(PLAN_FILE), &contents)
+    }
+}
+
+fn parse<T: DeserializeOwned>(path: &Path, contents: &[u8]) -> Result<T> {
+    serde_json::from_slice(contents)
+        .wrap_err(format!("failed to parse `{}`", path.to_string_lossy()))
+}
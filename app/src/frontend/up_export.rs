@@ -0,0 +1,396 @@
+//! Reads Up transactions from the Up app's CSV exports and from JSON dumps of the API, to
+//! backfill runs with history the API no longer returns.
+//!
+//! CSV exports have no ids, so each row gets one hashed from its account, local time, amount and
+//! payee, the same on every import. Their times are local, in a time zone which may observe
+//! daylight saving. They don't record transfer accounts either, so transfers are imported as
+//! external transactions.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use color_eyre::eyre::{eyre, Context, ContextCompat, Result};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::model::{format_cents, parse_cents, UpAccount, UpTransaction};
+
+/// Namespace of the ids given to CSV rows and accounts Up no longer lists.
+const NAMESPACE: Uuid = Uuid::from_u128(0xe47e_e3e6_952e_4e75_9ee4_be0f_818e_8d0e);
+
+/// Formats of the time column, which has no offset.
+const TIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+];
+
+/// Reads the transactions of a CSV export, or a JSON dump by extension.
+///
+/// CSV rows belong to `account_id`, times without an offset are local to `timezone`.
+pub fn read<P: AsRef<Path>>(
+    path: P,
+    account_id: Option<&str>,
+    timezone: Tz,
+) -> Result<Vec<UpTransaction>> {
+    let path = path.as_ref();
+    let path_str = path.to_string_lossy();
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("csv") => {
+            let account_id = account_id.wrap_err(format!(
+                "missing account for `{path_str}`, CSV exports don't name theirs"
+            ))?;
+            let file = File::open(path).wrap_err(format!("failed to open `{path_str}`"))?;
+            read_csv(file, account_id, timezone)
+        }
+        Some("json" | "jsonl") => {
+            let contents =
+                fs::read_to_string(path).wrap_err(format!("failed to open `{path_str}`"))?;
+            read_json(&contents)
+        }
+        _ => Err(eyre!("unknown export format for `{path_str}`")),
+    }
+    .wrap_err(format!("failed to read `{path_str}`"))
+}
+
+/// Reads a CSV export, matching columns by header.
+pub fn read_csv<R: std::io::Read>(
+    reader: R,
+    account_id: &str,
+    timezone: Tz,
+) -> Result<Vec<UpTransaction>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()?
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.trim().to_lowercase(), i))
+        .collect::<HashMap<_, _>>();
+    let column = |names: &[&str]| names.iter().find_map(|x| headers.get(*x).copied());
+    let time = column(&["time", "date"]).wrap_err("missing time column")?;
+    let amount =
+        column(&["subtotal (aud)", "total (aud)", "amount"]).wrap_err("missing amount column")?;
+    let payee = column(&["payee", "description"]).wrap_err("missing payee column")?;
+    let description = column(&["description"]);
+    let category = column(&["category"]);
+    let tags = column(&["tags"]);
+    let settled_at = column(&["settled date"]);
+
+    // Identical rows, such as two coffees in a minute, are told apart by their order.
+    let mut occurrences = HashMap::<String, usize>::new();
+    let mut transactions = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+        };
+        let line = i + 2;
+        let created_at = parse_time(field(Some(time)).unwrap_or_default(), timezone)
+            .wrap_err(format!("invalid time on line {line}"))?;
+        let cents = parse_cents(&field(Some(amount)).unwrap_or_default().replace(',', ""))
+            .wrap_err(format!("invalid amount on line {line}"))?;
+        let payee = field(Some(payee)).unwrap_or_default();
+        let message = field(description).filter(|x| *x != payee);
+        let settled_at = field(settled_at)
+            .map(|x| parse_time(x, timezone).or_else(|_| parse_date(x, timezone)))
+            .transpose()
+            .wrap_err(format!("invalid settled date on line {line}"))?;
+
+        // The local time keeps ids independent of the time zone imported with.
+        let local = created_at.naive_local().format("%Y-%m-%dT%H:%M:%S");
+        let key = format!("{account_id}|{local}|{cents}|{payee}");
+        let occurrence = occurrences.entry(key.clone()).or_default();
+        *occurrence += 1;
+        let id = transaction_id(&format!("{key}|{occurrence}"));
+
+        transactions.push(serde_json::from_value(json!({
+            "type": "transactions",
+            "id": id,
+            "attributes": {
+                "status": if settled_at.is_some() { "SETTLED" } else { "HELD" },
+                "rawText": null,
+                "description": payee,
+                "message": message,
+                "isCategorizable": true,
+                "holdInfo": null,
+                "roundUp": null,
+                "cashback": null,
+                "amount": money(cents)?,
+                "foreignAmount": null,
+                "settledAt": settled_at.map(|x| x.to_rfc3339()),
+                "createdAt": created_at.to_rfc3339(),
+            },
+            "relationships": {
+                "account": { "data": { "type": "accounts", "id": account_id } },
+                "transferAccount": { "data": null },
+                "category": {
+                    "data": field(category).map(|x| json!({ "type": "categories", "id": slug(x) })),
+                },
+                "parentCategory": { "data": null },
+                "tags": {
+                    "data": field(tags)
+                        .into_iter()
+                        .flat_map(|x| x.split(','))
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(|x| json!({ "type": "tags", "id": x }))
+                        .collect::<Vec<_>>(),
+                },
+            },
+        }))?);
+    }
+    Ok(transactions)
+}
+
+/// Reads a JSON dump: an array of transactions, an API page or a transaction per line.
+pub fn read_json(contents: &str) -> Result<Vec<UpTransaction>> {
+    let values = match serde_json::from_str::<Value>(contents) {
+        Ok(Value::Array(values)) => values,
+        Ok(Value::Object(mut page)) if page.contains_key("data") => match page.remove("data") {
+            Some(Value::Array(values)) => values,
+            _ => return Err(eyre!("expected a list of transactions")),
+        },
+        Ok(value) => vec![value],
+        Err(_) => contents
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+    };
+    values
+        .into_iter()
+        .map(|x| serde_json::from_value(x).wrap_err("invalid up transaction"))
+        .collect()
+}
+
+/// Creates an account Up no longer lists, with the transactions' total as its balance.
+pub fn account(
+    id: &str,
+    name: &str,
+    saver: bool,
+    transactions: &[UpTransaction],
+) -> Result<UpAccount> {
+    let transactions = transactions
+        .iter()
+        .filter(|x| x.relationships.account.data.id == id)
+        .collect::<Vec<_>>();
+    let balance = transactions
+        .iter()
+        .map(|x| i64::from(x.attributes.amount.value_in_base_units))
+        .sum();
+    let created_at = transactions
+        .iter()
+        .map(|x| x.attributes.created_at.as_str())
+        .min_by_key(|x| DateTime::parse_from_rfc3339(x).ok())
+        .unwrap_or_default();
+
+    Ok(serde_json::from_value(json!({
+        "type": "accounts",
+        "id": id,
+        "attributes": {
+            "displayName": name,
+            "accountType": if saver { "SAVER" } else { "TRANSACTIONAL" },
+            "ownershipType": "INDIVIDUAL",
+            "balance": money(balance)?,
+            "createdAt": created_at,
+        },
+        "relationships": { "transactions": { "links": null } },
+    }))?)
+}
+
+/// Id of an account Up no longer lists, the same for every import of its exports.
+#[must_use]
+pub fn account_id(name: &str) -> String {
+    transaction_id(&format!("account|{}", name.trim()))
+}
+
+fn transaction_id(key: &str) -> String {
+    Uuid::new_v5(&NAMESPACE, key.as_bytes())
+        .hyphenated()
+        .to_string()
+}
+
+fn money(cents: i64) -> Result<Value> {
+    Ok(json!({
+        "currencyCode": "AUD",
+        "value": format_cents(cents),
+        "valueInBaseUnits": i32::try_from(cents).wrap_err("amount out of range")?,
+    }))
+}
+
+/// Parses a time, resolving local times to the offset in effect in the time zone. Times repeated
+/// when daylight saving ends are read as the earlier one.
+fn parse_time(value: &str, timezone: Tz) -> Result<DateTime<FixedOffset>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time);
+    }
+    TIME_FORMATS
+        .iter()
+        .find_map(|x| NaiveDateTime::parse_from_str(value, x).ok())
+        .and_then(|x| timezone.from_local_datetime(&x).earliest())
+        .map(|x| x.fixed_offset())
+        .wrap_err(format!("invalid time `{value}`"))
+}
+
+/// Settled dates may have no time, they're read as the start of the day.
+fn parse_date(value: &str, timezone: Tz) -> Result<DateTime<FixedOffset>> {
+    parse_time(&format!("{value} 00:00:00"), timezone)
+}
+
+/// Turns a category name into its Up id, e.g. `Restaurants & Cafes` into
+/// `restaurants-and-cafes`.
+fn slug(name: &str) -> String {
+    name.to_lowercase()
+        .replace('&', " and ")
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const CSV: &str = indoc! {r#"
+        Time,BSB / Account Number,Transaction Type,Payee,Description,Category,Tags,Subtotal (AUD),Currency,Subtotal (Transaction Currency),Round Up (AUD),Total (AUD),Payment Method,Settled Date
+        2023-12-02 13:44:15,633-123 123456789,Purchase,7-Eleven,7 ELEVEN,Fuel,,-57.84,AUD,-57.84,,-57.84,Card,2023-12-04
+        2023-12-03 08:01:00,633-123 123456789,Purchase,Cafe,Cafe,Restaurants & Cafes,"work, coffee",-4.50,AUD,-4.50,-0.50,-5.00,Card,
+        2023-12-03 08:01:00,633-123 123456789,Purchase,Cafe,Cafe,Restaurants & Cafes,,-4.50,AUD,-4.50,-0.50,-5.00,Card,
+        2023-12-04 09:00:00,633-123 123456789,Salary,Employer,March pay,,,"1,200.00",AUD,"1,200.00",,"1,200.00",,2023-12-04
+    "#};
+
+    const SYDNEY: Tz = Tz::Australia__Sydney;
+
+    #[test]
+    fn csv_exports() -> Result<()> {
+        let transactions = read_csv(CSV.as_bytes(), "spending", SYDNEY)?;
+        assert_eq!(4, transactions.len());
+
+        let fuel = &transactions[0];
+        assert_eq!("7-Eleven", fuel.attributes.description);
+        assert_eq!(Some("7 ELEVEN".to_owned()), fuel.attributes.message);
+        assert_eq!(-5784, fuel.attributes.amount.value_in_base_units);
+        assert_eq!("2023-12-02T13:44:15+11:00", fuel.attributes.created_at);
+        assert_eq!(
+            Some("2023-12-04T00:00:00+11:00".to_owned()),
+            fuel.attributes.settled_at
+        );
+        assert_eq!("spending", fuel.relationships.account.data.id);
+
+        let coffee = &transactions[1];
+        assert_eq!(None, coffee.attributes.message);
+        assert_eq!(None, coffee.attributes.settled_at);
+        assert_eq!(
+            Some("restaurants-and-cafes".to_owned()),
+            coffee
+                .relationships
+                .category
+                .data
+                .as_ref()
+                .map(|x| x.id.clone())
+        );
+        assert_eq!(
+            vec!["work", "coffee"],
+            coffee
+                .relationships
+                .tags
+                .data
+                .iter()
+                .map(|x| x.id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("1200.00", transactions[3].attributes.amount.value);
+
+        // Ids are stable across imports, and unique for identical rows.
+        let again = read_csv(CSV.as_bytes(), "spending", SYDNEY)?;
+        assert_eq!(
+            transactions.iter().map(|x| &x.id).collect::<Vec<_>>(),
+            again.iter().map(|x| &x.id).collect::<Vec<_>>()
+        );
+        assert_ne!(transactions[1].id, transactions[2].id);
+        Ok(())
+    }
+
+    #[test]
+    fn daylight_saving() -> Result<()> {
+        // Daylight saving started at 2am on 2023-10-01 and ended at 3am on 2024-04-07.
+        let csv = indoc! {"
+            Time,Payee,Subtotal (AUD)
+            2023-09-30 12:00:00,Bakery,-8.40
+            2023-10-01 12:00:00,Bakery,-8.40
+            2024-04-07 02:30:00,Bakery,-8.40
+            2024-04-07 12:00:00,Bakery,-8.40
+        "};
+        let transactions = read_csv(csv.as_bytes(), "spending", SYDNEY)?;
+        assert_eq!(
+            vec![
+                "2023-09-30T12:00:00+10:00",
+                "2023-10-01T12:00:00+11:00",
+                "2024-04-07T02:30:00+11:00",
+                "2024-04-07T12:00:00+10:00",
+            ],
+            transactions
+                .iter()
+                .map(|x| x.attributes.created_at.as_str())
+                .collect::<Vec<_>>()
+        );
+        // Times skipped when daylight saving starts don't exist.
+        let skipped = "Time,Payee,Subtotal (AUD)\n2023-10-01 02:30:00,Bakery,-8.40\n";
+        assert!(read_csv(skipped.as_bytes(), "spending", SYDNEY).is_err());
+
+        // Ids only depend on the local time, not the time zone imported with.
+        let brisbane = read_csv(csv.as_bytes(), "spending", Tz::Australia__Brisbane)?;
+        assert_eq!(
+            transactions.iter().map(|x| &x.id).collect::<Vec<_>>(),
+            brisbane.iter().map(|x| &x.id).collect::<Vec<_>>()
+        );
+        for transaction in &transactions {
+            assert_eq!(
+                Some(uuid::Version::Sha1),
+                Uuid::parse_str(&transaction.id)?.get_version()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn json_dumps() -> Result<()> {
+        let transaction = fs::read_to_string("test/data/up_expense.json")?;
+        let value = serde_json::from_str::<Value>(&transaction)?;
+        let expected = read_json(&transaction)?;
+        assert_eq!(1, expected.len());
+
+        let array = serde_json::to_string(&[&value, &value])?;
+        let page = serde_json::to_string(&json!({ "data": [&value], "links": {} }))?;
+        let lines = format!("{value}\n{value}\n");
+        assert_eq!(2, read_json(&array)?.len());
+        assert_eq!(expected, read_json(&page)?);
+        assert_eq!(2, read_json(&lines)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn closed_accounts() -> Result<()> {
+        let id = account_id("Old Saver");
+        assert_eq!(id, account_id(" Old Saver "));
+        let transactions = read_csv(CSV.as_bytes(), &id, SYDNEY)?;
+        let account = account(&id, "Old Saver", true, &transactions)?;
+        assert_eq!("Old Saver", account.attributes.display_name);
+        assert_eq!(113_316, account.attributes.balance.value_in_base_units);
+        assert_eq!("2023-12-02T13:44:15+11:00", account.attributes.created_at);
+        Ok(())
+    }
+}
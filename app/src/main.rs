@@ -8,66 +8,193 @@
 )]
 #![allow(clippy::missing_errors_doc, clippy::wildcard_imports)]
 
-use std::path::PathBuf;
+use std::{io::IsTerminal, path::PathBuf, process::ExitCode};
 
 use clap::Parser;
 use figment::{
     providers::{Format, Toml},
     Figment,
 };
+use tracing::{error, info_span, Instrument};
 use up_ynab::{
+    api::{self, cassette::Cassette},
     cmd,
     frontend::{
         cli,
         cli::{Cli, Commands},
         config::Config,
+        output,
     },
     Result,
 };
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
+    match try_main().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+/// Maps API errors to distinct exit codes, so scripts can tell them apart.
+fn exit_code(e: &up_ynab::Error) -> u8 {
+    let api_error = e.chain().find_map(|x| x.downcast_ref::<api::Error>());
+    match api_error {
+        None => 1,
+        Some(api::Error::Unauthorized { .. }) => 3,
+        Some(api::Error::NotFound { .. }) => 4,
+        Some(api::Error::RateLimited { .. }) => 5,
+        Some(api::Error::Validation { .. }) => 6,
+        Some(api::Error::Server { .. }) => 7,
+        Some(api::Error::Transport { .. }) => 8,
+        Some(api::Error::Decode { .. }) => 9,
+    }
+}
+
+async fn try_main() -> Result<()> {
     color_eyre::install()?;
     install_tracing()?;
 
     let cli = Cli::parse();
-    let config = Figment::new()
-        .merge(Toml::file(
-            cli.config.unwrap_or(PathBuf::from("config.toml")),
-        ))
-        .extract::<Config>()?;
+    let figment = Figment::new().merge(Toml::file(
+        cli.config.clone().unwrap_or(PathBuf::from("config.toml")),
+    ));
+    let profiles = Config::select(
+        Config::load(&figment)?,
+        cli.profile.as_deref(),
+        cli.all_profiles,
+    )?;
+
+    // Profiles are isolated, a failing profile doesn't stop the others.
+    let mut failed = Vec::new();
+    let mut first_error = None;
+    let num_profiles = profiles.len();
+    for mut config in profiles {
+        let span = info_span!("profile", name = config.name);
+        // The cassette is kept until the profile finishes.
+        let result = match cassette(&cli, &mut config) {
+            Ok(_cassette) => run(&config, cli.command.clone()).instrument(span).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Err(e) if num_profiles > 1 => {
+                error!("profile `{}` failed: {e:?}", config.name);
+                failed.push(config.name.clone());
+                first_error.get_or_insert(e);
+            }
+            result => result?,
+        }
+    }
+
+    // The first error is kept so the exit code reflects its cause.
+    if let Some(e) = first_error {
+        return Err(e.wrap_err(format!("profiles failed: {}", failed.join(", "))));
+    }
+
+    Ok(())
+}
+
+/// Starts recording or replaying the profile's API requests, pointing its clients at the cassette.
+/// Each profile uses its own directory inside the cassette directory.
+fn cassette(cli: &Cli, config: &mut Config) -> Result<Option<Cassette>> {
+    let cassette = if let Some(dir) = &cli.record {
+        let secrets = config
+            .up
+            .iter()
+            .map(|x| x.api_token.clone())
+            .chain([config.ynab.api_token.clone()])
+            .collect();
+        let cassette = Cassette::record(dir.join(&config.name), &config.http, secrets)?;
+        // The proxy is used by the recorder, the clients connect to it directly.
+        config.http.proxy = None;
+        cassette
+    } else if let Some(dir) = &cli.replay {
+        Cassette::replay(dir.join(&config.name))?
+    } else {
+        return Ok(None);
+    };
 
+    config.http.up_base_url = Some(cassette.up_base_url());
+    config.http.ynab_base_url = Some(cassette.ynab_base_url());
+    Ok(Some(cassette))
+}
+
+async fn run(config: &Config, command: Commands) -> Result<()> {
     // TODO: replace this with a proc macro
-    match cli.command {
+    match command {
         Commands::Sync(args) => {
-            cmd::sync::sync(&config, args).await?;
+            cmd::sync::sync(config, args).await?;
+        }
+        Commands::Doctor => {
+            cmd::doctor::doctor(config).await?;
         }
         Commands::Get(get) => match get {
             cli::get::Cmd::Account(account) => match account {
-                cli::get::account::Cmd::Up => {
-                    cmd::get::account::up(&config).await?;
+                cli::get::account::Cmd::Up(args) => {
+                    let accounts = cmd::get::account::up(config).await?;
+                    output::write_to(&args, &accounts)?;
                 }
-                cli::get::account::Cmd::Ynab => {
-                    cmd::get::account::ynab(&config).await?;
+                cli::get::account::Cmd::Ynab(args) => {
+                    let accounts = cmd::get::account::ynab(config).await?;
+                    output::write_to(&args, &accounts)?;
                 }
             },
             cli::get::Cmd::Transaction(transaction) => match transaction {
-                cli::get::transaction::Cmd::Up(args) => {
-                    cmd::get::transaction::up(&config, args).await?;
+                cli::get::transaction::Cmd::Up { fetch, output } => {
+                    let transactions = cmd::get::transaction::up(config, fetch).await?;
+                    output::write_to(&output, &transactions)?;
                 }
-                cli::get::transaction::Cmd::Ynab(args) => {
-                    cmd::get::transaction::ynab(&config, args).await?;
+                cli::get::transaction::Cmd::Ynab { fetch, output } => {
+                    let transactions = cmd::get::transaction::ynab(config, fetch).await?;
+                    output::write_to(&output, &transactions)?;
                 }
             },
             cli::get::Cmd::Balance(balance) => match balance {
                 cli::get::balance::Cmd::Up(args) => {
-                    cmd::get::balance::up(&config, args).await?;
+                    cmd::get::balance::up(config, args).await?;
                 }
                 cli::get::balance::Cmd::Ynab(args) => {
-                    cmd::get::balance::ynab(&config, args).await?;
+                    cmd::get::balance::ynab(config, args)?;
                 }
             },
         },
+        Commands::Run(run) => match run {
+            cli::run::Cmd::Info(args) => {
+                cmd::run::info(config, &args)?;
+            }
+            cli::run::Cmd::Pack(args) => {
+                cmd::run::pack(config, args)?;
+            }
+            cli::run::Cmd::Encrypt(args) => {
+                cmd::run::encrypt(config, args)?;
+            }
+            cli::run::Cmd::Diff(args) => {
+                cmd::run::diff(config, &args)?;
+            }
+            cli::run::Cmd::Prune(args) => {
+                cmd::run::prune(config, &args)?;
+            }
+            cli::run::Cmd::Import(args) => {
+                cmd::run::import(config, args).await?;
+            }
+        },
+        Commands::Store(store) => match store {
+            cli::store::Cmd::Info => {
+                cmd::store::info(config)?;
+            }
+            cli::store::Cmd::Import(args) => {
+                cmd::store::import(config, args)?;
+            }
+            cli::store::Cmd::Export(args) => {
+                cmd::store::export(config, &args)?;
+            }
+        },
+        Commands::Query(args) => {
+            cmd::query::query(config, args).await?;
+        }
     }
 
     Ok(())
@@ -77,7 +204,10 @@ fn install_tracing() -> Result<()> {
     use tracing_error::ErrorLayer;
     use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
-    let fmt_layer = fmt::layer();
+    // Logs go to stderr, leaving stdout to the output of `get` and `query`.
+    let fmt_layer = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal());
     let filter_layer =
         EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("up_ynab=trace"))?;
 
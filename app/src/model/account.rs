@@ -1,9 +1,15 @@
+use std::collections::HashMap;
+
 use color_eyre::eyre::ContextCompat;
 use nutype::nutype;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::Result;
+use crate::{
+    api::{up::AccountKind as UpAccountKind, ynab::AccountKind},
+    model::UpTransaction,
+    Result,
+};
 
 pub type UpAccountInner = up_client::models::AccountResource;
 pub type YnabAccountInner = ynab_client::models::Account;
@@ -24,18 +30,96 @@ pub struct Account {
     pub ynab_transfer_id: Uuid,
 }
 
+/// A YNAB account without an Up counterpart, such as a credit card or mortgage.
+#[derive(
+    Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize,
+)]
+pub struct UnmanagedAccount {
+    pub name: String,
+    pub ynab_id: Uuid,
+    pub ynab_transfer_id: Uuid,
+}
+
+impl UpAccount {
+    /// Returns the name of the matching YNAB account.
+    #[must_use]
+    pub fn ynab_name<'a>(&'a self, mappings: &'a HashMap<String, String>) -> &'a str {
+        let name = self.attributes.display_name.trim();
+        mappings.get(name).map_or(name, String::as_str).trim()
+    }
+
+    #[must_use]
+    pub fn find_ynab<'a>(
+        &self,
+        ynab_accounts: &'a [YnabAccount],
+        mappings: &HashMap<String, String>,
+    ) -> Option<&'a YnabAccount> {
+        let name = self.ynab_name(mappings);
+        ynab_accounts.iter().find(|x| x.name.trim() == name)
+    }
+
+    /// Returns the YNAB account type matching this account.
+    #[must_use]
+    pub fn ynab_kind(&self) -> AccountKind {
+        match self.attributes.account_type {
+            UpAccountKind::Saver => AccountKind::Savings,
+            UpAccountKind::Transactional => AccountKind::Checking,
+        }
+    }
+
+    /// Returns the current balance in YNAB milliunits.
+    #[must_use]
+    pub fn ynab_balance(&self) -> i64 {
+        i64::from(self.attributes.balance.value_in_base_units) * 10
+    }
+
+    /// Returns the balance before the account's transactions among `transactions` in YNAB
+    /// milliunits, which a new YNAB account starts with so syncing them doesn't count them twice.
+    #[must_use]
+    pub fn ynab_starting_balance(&self, transactions: &[UpTransaction]) -> i64 {
+        let synced = transactions
+            .iter()
+            .filter(|x| x.relationships.account.data.id == self.id)
+            .map(|x| i64::from(x.attributes.amount.value_in_base_units) * 10)
+            .sum::<i64>();
+        self.ynab_balance() - synced
+    }
+}
+
+impl UnmanagedAccount {
+    pub fn identify(name: &str, ynab_accounts: &[YnabAccount]) -> Result<Self> {
+        let ynab_account = ynab_accounts
+            .iter()
+            .find(|x| x.name.trim() == name.trim())
+            .wrap_err(format!("failed to find ynab account `{name}`"))?;
+        Ok(UnmanagedAccount {
+            name: ynab_account.name.clone(),
+            ynab_id: ynab_account.id,
+            ynab_transfer_id: ynab_account
+                .transfer_payee_id
+                .wrap_err("missing ynab transfer id")?,
+        })
+    }
+}
+
 impl Account {
-    pub fn identify(up_accounts: &[UpAccount], ynab_accounts: &[YnabAccount]) -> Result<Vec<Self>> {
+    /// Matches Up accounts to YNAB accounts by name. `mappings` maps Up account names to YNAB
+    /// account names, for accounts whose names differ.
+    pub fn identify(
+        up_accounts: &[UpAccount],
+        ynab_accounts: &[YnabAccount],
+        mappings: &HashMap<String, String>,
+    ) -> Result<Vec<Self>> {
         let accounts = up_accounts
             .iter()
             .map(|up_account| {
                 let up_account_name = up_account.attributes.display_name.clone();
-                let ynab_account = ynab_accounts
-                    .iter()
-                    .find(|x| x.name.trim() == up_account_name.trim())
-                    .wrap_err(format!(
-                        "failed to match up account `{up_account_name}` to ynab account"
-                    ))?;
+                let ynab_account =
+                    up_account
+                        .find_ynab(ynab_accounts, mappings)
+                        .wrap_err(format!(
+                            "failed to match up account `{up_account_name}` to ynab account"
+                        ))?;
                 Ok(Account {
                     name: up_account_name,
                     up_id: up_account.id.clone(),
@@ -28,7 +28,8 @@ pub fn running_total(transactions: &[Transaction]) -> Vec<Balance> {
         let mut new_values = last_balance.values;
 
         let to = match &transaction.kind {
-            transaction::Kind::External { to, from_name: _ } => to,
+            transaction::Kind::External { to, from_name: _ }
+            | transaction::Kind::Unmanaged { to, from: _ } => to,
             transaction::Kind::Internal { to, from } => {
                 new_values
                     .entry(from.clone())
@@ -78,16 +79,7 @@ pub fn write_csv<P: AsRef<Path>>(balances: &[Balance], path: P) -> Result<()> {
         let id = Some(balance.transaction.id.clone());
         let amount = Some(balance.transaction.amount.to_string());
         let msg = balance.transaction.msg.clone();
-        let kind = Some(
-            match &balance.transaction.kind {
-                transaction::Kind::External {
-                    to: _,
-                    from_name: _,
-                } => "external",
-                transaction::Kind::Internal { to: _, from: _ } => "internal",
-            }
-            .to_owned(),
-        );
+        let kind = Some(balance.transaction.kind.name().to_owned());
 
         let to = Some(balance.transaction.to().name.clone());
         let from = Some(balance.transaction.from_name().to_owned());
@@ -125,7 +117,8 @@ impl<'a> fmt::Display for Balance<'a> {
                 to: _,
                 from_name: _,
             } => "expense",
-            transaction::Kind::Internal { to: _, from: _ } => "transfer",
+            transaction::Kind::Internal { to: _, from: _ }
+            | transaction::Kind::Unmanaged { to: _, from: _ } => "transfer",
         };
 
         let time = self.transaction.timestamp.to_rfc3339();
@@ -167,32 +160,12 @@ mod test {
     use fallible_iterator::{FallibleIterator, IteratorExt};
     use money2::Currency;
     use pretty_assertions::assert_eq;
-    use uuid::Uuid;
 
     use super::*;
-    use crate::model::{Account, UpTransaction};
-
-    fn spending_account() -> Result<Account> {
-        Ok(Account {
-            name: "Spending".to_owned(),
-            up_id: "2be1c9de-7a89-4e8f-8077-f535150b588d".to_owned(),
-            ynab_id: Uuid::from_str("f6ca888b-327a-45d0-9775-830abdaa3a04")?,
-            ynab_transfer_id: Uuid::from_str("89ddd9ef-2510-4b42-a889-e7a68cae291c")?,
-        })
-    }
-
-    fn home_account() -> Result<Account> {
-        Ok(Account {
-            name: "Home".to_owned(),
-            up_id: "328160b1-d7bc-41ee-9d7b-c7da4f2484b0".to_owned(),
-            ynab_id: Uuid::from_str("2b00a77e-9b3c-4277-9c6c-6944f7696705")?,
-            ynab_transfer_id: Uuid::from_str("f9b0b92f-70f7-4015-b885-4e5807a78a44")?,
-        })
-    }
-
-    fn accounts() -> Result<Vec<Account>> {
-        Ok(Vec::from([home_account()?, spending_account()?]))
-    }
+    use crate::model::{
+        fixture::{accounts, home_account, spending_account},
+        Account, UpTransaction,
+    };
 
     fn transactions_from_file<P: AsRef<Path>>(
         path: P,
@@ -0,0 +1,11 @@
+use nutype::nutype;
+
+pub type UpCategoryInner = up_client::models::CategoryResource;
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct UpCategory(UpCategoryInner);
+
+pub type YnabCategoryInner = ynab_client::models::Category;
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct YnabCategory(YnabCategoryInner);
@@ -0,0 +1,521 @@
+//! Filter expressions over Up transactions, e.g.
+//! `payee ~ uber and amount < -30 and date >= 2024-03-01 and tags = reimbursable`.
+//!
+//! Comparisons are `field op value`, joined with `and`, `or` and `not`, and grouped with
+//! parentheses. Text comparisons ignore case, `~` matches text containing the value.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use color_eyre::eyre::{eyre, Context, Result};
+use serde::{Serialize, Serializer};
+
+use crate::model::UpTransaction;
+
+/// An Up transaction flattened into the fields filters compare.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Record {
+    pub id: String,
+    pub created_at: DateTime<FixedOffset>,
+    /// The date in the transaction's time zone.
+    pub date: NaiveDate,
+    /// In cents, negative when spent.
+    #[serde(serialize_with = "serialize_cents")]
+    pub amount: i64,
+    pub payee: String,
+    pub account: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// `held` or `settled`.
+    pub status: String,
+    /// `internal` for transfers between Up accounts, `external` otherwise.
+    pub kind: String,
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare(Field, Op, Value),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Date,
+    Amount,
+    Payee,
+    Account,
+    Category,
+    Tags,
+    Status,
+    Kind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Date(NaiveDate),
+    /// In cents.
+    Amount(i64),
+    /// Lowercase.
+    Text(String),
+}
+
+impl Record {
+    /// Flattens the transaction, naming its account from `account_names` by id.
+    pub fn new(
+        transaction: &UpTransaction,
+        account_names: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let attributes = &transaction.attributes;
+        let relationships = &transaction.relationships;
+        let created_at = DateTime::parse_from_rfc3339(&attributes.created_at).wrap_err(format!(
+            "invalid creation date for up transaction `{}`",
+            transaction.id
+        ))?;
+        let account_id = &relationships.account.data.id;
+        let status = serde_json::to_value(attributes.status)?
+            .as_str()
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let kind = if relationships.transfer_account.data.is_some() {
+            "internal"
+        } else {
+            "external"
+        };
+
+        Ok(Self {
+            id: transaction.id.clone(),
+            created_at,
+            date: created_at.date_naive(),
+            amount: i64::from(attributes.amount.value_in_base_units),
+            payee: attributes.description.clone(),
+            account: account_names
+                .get(account_id)
+                .cloned()
+                .unwrap_or_else(|| account_id.clone()),
+            category: relationships.category.data.as_ref().map(|x| x.id.clone()),
+            tags: relationships
+                .tags
+                .data
+                .iter()
+                .map(|x| x.id.clone())
+                .collect(),
+            status,
+            kind: kind.to_owned(),
+            message: attributes.message.clone(),
+        })
+    }
+}
+
+impl Filter {
+    #[must_use]
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(record) && b.matches(record),
+            Self::Or(a, b) => a.matches(record) || b.matches(record),
+            Self::Not(a) => !a.matches(record),
+            Self::Compare(field, op, value) => match (field, value) {
+                (Field::Date, Value::Date(x)) => op.compare(&record.date, x),
+                (Field::Amount, Value::Amount(x)) => op.compare(&record.amount, x),
+                (Field::Payee, Value::Text(x)) => op.compare_text(Some(&record.payee), x),
+                (Field::Account, Value::Text(x)) => op.compare_text(Some(&record.account), x),
+                (Field::Category, Value::Text(x)) => op.compare_text(record.category.as_deref(), x),
+                (Field::Status, Value::Text(x)) => op.compare_text(Some(&record.status), x),
+                (Field::Kind, Value::Text(x)) => op.compare_text(Some(&record.kind), x),
+                // A tag comparison holds for any tag, `!=` for every tag.
+                (Field::Tags, Value::Text(x)) if *op == Op::Ne => {
+                    record.tags.iter().all(|tag| op.compare_text(Some(tag), x))
+                }
+                (Field::Tags, Value::Text(x)) => {
+                    record.tags.iter().any(|tag| op.compare_text(Some(tag), x))
+                }
+                // Values are checked against their field when parsed.
+                _ => false,
+            },
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            len: s.len(),
+        };
+        let filter = parser.or()?;
+        if let Some((at, token)) = parser.tokens.get(parser.pos) {
+            return Err(eyre!("unexpected {token} at {at}"));
+        }
+        Ok(filter)
+    }
+}
+
+impl FromStr for Field {
+    type Err = color_eyre::eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "date" => Self::Date,
+            "amount" => Self::Amount,
+            "payee" => Self::Payee,
+            "account" => Self::Account,
+            "category" => Self::Category,
+            "tag" | "tags" => Self::Tags,
+            "status" => Self::Status,
+            "kind" => Self::Kind,
+            _ => return Err(eyre!("unknown field `{s}`")),
+        })
+    }
+}
+
+impl Op {
+    fn compare<T: Ord>(self, a: &T, b: &T) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Contains => false,
+        }
+    }
+
+    /// Compares ignoring case. A missing value only differs from every value.
+    fn compare_text(self, a: Option<&str>, b: &str) -> bool {
+        let Some(a) = a.map(str::to_lowercase) else {
+            return self == Self::Ne;
+        };
+        match self {
+            Self::Contains => a.contains(b),
+            _ => self.compare(&a.as_str(), &b),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Contains => "~",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Op(Op),
+    Word(String),
+    /// Quoted text, never a keyword.
+    Text(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => f.write_str("`(`"),
+            Self::Close => f.write_str("`)`"),
+            Self::Op(x) => write!(f, "`{x}`"),
+            Self::Word(x) => write!(f, "`{x}`"),
+            Self::Text(x) => write!(f, "{x:?}"),
+        }
+    }
+}
+
+/// Splits the expression into tokens, with their byte offsets.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Contains),
+            '=' => Token::Op(Op::Eq),
+            '!' | '<' | '>' => {
+                let eq = chars.next_if(|(_, x)| *x == '=').is_some();
+                Token::Op(match (c, eq) {
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => return Err(eyre!("expected `!=` at {at}")),
+                })
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, x)) if x == c => break,
+                        Some((_, '\\')) => {
+                            text.extend(chars.next().map(|(_, x)| x));
+                        }
+                        Some((_, x)) => text.push(x),
+                        None => return Err(eyre!("unterminated string at {at}")),
+                    }
+                }
+                Token::Text(text)
+            }
+            _ => {
+                let mut word = c.to_string();
+                while let Some((_, x)) =
+                    chars.next_if(|(_, x)| !x.is_whitespace() && !"()~=!<>\"'".contains(*x))
+                {
+                    word.push(x);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push((at, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// Length of the expression, where errors at its end are reported.
+    len: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Filter> {
+        let mut filter = self.and()?;
+        while self.keyword("or") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter> {
+        let mut filter = self.not()?;
+        while self.keyword("and") {
+            filter = Filter::And(Box::new(filter), Box::new(self.not()?));
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<Filter> {
+        if self.keyword("not") {
+            return Ok(Filter::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Filter> {
+        let (at, token) = self.next("a comparison")?;
+        match token {
+            Token::Open => {
+                let filter = self.or()?;
+                match self.next("`)`")? {
+                    (_, Token::Close) => Ok(filter),
+                    (at, token) => Err(eyre!("expected `)` at {at}, found {token}")),
+                }
+            }
+            Token::Word(field) => {
+                let field = field
+                    .parse::<Field>()
+                    .wrap_err(format!("invalid comparison at {at}"))?;
+                let op = match self.next("an operator")? {
+                    (_, Token::Op(op)) => op,
+                    (at, token) => {
+                        return Err(eyre!("expected an operator at {at}, found {token}"))
+                    }
+                };
+                let (at, value) = match self.next("a value")? {
+                    (at, Token::Word(x) | Token::Text(x)) => (at, x),
+                    (at, token) => return Err(eyre!("expected a value at {at}, found {token}")),
+                };
+                let value = parse_value(field, op, &value)
+                    .wrap_err(format!("invalid comparison at {at}"))?;
+                Ok(Filter::Compare(field, op, value))
+            }
+            token => Err(eyre!("expected a comparison at {at}, found {token}")),
+        }
+    }
+
+    /// Consumes the keyword if it's next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(
+            self.tokens.get(self.pos),
+            Some((_, Token::Word(x))) if x.eq_ignore_ascii_case(keyword)
+        );
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(usize, Token)> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| eyre!("expected {expected} at {}", self.len))?;
+        self.pos += 1;
+        Ok(token)
+    }
+}
+
+fn parse_value(field: Field, op: Op, value: &str) -> Result<Value> {
+    match field {
+        Field::Date | Field::Amount if op == Op::Contains => Err(eyre!("`~` only compares text")),
+        Field::Tags if !matches!(op, Op::Eq | Op::Ne | Op::Contains) => {
+            Err(eyre!("tags only support `=`, `!=` and `~`"))
+        }
+        Field::Date => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Value::Date)
+            .wrap_err(format!("invalid date `{value}`, expected YYYY-MM-DD")),
+        Field::Amount => parse_cents(value).map(Value::Amount),
+        _ => Ok(Value::Text(value.to_lowercase())),
+    }
+}
+
+/// Parses an amount like `-30`, `$12.5` or `-$0.99` into cents.
+pub fn parse_cents(value: &str) -> Result<i64> {
+    let invalid = || eyre!("invalid amount `{value}`");
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('$').unwrap_or(rest);
+    let (dollars, cents) = rest.split_once('.').unwrap_or((rest, ""));
+    if dollars.is_empty()
+        || cents.len() > 2
+        || !dollars
+            .chars()
+            .chain(cents.chars())
+            .all(|x| x.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let cents = format!("{cents:0<2}")
+        .parse::<i64>()
+        .map_err(|_| invalid())?;
+    let total = dollars
+        .parse::<i64>()
+        .ok()
+        .and_then(|x| x.checked_mul(100)?.checked_add(cents))
+        .ok_or_else(invalid)?;
+    Ok(if negative { -total } else { total })
+}
+
+/// Formats cents as a decimal amount, e.g. `-12.30`.
+#[must_use]
+pub fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!(
+        "{sign}{}.{:02}",
+        cents.unsigned_abs() / 100,
+        cents.unsigned_abs() % 100
+    )
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_cents<S: Serializer>(cents: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_cents(*cents))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record() -> Record {
+        let created_at =
+            DateTime::parse_from_rfc3339("2024-03-14T18:30:00+11:00").unwrap_or_default();
+        Record {
+            id: "1".to_owned(),
+            created_at,
+            date: created_at.date_naive(),
+            amount: -4550,
+            payee: "Uber Eats".to_owned(),
+            account: "Spending".to_owned(),
+            category: Some("takeaway".to_owned()),
+            tags: vec!["Reimbursable".to_owned(), "work".to_owned()],
+            status: "settled".to_owned(),
+            kind: "external".to_owned(),
+            message: None,
+        }
+    }
+
+    fn matches(filter: &str) -> Result<bool> {
+        Ok(filter.parse::<Filter>()?.matches(&record()))
+    }
+
+    #[test]
+    fn filter_records() -> Result<()> {
+        assert!(matches(
+            "payee ~ uber and amount < -$30 and date >= 2024-03-01 and date < 2024-04-01 \
+             and tags = reimbursable"
+        )?);
+        assert!(matches("payee = 'uber eats'")?);
+        assert!(!matches("payee = uber")?);
+        assert!(matches("amount = -45.5")?);
+        assert!(matches("not status = held")?);
+        assert!(matches(
+            "kind = internal or (account = spending and category != groceries)"
+        )?);
+        assert!(!matches("not (tags = work and tag ~ reimb)")?);
+        assert!(matches("tags != personal")?);
+        assert!(!matches("tags != work")?);
+        assert!(matches("date = 2024-03-14")?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_filters() {
+        for (filter, error) in [
+            ("", "expected a comparison at 0"),
+            ("payee", "expected an operator at 5"),
+            ("payee ~", "expected a value at 7"),
+            ("payee ~ uber and", "expected a comparison at 16"),
+            ("(payee ~ uber", "expected `)` at 13"),
+            ("payee ~ uber)", "unexpected `)` at 12"),
+            ("cost > 30", "invalid comparison at 0"),
+            ("amount > 30.123", "invalid comparison at 9"),
+            ("date ~ 2024", "invalid comparison at 7"),
+            ("tags < a", "invalid comparison at 7"),
+            ("payee = \"uber", "unterminated string at 8"),
+        ] {
+            let e = filter.parse::<Filter>().err().map(|e| e.to_string());
+            assert_eq!(Some(error.to_owned()), e, "{filter}");
+        }
+    }
+
+    #[test]
+    fn amounts() -> Result<()> {
+        assert_eq!(3000, parse_cents("30")?);
+        assert_eq!(-1250, parse_cents("-$12.5")?);
+        assert_eq!(99, parse_cents("$0.99")?);
+        assert!(parse_cents("$").is_err());
+        assert!(parse_cents("1.2.3").is_err());
+        assert_eq!("-12.50", format_cents(-1250));
+        assert_eq!("0.05", format_cents(5));
+        Ok(())
+    }
+}
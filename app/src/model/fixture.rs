@@ -0,0 +1,30 @@
+//! Up and YNAB account pairs shared by the model tests.
+
+use std::str::FromStr;
+
+use color_eyre::eyre::Result;
+use uuid::Uuid;
+
+use crate::model::Account;
+
+pub fn spending_account() -> Result<Account> {
+    Ok(Account {
+        name: "Spending".to_owned(),
+        up_id: "2be1c9de-7a89-4e8f-8077-f535150b588d".to_owned(),
+        ynab_id: Uuid::from_str("f6ca888b-327a-45d0-9775-830abdaa3a04")?,
+        ynab_transfer_id: Uuid::from_str("89ddd9ef-2510-4b42-a889-e7a68cae291c")?,
+    })
+}
+
+pub fn home_account() -> Result<Account> {
+    Ok(Account {
+        name: "Home".to_owned(),
+        up_id: "328160b1-d7bc-41ee-9d7b-c7da4f2484b0".to_owned(),
+        ynab_id: Uuid::from_str("2b00a77e-9b3c-4277-9c6c-6944f7696705")?,
+        ynab_transfer_id: Uuid::from_str("f9b0b92f-70f7-4015-b885-4e5807a78a44")?,
+    })
+}
+
+pub fn accounts() -> Result<Vec<Account>> {
+    Ok(Vec::from([home_account()?, spending_account()?]))
+}
@@ -1,5 +1,16 @@
 pub mod account;
 pub mod balance;
+pub mod category;
+pub mod filter;
+#[cfg(test)]
+pub(crate) mod fixture;
+pub mod rule;
+pub mod tag;
 pub mod transaction;
+pub mod transfer;
+pub mod webhook;
 
-pub use self::{account::*, balance::*, transaction::*};
+pub use self::{
+    account::*, balance::*, category::*, filter::*, rule::*, tag::*, transaction::*, transfer::*,
+    webhook::*,
+};
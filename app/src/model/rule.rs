@@ -0,0 +1,142 @@
+use color_eyre::eyre::{Context, Result};
+use regex::Regex;
+use tracing::{debug, info};
+
+use crate::{
+    frontend::config,
+    model::{transaction::Kind, Transaction, UnmanagedAccount, UpTransaction, YnabAccount},
+};
+
+/// Turns matching external transactions into transfers to an unmanaged YNAB account.
+#[derive(Debug, Clone)]
+pub struct TransferRule {
+    pub description: Option<Regex>,
+    pub raw_text: Option<Regex>,
+    pub account: UnmanagedAccount,
+}
+
+impl TransferRule {
+    pub fn identify(rules: &[config::Rule], ynab_accounts: &[YnabAccount]) -> Result<Vec<Self>> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let compile = |x: &Option<String>| {
+                    x.as_deref()
+                        .map(Regex::new)
+                        .transpose()
+                        .wrap_err(format!("invalid regex in rule for `{}`", rule.account))
+                };
+
+                Ok(TransferRule {
+                    description: compile(&rule.description)?,
+                    raw_text: compile(&rule.raw_text)?,
+                    account: UnmanagedAccount::identify(&rule.account, ynab_accounts)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        info!("loaded {} transfer rules", rules.len());
+        Ok(rules)
+    }
+
+    /// Checks whether every pattern of the rule matches the transaction. Rules without patterns
+    /// never match.
+    #[must_use]
+    pub fn matches(&self, transaction: &UpTransaction) -> bool {
+        let description = self
+            .description
+            .as_ref()
+            .map(|x| x.is_match(&transaction.attributes.description));
+        let raw_text = self.raw_text.as_ref().map(|x| {
+            transaction
+                .attributes
+                .raw_text
+                .as_deref()
+                .is_some_and(|raw_text| x.is_match(raw_text))
+        });
+
+        match (description, raw_text) {
+            (None, None) => false,
+            (description, raw_text) => description.unwrap_or(true) && raw_text.unwrap_or(true),
+        }
+    }
+}
+
+impl Transaction {
+    /// Applies the first matching rule, turning an external transaction into a transfer to an
+    /// unmanaged account.
+    #[must_use]
+    pub fn with_rules(mut self, source: &UpTransaction, rules: &[TransferRule]) -> Self {
+        if let Kind::External { to, from_name: _ } = &self.kind
+            && let Some(rule) = rules.iter().find(|x| x.matches(source))
+        {
+            debug!(
+                "transaction `{}` matched rule for `{}`",
+                self.id, rule.account.name
+            );
+            self.kind = Kind::Unmanaged {
+                to: to.clone(),
+                from: rule.account.clone(),
+            };
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, str::FromStr};
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::fixture::spending_account;
+
+    fn credit_card_account() -> Result<UnmanagedAccount> {
+        Ok(UnmanagedAccount {
+            name: "Credit Card".to_owned(),
+            ynab_id: Uuid::from_str("0e0c3c5a-3c1e-4c8e-9f4a-1c6f3e2f9a10")?,
+            ynab_transfer_id: Uuid::from_str("5b8f2d4e-7a1c-4f3b-8e6d-2a9c0b1d3e4f")?,
+        })
+    }
+
+    fn rule(description: Option<&str>, raw_text: Option<&str>) -> Result<TransferRule> {
+        Ok(TransferRule {
+            description: description.map(Regex::new).transpose()?,
+            raw_text: raw_text.map(Regex::new).transpose()?,
+            account: credit_card_account()?,
+        })
+    }
+
+    #[test]
+    fn rule_matches() -> Result<()> {
+        let payload = fs::read_to_string("test/data/up_expense.json")?;
+        let up_transaction = serde_json::from_str::<UpTransaction>(&payload)?;
+
+        assert!(rule(Some("^7-Eleven$"), None)?.matches(&up_transaction));
+        assert!(!rule(Some("Amazon"), None)?.matches(&up_transaction));
+        assert!(rule(Some("7-Eleven"), Some("ELEVEN"))?.matches(&up_transaction));
+        assert!(!rule(Some("7-Eleven"), Some("AMAZON"))?.matches(&up_transaction));
+        assert!(!rule(None, None)?.matches(&up_transaction));
+        Ok(())
+    }
+
+    #[test]
+    fn rule_to_unmanaged() -> Result<()> {
+        let payload = fs::read_to_string("test/data/up_expense.json")?;
+        let up_transaction = serde_json::from_str::<UpTransaction>(&payload)?;
+        let rules = vec![rule(Some("Eleven"), None)?];
+        let transaction = up_transaction
+            .to_transaction(&[spending_account()?])?
+            .with_rules(&up_transaction, &rules);
+
+        assert_eq!(
+            Kind::Unmanaged {
+                to: spending_account()?,
+                from: credit_card_account()?,
+            },
+            transaction.kind
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+use nutype::nutype;
+
+pub type UpTagInner = up_client::models::TagResource;
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct UpTag(UpTagInner);
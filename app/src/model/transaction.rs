@@ -8,10 +8,14 @@ use pretty_assertions::Comparison;
 use tracing::debug;
 use ynab_client::models::TransactionClearedStatus;
 
-use crate::{model::Account, YnabBudget};
+use crate::{
+    model::{Account, UnmanagedAccount},
+    YnabBudget,
+};
 
 pub type UpTransactionInner = up_client::models::TransactionResource;
 pub type YnabTransactionInner = ynab_client::models::TransactionDetail;
+pub type YnabHybridTransactionInner = ynab_client::models::HybridTransaction;
 pub type NewYnabTransactionInner = ynab_client::models::SaveTransaction;
 pub type UpdateYnabTransactionInner = ynab_client::models::SaveTransactionWithId;
 
@@ -21,6 +25,10 @@ pub struct UpTransaction(UpTransactionInner);
 #[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
 pub struct YnabTransaction(YnabTransactionInner);
 
+/// A transaction or subtransaction, as returned when listing a category's or payee's transactions.
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct YnabHybridTransaction(YnabHybridTransactionInner);
+
 #[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
 pub struct NewYnabTransaction(NewYnabTransactionInner);
 
@@ -31,6 +39,19 @@ pub struct UpdateYnabTransaction(UpdateYnabTransactionInner);
 pub enum Kind {
     External { to: Account, from_name: String },
     Internal { to: Account, from: Account },
+    Unmanaged { to: Account, from: UnmanagedAccount },
+}
+
+impl Kind {
+    /// Name used in CSV and structured output.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::External { .. } => "external",
+            Self::Internal { .. } => "internal",
+            Self::Unmanaged { .. } => "unmanaged",
+        }
+    }
 }
 
 // TODO: add category support
@@ -63,14 +84,18 @@ impl Transaction {
     #[must_use]
     pub fn to(&self) -> &Account {
         match &self.kind {
-            Kind::Internal { to, from: _ } | Kind::External { to, from_name: _ } => to,
+            Kind::Internal { to, from: _ }
+            | Kind::External { to, from_name: _ }
+            | Kind::Unmanaged { to, from: _ } => to,
         }
     }
 
     #[must_use]
     pub fn to_name(&self) -> &str {
         match &self.kind {
-            Kind::Internal { to, from: _ } | Kind::External { to, from_name: _ } => &to.name,
+            Kind::Internal { to, from: _ }
+            | Kind::External { to, from_name: _ }
+            | Kind::Unmanaged { to, from: _ } => &to.name,
         }
     }
 
@@ -80,7 +105,8 @@ impl Transaction {
             Kind::External {
                 to: _,
                 from_name: _,
-            } => None,
+            }
+            | Kind::Unmanaged { to: _, from: _ } => None,
             Kind::Internal { to: _, from } => Some(from),
         }
     }
@@ -90,6 +116,7 @@ impl Transaction {
         match &self.kind {
             Kind::External { to: _, from_name } => from_name,
             Kind::Internal { to: _, from } => &from.name,
+            Kind::Unmanaged { to: _, from } => &from.name,
         }
     }
 
@@ -99,7 +126,8 @@ impl Transaction {
             Kind::External {
                 to: _,
                 from_name: _,
-            } => false,
+            }
+            | Kind::Unmanaged { to: _, from: _ } => false,
             Kind::Internal { to: _, from: _ } => true,
         }
     }
@@ -111,13 +139,27 @@ impl Transaction {
                 to: _,
                 from_name: _,
             } => true,
-            Kind::Internal { to: _, from: _ } => false,
+            Kind::Internal { to: _, from: _ } | Kind::Unmanaged { to: _, from: _ } => false,
+        }
+    }
+
+    #[must_use]
+    pub fn is_unmanaged(&self) -> bool {
+        match &self.kind {
+            Kind::External {
+                to: _,
+                from_name: _,
+            }
+            | Kind::Internal { to: _, from: _ } => false,
+            Kind::Unmanaged { to: _, from: _ } => true,
         }
     }
 
     #[must_use]
     pub fn is_normalized(&self) -> bool {
-        self.is_external() || (self.is_internal() && self.amount.amount.is_sign_positive())
+        self.is_external()
+            || self.is_unmanaged()
+            || (self.is_internal() && self.amount.amount.is_sign_positive())
     }
 
     pub fn to_new_ynab(&self) -> Result<NewYnabTransaction> {
@@ -131,6 +173,15 @@ impl Transaction {
 
 impl UpTransaction {
     pub fn to_transaction(&self, accounts: &[Account]) -> Result<Transaction> {
+        self.to_transaction_impl(accounts, false)
+    }
+
+    /// Converts the transaction, treating transfers as external transactions.
+    pub fn to_external_transaction(&self, accounts: &[Account]) -> Result<Transaction> {
+        self.to_transaction_impl(accounts, true)
+    }
+
+    fn to_transaction_impl(&self, accounts: &[Account], is_external: bool) -> Result<Transaction> {
         let to_id =
             Some(self.relationships.account.data.id.as_str()).wrap_err("missing `to` account")?;
         let from_id = self
@@ -138,6 +189,7 @@ impl UpTransaction {
             .transfer_account
             .data
             .as_ref()
+            .filter(|_| !is_external)
             .map(|transfer_account| transfer_account.id.as_str());
 
         let to = accounts
@@ -170,7 +222,8 @@ impl UpTransaction {
             Kind::External {
                 to: _,
                 from_name: _,
-            } => self.attributes.message.clone(),
+            }
+            | Kind::Unmanaged { to: _, from: _ } => self.attributes.message.clone(),
             Kind::Internal { to: _, from: _ } => Some(self.attributes.description.clone()),
         };
 
@@ -201,26 +254,37 @@ impl UpTransaction {
 }
 
 impl YnabTransaction {
-    pub fn to_transaction(&self, budget: &YnabBudget, accounts: &[Account]) -> Result<Transaction> {
+    pub fn to_transaction(
+        &self,
+        budget: &YnabBudget,
+        accounts: &[Account],
+        unmanaged_accounts: &[UnmanagedAccount],
+    ) -> Result<Transaction> {
         let to = accounts
             .iter()
             .find(|account| account.ynab_id == self.account_id)
             .map(ToOwned::to_owned)
             .wrap_err("failed to match incoming ynab account")?;
 
-        let from = match &self.transfer_account_id.flatten() {
-            Some(transfer_account) => Some(
-                accounts
+        let kind = match &self.transfer_account_id.flatten() {
+            Some(transfer_account) => {
+                if let Some(from) = accounts
                     .iter()
                     .find(|account| account.ynab_id == *transfer_account)
-                    .map(ToOwned::to_owned)
-                    .wrap_err("failed to match outgoing ynab account")?,
-            ),
-            None => None,
-        };
-
-        let kind = match from {
-            Some(from) => Kind::Internal { to, from },
+                {
+                    Kind::Internal {
+                        to,
+                        from: from.clone(),
+                    }
+                } else {
+                    let from = unmanaged_accounts
+                        .iter()
+                        .find(|account| account.ynab_id == *transfer_account)
+                        .map(ToOwned::to_owned)
+                        .wrap_err("failed to match outgoing ynab account")?;
+                    Kind::Unmanaged { to, from }
+                }
+            }
             None => Kind::External {
                 to,
                 from_name: self
@@ -292,6 +356,10 @@ impl TryFrom<Transaction> for NewYnabTransaction {
                 transaction.account_id = Some(to.ynab_id);
                 transaction.payee_id = Some(Some(from.ynab_transfer_id));
             }
+            Kind::Unmanaged { to, from } => {
+                transaction.account_id = Some(to.ynab_id);
+                transaction.payee_id = Some(Some(from.ynab_transfer_id));
+            }
         }
 
         Ok(NewYnabTransaction::new(transaction))
@@ -330,6 +398,10 @@ impl TryFrom<Transaction> for UpdateYnabTransaction {
                 transaction.account_id = Some(to.ynab_id);
                 transaction.payee_id = Some(Some(from.ynab_transfer_id));
             }
+            Kind::Unmanaged { to, from } => {
+                transaction.account_id = Some(to.ynab_id);
+                transaction.payee_id = Some(Some(from.ynab_transfer_id));
+            }
         }
 
         Ok(UpdateYnabTransaction::new(transaction))
@@ -345,32 +417,12 @@ mod test {
     use std::fs;
 
     use pretty_assertions::assert_eq;
-    use uuid::Uuid;
 
     use super::*;
-    use crate::model::{Account, UpTransaction};
-
-    fn spending_account() -> Result<Account> {
-        Ok(Account {
-            name: "Spending".to_owned(),
-            up_id: "2be1c9de-7a89-4e8f-8077-f535150b588d".to_owned(),
-            ynab_id: Uuid::from_str("f6ca888b-327a-45d0-9775-830abdaa3a04")?,
-            ynab_transfer_id: Uuid::from_str("89ddd9ef-2510-4b42-a889-e7a68cae291c")?,
-        })
-    }
-
-    fn home_account() -> Result<Account> {
-        Ok(Account {
-            name: "Home".to_owned(),
-            up_id: "328160b1-d7bc-41ee-9d7b-c7da4f2484b0".to_owned(),
-            ynab_id: Uuid::from_str("2b00a77e-9b3c-4277-9c6c-6944f7696705")?,
-            ynab_transfer_id: Uuid::from_str("f9b0b92f-70f7-4015-b885-4e5807a78a44")?,
-        })
-    }
-
-    fn accounts() -> Result<Vec<Account>> {
-        Ok(Vec::from([home_account()?, spending_account()?]))
-    }
+    use crate::model::{
+        fixture::{accounts, home_account, spending_account},
+        UpTransaction,
+    };
 
     #[test]
     fn up_expense() -> Result<()> {
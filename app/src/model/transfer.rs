@@ -0,0 +1,400 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use chrono::{DateTime, Duration, FixedOffset};
+use color_eyre::eyre::Result;
+use tracing::{info, warn};
+
+use crate::model::{Account, Transaction, TransferRule, UpTransaction};
+
+/// The maximum number of minutes between both legs of a transfer.
+const TRANSFER_TOLERANCE_MINS: i64 = 5;
+
+/// Description Up gives the receiving leg of a Round Up.
+const ROUND_UP_DESCRIPTION: &str = "Round Up";
+
+/// What to do with a transfer leg whose counterpart can't be found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanPolicy {
+    /// Import the orphan as an external transaction.
+    #[default]
+    External,
+    /// Don't import the orphan.
+    Skip,
+    /// Fetch transactions around the orphan to find its counterpart, importing any remaining
+    /// orphans as external transactions.
+    Widen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanReason {
+    /// The counterpart wasn't fetched, likely because it falls outside the fetch window.
+    MissingCounterpart,
+    /// The counterpart belongs to an Up account without a matching YNAB account.
+    UnmappedAccount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orphan {
+    pub transaction: UpTransaction,
+    pub reason: OrphanReason,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transfers {
+    /// Normalized transactions, containing external transactions and the positive leg of each
+    /// paired transfer.
+    pub transactions: Vec<Transaction>,
+    /// Transfer legs without a counterpart.
+    pub orphans: Vec<Orphan>,
+}
+
+impl Transfers {
+    /// Pairs both legs of each internal transfer, using the transfer account, amount and time.
+    pub fn pair(
+        up_transactions: &[UpTransaction],
+        accounts: &[Account],
+        rules: &[TransferRule],
+    ) -> Result<Self> {
+        let is_mapped = |id: &str| accounts.iter().any(|x| x.up_id == id);
+        let mut used = HashSet::<&str>::new();
+        let mut transfers = Self::default();
+
+        // Counterparts are looked up by account and amount, purchases also by their Round Up.
+        let mut candidates = HashMap::<(&str, i32), Vec<&UpTransaction>>::new();
+        for x in up_transactions {
+            let account_id = x.relationships.account.data.id.as_str();
+            let amounts = std::iter::once(&x.attributes.amount)
+                .chain(x.attributes.round_up.as_ref().map(|x| &x.amount))
+                .map(|x| x.value_in_base_units);
+            for amount in amounts {
+                candidates.entry((account_id, amount)).or_default().push(x);
+            }
+        }
+
+        for up_transaction in up_transactions {
+            let Some(transfer_id) = up_transaction.transfer_account_id() else {
+                transfers.transactions.push(
+                    up_transaction
+                        .to_transaction(accounts)?
+                        .with_rules(up_transaction, rules),
+                );
+                continue;
+            };
+
+            // Legs in unmapped accounts are never synced, their counterparts become orphans.
+            if used.contains(up_transaction.id.as_str())
+                || !is_mapped(&up_transaction.relationships.account.data.id)
+            {
+                continue;
+            }
+
+            if !is_mapped(transfer_id) {
+                transfers.orphans.push(Orphan {
+                    transaction: up_transaction.clone(),
+                    reason: OrphanReason::UnmappedAccount,
+                });
+                continue;
+            }
+
+            let amount = up_transaction.attributes.amount.value_in_base_units;
+            let counterpart = amount
+                .checked_neg()
+                .and_then(|x| candidates.get(&(transfer_id, x)))
+                .into_iter()
+                .flatten()
+                .filter(|x| x.id != up_transaction.id && !used.contains(x.id.as_str()))
+                .filter(|x| is_counterpart(up_transaction, x))
+                .min_by_key(|x| time_between(up_transaction, x));
+
+            match counterpart {
+                Some(counterpart) => {
+                    used.insert(up_transaction.id.as_str());
+                    used.insert(counterpart.id.as_str());
+                    // Round Ups are always positive, their counterpart is imported separately.
+                    let positive = if up_transaction.attributes.amount.value_in_base_units > 0 {
+                        up_transaction
+                    } else {
+                        counterpart
+                    };
+                    transfers
+                        .transactions
+                        .push(positive.to_transaction(accounts)?);
+                }
+                // A Round Up is a transfer between accounts even when its purchase wasn't fetched.
+                None if up_transaction.is_round_up() => {
+                    transfers
+                        .transactions
+                        .push(up_transaction.to_transaction(accounts)?);
+                }
+                None => transfers.orphans.push(Orphan {
+                    transaction: up_transaction.clone(),
+                    reason: OrphanReason::MissingCounterpart,
+                }),
+            }
+        }
+
+        // Legs paired after being visited are no longer orphans.
+        transfers
+            .orphans
+            .retain(|x| !used.contains(x.transaction.id.as_str()));
+
+        info!(
+            "paired transfers, found {} orphaned transfers",
+            transfers.orphans.len()
+        );
+        Ok(transfers)
+    }
+
+    /// Applies the orphan policy, returning the resulting transactions.
+    pub fn resolve(
+        self,
+        policy: OrphanPolicy,
+        accounts: &[Account],
+        rules: &[TransferRule],
+    ) -> Result<Vec<Transaction>> {
+        let mut transactions = self.transactions;
+        for orphan in &self.orphans {
+            warn!("{orphan}");
+        }
+
+        match policy {
+            OrphanPolicy::Skip => {
+                if !self.orphans.is_empty() {
+                    warn!("skipping {} orphaned transfers", self.orphans.len());
+                }
+            }
+            OrphanPolicy::External | OrphanPolicy::Widen => {
+                if !self.orphans.is_empty() {
+                    warn!(
+                        "importing {} orphaned transfers as external transactions",
+                        self.orphans.len()
+                    );
+                }
+                for orphan in self.orphans {
+                    transactions.push(
+                        orphan
+                            .transaction
+                            .to_external_transaction(accounts)?
+                            .with_rules(&orphan.transaction, rules),
+                    );
+                }
+            }
+        }
+
+        Ok(transactions)
+    }
+}
+
+impl Orphan {
+    #[must_use]
+    pub fn timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.transaction.attributes.created_at).ok()
+    }
+
+    /// The window in which the counterpart of this orphan must have been created.
+    #[must_use]
+    pub fn window(&self) -> Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let tolerance = Duration::minutes(TRANSFER_TOLERANCE_MINS);
+        self.timestamp().map(|x| (x - tolerance, x + tolerance))
+    }
+
+    /// Checks whether `transaction` is the missing counterpart of this orphan.
+    #[must_use]
+    pub fn is_counterpart(&self, transaction: &UpTransaction) -> bool {
+        self.transaction.id != transaction.id && is_counterpart(&self.transaction, transaction)
+    }
+}
+
+impl fmt::Display for Orphan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            OrphanReason::MissingCounterpart => "counterpart not fetched",
+            OrphanReason::UnmappedAccount => "counterpart account unmapped",
+        };
+
+        write!(
+            f,
+            "orphaned transfer `{}` ({}) of {} {} at {}: {reason}",
+            self.transaction.id,
+            self.transaction.attributes.description,
+            self.transaction.attributes.amount.value,
+            self.transaction.attributes.amount.currency_code,
+            self.transaction.attributes.created_at,
+        )
+    }
+}
+
+impl UpTransaction {
+    /// Checks whether this is the receiving leg of a Round Up, which has no sending leg.
+    #[must_use]
+    pub fn is_round_up(&self) -> bool {
+        self.attributes.amount.value_in_base_units > 0
+            && self.transfer_account_id().is_some()
+            && self.attributes.description == ROUND_UP_DESCRIPTION
+    }
+
+    #[must_use]
+    pub fn transfer_account_id(&self) -> Option<&str> {
+        self.relationships
+            .transfer_account
+            .data
+            .as_ref()
+            .map(|x| x.id.as_str())
+    }
+}
+
+/// Checks whether `b` is the other leg of the transfer `a`. Round Ups only have a single leg in the
+/// receiving account, their counterpart is the purchase that was rounded up.
+fn is_counterpart(a: &UpTransaction, b: &UpTransaction) -> bool {
+    let a_amount = a.attributes.amount.value_in_base_units;
+    let is_within_tolerance =
+        time_between(a, b) <= Duration::minutes(TRANSFER_TOLERANCE_MINS).num_seconds();
+    let is_reverse = Some(a.relationships.account.data.id.as_str()) == b.transfer_account_id()
+        && a.transfer_account_id() == Some(b.relationships.account.data.id.as_str());
+    let is_round_up = a_amount > 0
+        && b.transfer_account_id().is_none()
+        && a.transfer_account_id() == Some(b.relationships.account.data.id.as_str())
+        && b.attributes
+            .round_up
+            .as_ref()
+            .is_some_and(|x| x.amount.value_in_base_units == -a_amount);
+
+    is_within_tolerance
+        && ((is_reverse && b.attributes.amount.value_in_base_units == -a_amount) || is_round_up)
+}
+
+fn time_between(a: &UpTransaction, b: &UpTransaction) -> i64 {
+    match (
+        DateTime::parse_from_rfc3339(&a.attributes.created_at),
+        DateTime::parse_from_rfc3339(&b.attributes.created_at),
+    ) {
+        (Ok(a), Ok(b)) => (a - b).num_seconds().abs(),
+        _ => i64::MAX,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use money2::{Currency, Money};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::model::{
+        fixture::{accounts, spending_account},
+        transaction::Kind,
+    };
+
+    fn up_transactions_from_file(path: &str) -> Result<Vec<UpTransaction>> {
+        let payload = fs::read_to_string(path)?;
+        Ok(serde_json::from_str::<Vec<UpTransaction>>(&payload)?)
+    }
+
+    #[test]
+    fn pair_transfer() -> Result<()> {
+        let up_transactions = up_transactions_from_file("test/data/up_transfer_balance.json")?;
+        let transfers = Transfers::pair(&up_transactions, &accounts()?, &[])?;
+
+        assert!(transfers.orphans.is_empty());
+        assert_eq!(
+            vec![
+                "c86d3d61-106d-460b-99af-470912d17a56",
+                "0b519c3e-5958-49ec-bc48-86d792247a2f"
+            ],
+            transfers
+                .transactions
+                .iter()
+                .map(|x| x.id.as_str())
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pair_round_ups() -> Result<()> {
+        let up_transactions = up_transactions_from_file("test/data/up_sussy_round_up.json")?;
+        let accounts = accounts()?;
+        let transfers = Transfers::pair(&up_transactions, &accounts, &[])?;
+
+        assert!(transfers.orphans.is_empty());
+        assert_eq!(4, transfers.transactions.len());
+        Ok(())
+    }
+
+    #[test]
+    fn orphan_missing_counterpart() -> Result<()> {
+        let payload = fs::read_to_string("test/data/up_transfer.json")?;
+        let up_transactions = vec![serde_json::from_str::<UpTransaction>(&payload)?];
+        let accounts = accounts()?;
+        let transfers = Transfers::pair(&up_transactions, &accounts, &[])?;
+
+        assert!(transfers.transactions.is_empty());
+        assert_eq!(
+            vec![OrphanReason::MissingCounterpart],
+            transfers
+                .orphans
+                .iter()
+                .map(|x| x.reason)
+                .collect::<Vec<_>>()
+        );
+
+        let skipped = transfers
+            .clone()
+            .resolve(OrphanPolicy::Skip, &accounts, &[])?;
+        assert!(skipped.is_empty());
+
+        let external = transfers.resolve(OrphanPolicy::External, &accounts, &[])?;
+        let expected = Transaction {
+            id: "f1b6981f-94d2-42b6-9cae-304dae08a480".to_string(),
+            timestamp: DateTime::parse_from_rfc3339("2023-12-07T22:35:56+11:00")?,
+            amount: Money::new(37_94, 2, Currency::Aud),
+            kind: Kind::External {
+                to: spending_account()?,
+                from_name: "Transfer from Home".to_string(),
+            },
+            msg: Some(String::new()),
+        };
+        assert_eq!(vec![expected], external);
+        Ok(())
+    }
+
+    #[test]
+    fn round_up_without_purchase() -> Result<()> {
+        let payload = fs::read_to_string("test/data/up_round_up_transfer.json")?;
+        let up_transactions = vec![serde_json::from_str::<UpTransaction>(&payload)?];
+        let transfers = Transfers::pair(&up_transactions, &accounts()?, &[])?;
+
+        assert!(transfers.orphans.is_empty());
+        assert_eq!(
+            vec![true],
+            transfers
+                .transactions
+                .iter()
+                .map(|x| matches!(x.kind, Kind::Internal { .. }))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn orphan_unmapped_account() -> Result<()> {
+        let up_transactions = up_transactions_from_file("test/data/up_transfer_balance.json")?;
+        let accounts = vec![spending_account()?];
+        let transfers = Transfers::pair(&up_transactions, &accounts, &[])?;
+
+        assert_eq!(
+            vec![OrphanReason::UnmappedAccount],
+            transfers
+                .orphans
+                .iter()
+                .map(|x| x.reason)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
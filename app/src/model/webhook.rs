@@ -0,0 +1,14 @@
+use nutype::nutype;
+
+pub type UpWebhookInner = up_client::models::WebhookResource;
+pub type UpWebhookLogInner = up_client::models::WebhookDeliveryLogResource;
+pub type UpWebhookEventInner = up_client::models::WebhookEventResource;
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct UpWebhook(UpWebhookInner);
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct UpWebhookLog(UpWebhookLogInner);
+
+#[nutype(derive(Debug, Clone, Deref, PartialEq, Serialize, Deserialize))]
+pub struct UpWebhookEvent(UpWebhookEventInner);
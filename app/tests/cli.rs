@@ -0,0 +1,126 @@
+//! Runs the binary, checking stdout only holds the command's output.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("up_ynab-{}-{name}", std::process::id()))
+}
+
+fn config(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = temp_path(name).with_extension("toml");
+    fs::write(
+        &path,
+        "
+        [up]
+        api_token = \"up-token\"
+
+        [ynab]
+        api_token = \"ynab-token\"
+        budget_id = \"4a0e2b2a-6d3c-4b8e-9f1a-2c3d4e5f6a7b\"
+        ",
+    )?;
+    Ok(path)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the test run, reading it writes a manifest into it.
+fn run_dir(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = temp_path(name);
+    let _ = fs::remove_dir_all(&path);
+    copy_dir(Path::new("test/data/run"), &path)?;
+    Ok(path)
+}
+
+/// Runs the binary with the given arguments, returning its stdout.
+fn run(name: &str, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let config = config(name)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_up_ynab"))
+        .arg("--config")
+        .arg(&config)
+        .args(args)
+        .env("RUST_LOG", "up_ynab=trace")
+        .output()?;
+    fs::remove_file(&config)?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Logs go to stderr instead.
+    assert!(!output.stderr.is_empty());
+    Ok(output.stdout)
+}
+
+#[test]
+fn query_writes_rows_to_stdout() -> Result<(), Box<dyn Error>> {
+    let run_dir = run_dir("query_writes_rows_to_stdout")?;
+    let in_path = run_dir.to_string_lossy();
+    let stdout = run(
+        "query_writes_rows_to_stdout",
+        &["query", "--in-path", &in_path, "--format", "json"],
+    )?;
+    let rows = serde_json::from_slice::<Vec<serde_json::Value>>(&stdout)?;
+    assert_eq!(2, rows.len());
+
+    fs::remove_dir_all(&run_dir)?;
+    Ok(())
+}
+
+#[test]
+fn get_writes_rows_to_stdout() -> Result<(), Box<dyn Error>> {
+    let run_dir = run_dir("get_writes_rows_to_stdout")?;
+    let in_path = run_dir.to_string_lossy();
+    let stdout = run(
+        "get_writes_rows_to_stdout",
+        &[
+            "get",
+            "balance",
+            "up",
+            "--in-path",
+            &in_path,
+            "--format",
+            "jsonl",
+        ],
+    )?;
+    let rows = String::from_utf8(stdout)?
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(2, rows.len());
+
+    // The run has no YNAB transactions.
+    let stdout = run(
+        "get_writes_rows_to_stdout",
+        &[
+            "get",
+            "balance",
+            "ynab",
+            "--in-path",
+            &in_path,
+            "--format",
+            "json",
+        ],
+    )?;
+    assert!(serde_json::from_slice::<Vec<serde_json::Value>>(&stdout)?.is_empty());
+
+    fs::remove_dir_all(&run_dir)?;
+    Ok(())
+}
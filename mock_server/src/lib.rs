@@ -0,0 +1,409 @@
+//! An in-memory mock of the Up and YNAB APIs, so syncs can be tested without network access.
+//!
+//! Both APIs are served from one port, under the `/up` and `/ynab` prefixes. State is kept as
+//! JSON in the shapes the real APIs return, and can be seeded from a run directory.
+
+#![warn(
+    clippy::suspicious,
+    clippy::complexity,
+    clippy::perf,
+    clippy::style,
+    clippy::pedantic,
+    clippy::unwrap_used
+)]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+mod up;
+mod ynab;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    fs::{self, File},
+    io::BufReader,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use color_eyre::eyre::{Context, Result};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+pub use hyper::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+/// The API a request is made to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Up,
+    Ynab,
+}
+
+/// An error response returned instead of the next matching request's response.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    status: StatusCode,
+    retry_after: Option<u64>,
+    method: Option<Method>,
+}
+
+/// A request received by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRequest {
+    pub service: Service,
+    pub method: Method,
+    /// The path without the service prefix, e.g. `/budgets/{id}/transactions`.
+    pub path: String,
+}
+
+/// A mock Up and YNAB server, shut down when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    base_url: String,
+    max_page_size: Option<usize>,
+    up_accounts: Vec<Value>,
+    up_transactions: Vec<Value>,
+    ynab_budgets: Vec<Value>,
+    ynab_accounts: Vec<Value>,
+    ynab_transactions: Vec<Value>,
+    /// The server knowledge each YNAB transaction was last modified at.
+    knowledge: HashMap<String, i64>,
+    server_knowledge: i64,
+    failures: VecDeque<(Service, Failure)>,
+    requests: Vec<ReceivedRequest>,
+}
+
+type Query = Vec<(String, String)>;
+
+impl Failure {
+    #[must_use]
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            retry_after: None,
+            method: None,
+        }
+    }
+
+    /// Sets the `Retry-After` header, in seconds.
+    #[must_use]
+    pub fn retry_after(mut self, secs: u64) -> Self {
+        self.retry_after = Some(secs);
+        self
+    }
+
+    /// Only fails requests with the given method.
+    #[must_use]
+    pub fn on(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+}
+
+impl MockServer {
+    /// Starts the server on a random local port, within the current Tokio runtime.
+    pub fn start() -> Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let make_service = {
+            let state = state.clone();
+            make_service_fn(move |_| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+            })
+        };
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .wrap_err("failed to bind mock server")?
+            .serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, rx) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            rx.await.ok();
+        }));
+
+        lock(&state).base_url = format!("http://{addr}");
+        Ok(Self {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    #[must_use]
+    pub fn up_base_url(&self) -> String {
+        format!("http://{}/up", self.addr)
+    }
+
+    #[must_use]
+    pub fn ynab_base_url(&self) -> String {
+        format!("http://{}/ynab", self.addr)
+    }
+
+    /// Seeds the server with the entries of a run directory, missing components are skipped.
+    pub fn seed_run<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let up_accounts = read_entries(path.join("up_accounts"))?;
+        let up_transactions = read_entries(path.join("up_transactions"))?;
+        let ynab_budgets = read_entries(path.join("ynab_budgets"))?;
+        let ynab_accounts = read_entries(path.join("ynab_accounts"))?;
+        let ynab_transactions = read_entries(path.join("ynab_transactions"))?;
+
+        let mut state = self.lock();
+        state.up_accounts.extend(up_accounts);
+        for transaction in up_transactions {
+            state.add_up_transaction(transaction);
+        }
+        state.ynab_budgets.extend(ynab_budgets);
+        state.ynab_accounts.extend(ynab_accounts);
+        for transaction in ynab_transactions {
+            state.add_ynab_transaction(transaction);
+        }
+        Ok(())
+    }
+
+    pub fn add_up_account<T: Serialize>(&self, account: &T) -> Result<()> {
+        let account = serde_json::to_value(account)?;
+        self.lock().up_accounts.push(account);
+        Ok(())
+    }
+
+    pub fn add_up_transaction<T: Serialize>(&self, transaction: &T) -> Result<()> {
+        let transaction = serde_json::to_value(transaction)?;
+        self.lock().add_up_transaction(transaction);
+        Ok(())
+    }
+
+    pub fn add_ynab_budget<T: Serialize>(&self, budget: &T) -> Result<()> {
+        let budget = serde_json::to_value(budget)?;
+        self.lock().ynab_budgets.push(budget);
+        Ok(())
+    }
+
+    pub fn add_ynab_account<T: Serialize>(&self, account: &T) -> Result<()> {
+        let account = serde_json::to_value(account)?;
+        self.lock().ynab_accounts.push(account);
+        Ok(())
+    }
+
+    pub fn add_ynab_transaction<T: Serialize>(&self, transaction: &T) -> Result<()> {
+        let transaction = serde_json::to_value(transaction)?;
+        self.lock().add_ynab_transaction(transaction);
+        Ok(())
+    }
+
+    /// Caps the page size of Up list endpoints, to exercise pagination with few entries.
+    pub fn set_max_page_size(&self, size: usize) {
+        self.lock().max_page_size = Some(size.max(1));
+    }
+
+    /// Fails the next request to the service matching the failure, failures are used in order.
+    pub fn fail_next(&self, service: Service, failure: Failure) {
+        self.lock().failures.push_back((service, failure));
+    }
+
+    #[must_use]
+    pub fn ynab_accounts(&self) -> Vec<Value> {
+        self.lock().ynab_accounts.clone()
+    }
+
+    #[must_use]
+    pub fn ynab_transactions(&self) -> Vec<Value> {
+        self.lock().ynab_transactions.clone()
+    }
+
+    #[must_use]
+    pub fn server_knowledge(&self) -> i64 {
+        self.lock().server_knowledge
+    }
+
+    /// Returns every request received, in order.
+    #[must_use]
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+impl State {
+    /// Adds an Up transaction, keeping transactions newest first like the Up API.
+    fn add_up_transaction(&mut self, transaction: Value) {
+        self.up_transactions.push(transaction);
+        self.up_transactions
+            .sort_by_key(|x| std::cmp::Reverse(up::created_at(x)));
+    }
+
+    fn add_ynab_transaction(&mut self, transaction: Value) {
+        self.server_knowledge += 1;
+        if let Some(id) = transaction["id"].as_str() {
+            self.knowledge.insert(id.to_owned(), self.server_knowledge);
+        }
+        self.ynab_transactions.push(transaction);
+    }
+
+    fn take_failure(&mut self, service: Service, method: &Method) -> Option<Failure> {
+        let i = self.failures.iter().position(|(x, failure)| {
+            *x == service && failure.method.as_ref().map_or(true, |x| x == method)
+        })?;
+        self.failures.remove(i).map(|(_, failure)| failure)
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let query = url::form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+        .into_owned()
+        .collect::<Query>();
+    let is_authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|x| x.to_str().ok())
+        .is_some_and(|x| x.starts_with("Bearer "));
+
+    let (service, path) = if let Some(path) = path.strip_prefix("/up") {
+        (Service::Up, path.to_owned())
+    } else if let Some(path) = path.strip_prefix("/ynab") {
+        (Service::Ynab, path.to_owned())
+    } else {
+        return Ok(error(Service::Up, StatusCode::NOT_FOUND, "unknown service"));
+    };
+
+    let body = match hyper::body::to_bytes(req.into_body()).await {
+        Ok(body) if body.is_empty() => Value::Null,
+        Ok(body) => match serde_json::from_slice::<Value>(&body) {
+            Ok(body) => body,
+            Err(e) => return Ok(error(service, StatusCode::BAD_REQUEST, &e.to_string())),
+        },
+        Err(e) => return Ok(error(service, StatusCode::BAD_REQUEST, &e.to_string())),
+    };
+
+    let mut state = lock(&state);
+    state.requests.push(ReceivedRequest {
+        service,
+        method: method.clone(),
+        path: path.clone(),
+    });
+
+    if !is_authorized {
+        return Ok(error(
+            service,
+            StatusCode::UNAUTHORIZED,
+            "missing bearer token",
+        ));
+    }
+    if let Some(failure) = state.take_failure(service, &method) {
+        let mut response = error(service, failure.status, "injected failure");
+        if let Some(retry_after) = failure.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, retry_after.into());
+        }
+        return Ok(response);
+    }
+
+    let response = match service {
+        Service::Up => up::handle(&state, &method, &path, &query),
+        Service::Ynab => ynab::handle(&mut state, &method, &path, &query, &body),
+    };
+    Ok(response)
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    // A panicking handler doesn't leave the state inconsistent, every mutation is a single push.
+    state
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn respond(status: StatusCode, body: &Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+/// Returns an error in the format of the service's error bodies.
+fn error(service: Service, status: StatusCode, detail: &str) -> Response<Body> {
+    let reason = status.canonical_reason().unwrap_or("Error");
+    let body = match service {
+        Service::Up => json!({
+            "errors": [{
+                "status": status.as_str(),
+                "title": reason,
+                "detail": detail,
+            }]
+        }),
+        Service::Ynab => json!({
+            "error": {
+                "id": status.as_str(),
+                "name": reason.to_lowercase().replace(' ', "_"),
+                "detail": detail,
+            }
+        }),
+    };
+    respond(status, &body)
+}
+
+fn query<'a>(query: &'a Query, key: &str) -> Option<&'a str> {
+    query
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn read_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Value>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let path_str = path.to_string_lossy();
+    let mut paths = fs::read_dir(path)
+        .wrap_err(format!("failed to read `{path_str}`"))?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let path_str = path.to_string_lossy();
+            File::open(&path)
+                .map(BufReader::new)
+                .wrap_err(format!("failed to open `{path_str}`"))
+                .and_then(|x| {
+                    serde_json::from_reader::<_, Value>(x)
+                        .wrap_err(format!("failed to parse `{path_str}`"))
+                })
+        })
+        .collect()
+}
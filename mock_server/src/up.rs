@@ -0,0 +1,120 @@
+use chrono::{DateTime, FixedOffset};
+use hyper::{Body, Method, Response, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{error, query, respond, Query, Service, State};
+
+/// The page size used by the Up API when none is requested.
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+pub(crate) fn handle(state: &State, method: &Method, path: &str, query: &Query) -> Response<Body> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["accounts"]) => accounts(state, path, query),
+        (&Method::GET, ["transactions"]) => transactions(state, path, query, None),
+        (&Method::GET, ["accounts", account_id, "transactions"]) => {
+            if state.up_accounts.iter().all(|x| x["id"] != *account_id) {
+                return error(Service::Up, StatusCode::NOT_FOUND, "account not found");
+            }
+            transactions(state, path, query, Some(account_id))
+        }
+        // Categories and tags aren't seeded, every transaction is uncategorised and untagged.
+        (&Method::GET, ["categories"]) => respond(StatusCode::OK, &json!({ "data": [] })),
+        (&Method::GET, ["tags"]) => page(state, path, query, &[]),
+        (&Method::GET, ["util", "ping"]) => respond(
+            StatusCode::OK,
+            &json!({ "meta": { "id": "mock", "statusEmoji": "⚡️" } }),
+        ),
+        _ => error(Service::Up, StatusCode::NOT_FOUND, "unknown endpoint"),
+    }
+}
+
+pub(crate) fn created_at(transaction: &Value) -> Option<DateTime<FixedOffset>> {
+    transaction["attributes"]["createdAt"]
+        .as_str()
+        .and_then(|x| DateTime::parse_from_rfc3339(x).ok())
+}
+
+fn accounts(state: &State, path: &str, query: &Query) -> Response<Body> {
+    let account_type = self::query(query, "filter[accountType]");
+    let ownership_type = self::query(query, "filter[ownershipType]");
+    let accounts = state
+        .up_accounts
+        .iter()
+        .filter(|x| account_type.map_or(true, |kind| x["attributes"]["accountType"] == kind))
+        .filter(|x| ownership_type.map_or(true, |kind| x["attributes"]["ownershipType"] == kind))
+        .collect::<Vec<_>>();
+    page(state, path, query, &accounts)
+}
+
+fn transactions(
+    state: &State,
+    path: &str,
+    query: &Query,
+    account_id: Option<&str>,
+) -> Response<Body> {
+    let mut filters = [None, None];
+    for (i, key) in ["filter[since]", "filter[until]"].into_iter().enumerate() {
+        if let Some(value) = self::query(query, key) {
+            match DateTime::parse_from_rfc3339(value) {
+                Ok(x) => filters[i] = Some(x),
+                Err(e) => {
+                    return error(
+                        Service::Up,
+                        StatusCode::BAD_REQUEST,
+                        &format!("invalid `{key}`: {e}"),
+                    )
+                }
+            }
+        }
+    }
+    let [since, until] = filters;
+    let status = self::query(query, "filter[status]");
+
+    let transactions = state
+        .up_transactions
+        .iter()
+        .filter(|x| account_id.map_or(true, |id| x["relationships"]["account"]["data"]["id"] == id))
+        .filter(|x| status.map_or(true, |status| x["attributes"]["status"] == status))
+        .filter(|x| {
+            let created_at = created_at(x);
+            since.map_or(true, |since| created_at >= Some(since))
+                && until.map_or(true, |until| created_at < Some(until))
+        })
+        .collect::<Vec<_>>();
+    page(state, path, query, &transactions)
+}
+
+/// Returns a page of entries, linking to the next page with the same query.
+fn page(state: &State, path: &str, query: &Query, entries: &[&Value]) -> Response<Body> {
+    let size = self::query(query, "page[size]")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .min(state.max_page_size.unwrap_or(usize::MAX))
+        .max(1);
+    let after = self::query(query, "page[after]")
+        .and_then(|x| x.parse::<usize>().ok())
+        .unwrap_or_default();
+
+    let data = entries
+        .iter()
+        .skip(after)
+        .take(size)
+        .copied()
+        .collect::<Vec<_>>();
+    let next = (after + size < entries.len()).then(|| {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(query.iter().filter(|(k, _)| k != "page[after]"))
+            .append_pair("page[after]", &(after + size).to_string())
+            .finish();
+        format!("{}/up{path}?{query}", state.base_url)
+    });
+
+    respond(
+        StatusCode::OK,
+        &json!({
+            "data": data,
+            "links": { "prev": null, "next": next },
+        }),
+    )
+}
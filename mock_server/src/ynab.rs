@@ -0,0 +1,385 @@
+use hyper::{Body, Method, Response, StatusCode};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::{error, query, respond, Query, Service, State};
+
+pub(crate) fn handle(
+    state: &mut State,
+    method: &Method,
+    path: &str,
+    query: &Query,
+    body: &Value,
+) -> Response<Body> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    if let ["budgets", budget_id, ..] = segments.as_slice() {
+        if state.ynab_budgets.iter().all(|x| x["id"] != *budget_id) {
+            return error(Service::Ynab, StatusCode::NOT_FOUND, "budget not found");
+        }
+    }
+
+    // Budgets share the same accounts and transactions, a run only holds one budget's.
+    match (method, segments.as_slice()) {
+        (&Method::GET, ["user"]) => respond(
+            StatusCode::OK,
+            &json!({ "data": { "user": { "id": Uuid::nil().to_string() } } }),
+        ),
+        (&Method::GET, ["budgets"]) => respond(
+            StatusCode::OK,
+            &json!({ "data": { "budgets": state.ynab_budgets, "default_budget": null } }),
+        ),
+        (&Method::GET, ["budgets", _, "accounts"]) => respond(
+            StatusCode::OK,
+            &json!({
+                "data": {
+                    "accounts": state.ynab_accounts,
+                    "server_knowledge": state.server_knowledge,
+                }
+            }),
+        ),
+        (&Method::POST, ["budgets", _, "accounts"]) => new_account(state, body),
+        (&Method::GET, ["budgets", _, "categories"]) => respond(
+            StatusCode::OK,
+            &json!({
+                "data": {
+                    "category_groups": [],
+                    "server_knowledge": state.server_knowledge,
+                }
+            }),
+        ),
+        (&Method::GET, ["budgets", _, "transactions"]) => transactions(state, query, None, false),
+        (&Method::POST, ["budgets", _, "transactions"]) => new_transactions(state, body),
+        (&Method::PATCH, ["budgets", _, "transactions"]) => update_transactions(state, body),
+        (&Method::DELETE, ["budgets", _, "transactions", id]) => delete_transaction(state, id),
+        (&Method::GET, ["budgets", _, "accounts", id, "transactions"]) => {
+            transactions(state, query, Some(("account_id", id)), false)
+        }
+        (&Method::GET, ["budgets", _, "categories", id, "transactions"]) => {
+            transactions(state, query, Some(("category_id", id)), true)
+        }
+        (&Method::GET, ["budgets", _, "payees", id, "transactions"]) => {
+            transactions(state, query, Some(("payee_id", id)), true)
+        }
+        _ => error(Service::Ynab, StatusCode::NOT_FOUND, "unknown endpoint"),
+    }
+}
+
+fn new_account(state: &mut State, body: &Value) -> Response<Body> {
+    let account = &body["account"];
+    let (Some(name), Some(kind), Some(balance)) = (
+        account["name"].as_str(),
+        account["type"].as_str(),
+        account["balance"].as_i64(),
+    ) else {
+        return error(
+            Service::Ynab,
+            StatusCode::BAD_REQUEST,
+            "account requires a name, type and balance",
+        );
+    };
+
+    state.server_knowledge += 1;
+    let account = json!({
+        "id": Uuid::new_v4().to_string(),
+        "name": name,
+        "type": kind,
+        "on_budget": true,
+        "closed": false,
+        "note": null,
+        "balance": balance,
+        "cleared_balance": balance,
+        "uncleared_balance": 0,
+        "transfer_payee_id": Uuid::new_v4().to_string(),
+        "direct_import_linked": false,
+        "direct_import_in_error": false,
+        "last_reconciled_at": null,
+        "deleted": false,
+    });
+    state.ynab_accounts.push(account.clone());
+
+    respond(
+        StatusCode::CREATED,
+        &json!({
+            "data": { "account": account, "server_knowledge": state.server_knowledge }
+        }),
+    )
+}
+
+/// Lists transactions, only those whose `field` is `id` when scoped. The category and payee
+/// endpoints return hybrid transactions, which can also be subtransactions.
+fn transactions(
+    state: &State,
+    query: &Query,
+    scope: Option<(&str, &str)>,
+    is_hybrid: bool,
+) -> Response<Body> {
+    let since_date =
+        self::query(query, "since_date").map(|x| x.chars().take(10).collect::<String>());
+    let last_knowledge = match self::query(query, "last_knowledge_of_server").map(str::parse::<i64>)
+    {
+        Some(Ok(x)) => Some(x),
+        Some(Err(e)) => {
+            return error(
+                Service::Ynab,
+                StatusCode::BAD_REQUEST,
+                &format!("invalid `last_knowledge_of_server`: {e}"),
+            )
+        }
+        None => None,
+    };
+
+    // Delta requests include deleted transactions, so clients can remove them.
+    let transactions = state
+        .ynab_transactions
+        .iter()
+        .filter(|x| {
+            since_date
+                .as_deref()
+                .map_or(true, |since| x["date"].as_str() >= Some(since))
+        })
+        .filter(|x| match last_knowledge {
+            Some(last) => state.knowledge_of(x) > last,
+            None => x["deleted"] != true,
+        })
+        .filter(|x| scope.map_or(true, |(field, id)| x[field] == id))
+        .map(|x| {
+            let mut transaction = x.clone();
+            if is_hybrid {
+                transaction["type"] = "transaction".into();
+                transaction["parent_transaction_id"] = Value::Null;
+            }
+            transaction
+        })
+        .collect::<Vec<_>>();
+
+    respond(
+        StatusCode::OK,
+        &json!({
+            "data": {
+                "transactions": transactions,
+                "server_knowledge": state.server_knowledge,
+            }
+        }),
+    )
+}
+
+fn new_transactions(state: &mut State, body: &Value) -> Response<Body> {
+    let (saves, is_single) = match (&body["transaction"], &body["transactions"]) {
+        (Value::Object(_), _) => (vec![&body["transaction"]], true),
+        (_, Value::Array(saves)) => (saves.iter().collect(), false),
+        _ => {
+            return error(
+                Service::Ynab,
+                StatusCode::BAD_REQUEST,
+                "either `transaction` or `transactions` is required",
+            )
+        }
+    };
+    if let Some(detail) = saves.iter().find_map(|x| state.validate(x)) {
+        return error(Service::Ynab, StatusCode::BAD_REQUEST, &detail);
+    }
+
+    // Import ids are unique per account, duplicates are skipped and reported.
+    state.server_knowledge += 1;
+    let mut created = Vec::new();
+    let mut duplicate_import_ids = Vec::new();
+    for save in saves {
+        if let Some(import_id) = save["import_id"].as_str() {
+            let is_duplicate = state
+                .ynab_transactions
+                .iter()
+                .any(|x| x["import_id"] == import_id && x["account_id"] == save["account_id"]);
+            if is_duplicate {
+                duplicate_import_ids.push(import_id.to_owned());
+                continue;
+            }
+        }
+
+        let mut transaction = json!({
+            "id": Uuid::new_v4().to_string(),
+            "date": save["date"],
+            "amount": save["amount"],
+            "memo": save["memo"],
+            "cleared": save.get("cleared").unwrap_or(&json!("uncleared")),
+            "approved": save.get("approved").unwrap_or(&json!(false)),
+            "flag_color": save["flag_color"],
+            "account_id": save["account_id"],
+            "payee_id": save["payee_id"],
+            "payee_name": save["payee_name"],
+            "category_id": save["category_id"],
+            "category_name": null,
+            "transfer_transaction_id": null,
+            "matched_transaction_id": null,
+            "import_id": save["import_id"],
+            "import_payee_name": null,
+            "import_payee_name_original": null,
+            "debt_transaction_type": null,
+            "deleted": false,
+            "subtransactions": [],
+        });
+        state.resolve(&mut transaction);
+        state.set_knowledge(&transaction);
+        state.ynab_transactions.push(transaction.clone());
+        created.push(transaction);
+    }
+
+    let mut data = json!({
+        "transaction_ids": created.iter().map(|x| x["id"].clone()).collect::<Vec<_>>(),
+        "duplicate_import_ids": duplicate_import_ids,
+        "server_knowledge": state.server_knowledge,
+    });
+    if is_single {
+        data["transaction"] = created.into_iter().next().unwrap_or_default();
+    } else {
+        data["transactions"] = created.into();
+    }
+    respond(StatusCode::CREATED, &json!({ "data": data }))
+}
+
+fn update_transactions(state: &mut State, body: &Value) -> Response<Body> {
+    let Some(saves) = body["transactions"].as_array() else {
+        return error(
+            Service::Ynab,
+            StatusCode::BAD_REQUEST,
+            "`transactions` is required",
+        );
+    };
+
+    let mut indices = Vec::new();
+    for save in saves {
+        let i = state.ynab_transactions.iter().position(|x| {
+            x["deleted"] != true
+                && match (save["id"].as_str(), save["import_id"].as_str()) {
+                    (Some(id), _) => x["id"] == id,
+                    (None, Some(import_id)) => x["import_id"] == import_id,
+                    (None, None) => false,
+                }
+        });
+        let Some(i) = i else {
+            return error(
+                Service::Ynab,
+                StatusCode::BAD_REQUEST,
+                "transaction to update not found, an id or import_id is required",
+            );
+        };
+        if let Some(detail) = state.validate(save) {
+            return error(Service::Ynab, StatusCode::BAD_REQUEST, &detail);
+        }
+        indices.push(i);
+    }
+
+    state.server_knowledge += 1;
+    let mut updated = Vec::new();
+    for (save, i) in saves.iter().zip(indices) {
+        let mut transaction = state.ynab_transactions[i].clone();
+        if let (Value::Object(transaction), Some(save)) = (&mut transaction, save.as_object()) {
+            merge(transaction, save);
+        }
+        state.resolve(&mut transaction);
+        state.set_knowledge(&transaction);
+        state.ynab_transactions[i] = transaction.clone();
+        updated.push(transaction);
+    }
+
+    respond(
+        StatusCode::from_u16(209).unwrap_or(StatusCode::OK),
+        &json!({
+            "data": {
+                "transaction_ids": updated.iter().map(|x| x["id"].clone()).collect::<Vec<_>>(),
+                "transactions": updated,
+                "server_knowledge": state.server_knowledge,
+            }
+        }),
+    )
+}
+
+fn delete_transaction(state: &mut State, id: &str) -> Response<Body> {
+    let Some(i) = state
+        .ynab_transactions
+        .iter()
+        .position(|x| x["id"] == id && x["deleted"] != true)
+    else {
+        return error(
+            Service::Ynab,
+            StatusCode::NOT_FOUND,
+            "transaction not found",
+        );
+    };
+
+    state.server_knowledge += 1;
+    state.ynab_transactions[i]["deleted"] = true.into();
+    let transaction = state.ynab_transactions[i].clone();
+    state.set_knowledge(&transaction);
+
+    respond(
+        StatusCode::OK,
+        &json!({
+            "data": { "transaction": transaction, "server_knowledge": state.server_knowledge }
+        }),
+    )
+}
+
+/// Overwrites the fields present in the save, the id is never changed.
+fn merge(transaction: &mut Map<String, Value>, save: &Map<String, Value>) {
+    for (k, v) in save {
+        if k != "id" {
+            transaction.insert(k.clone(), v.clone());
+        }
+    }
+}
+
+impl State {
+    /// Returns the reason a saved transaction would be rejected.
+    fn validate(&self, save: &Value) -> Option<String> {
+        if !save["account_id"].is_null() && self.account(&save["account_id"]).is_none() {
+            return Some(format!("account `{}` not found", save["account_id"]));
+        }
+        if let Some(date) = save["date"].as_str() {
+            if chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                return Some(format!("invalid date `{date}`"));
+            }
+        }
+        None
+    }
+
+    fn account(&self, id: &Value) -> Option<&Value> {
+        self.ynab_accounts.iter().find(|x| x["id"] == *id)
+    }
+
+    /// Fills in the fields YNAB derives from the account and payee.
+    fn resolve(&self, transaction: &mut Value) {
+        let account_name = self
+            .account(&transaction["account_id"])
+            .map(|x| x["name"].clone())
+            .unwrap_or_default();
+        transaction["account_name"] = account_name;
+
+        let transfer_account = self.ynab_accounts.iter().find(|x| {
+            !transaction["payee_id"].is_null() && x["transfer_payee_id"] == transaction["payee_id"]
+        });
+        transaction["transfer_account_id"] = transfer_account
+            .map(|x| x["id"].clone())
+            .unwrap_or_default();
+        if let Some(account) = transfer_account.filter(|_| transaction["payee_name"].is_null()) {
+            transaction["payee_name"] = format!(
+                "Transfer : {}",
+                account["name"].as_str().unwrap_or_default()
+            )
+            .into();
+        }
+    }
+
+    fn knowledge_of(&self, transaction: &Value) -> i64 {
+        transaction["id"]
+            .as_str()
+            .and_then(|x| self.knowledge.get(x))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_knowledge(&mut self, transaction: &Value) {
+        if let Some(id) = transaction["id"].as_str() {
+            self.knowledge.insert(id.to_owned(), self.server_knowledge);
+        }
+    }
+}
@@ -0,0 +1,25 @@
+#!/usr/bin/env bash
+# Regenerates the Up and YNAB API clients with OpenAPI Generator, then applies each client's
+# patches from `<client>/patches`, which the generator's templates don't cover.
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+UP_SPEC=${UP_SPEC:-https://raw.githubusercontent.com/up-banking/api/master/v1/openapi.json}
+YNAB_SPEC=${YNAB_SPEC:-https://api.ynab.com/papi/open_api_spec.yaml}
+
+generate() {
+    local client=$1 spec=$2
+    openapi-generator-cli version-manager set 7.2.0
+    openapi-generator-cli generate \
+        --generator-name rust \
+        --library reqwest \
+        --input-spec "$spec" \
+        --output "$client" \
+        --additional-properties "packageName=$client"
+    for patch in "$client"/patches/*.patch; do
+        git apply --directory="$client" "$patch"
+    done
+}
+
+generate up_client "$UP_SPEC"
+generate ynab_client "$YNAB_SPEC"
@@ -96,6 +96,7 @@ pub async fn accounts_get(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -105,6 +106,7 @@ pub async fn accounts_get(
             serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };
@@ -144,6 +146,7 @@ pub async fn accounts_id_get(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -153,6 +156,7 @@ pub async fn accounts_id_get(
             serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };
@@ -158,6 +158,7 @@ pub async fn accounts_account_id_transactions_get(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -167,6 +168,7 @@ pub async fn accounts_account_id_transactions_get(
             serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };
@@ -235,6 +237,7 @@ pub async fn transactions_get(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -244,6 +247,7 @@ pub async fn transactions_get(
             serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };
@@ -283,6 +287,7 @@ pub async fn transactions_id_get(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -292,6 +297,7 @@ pub async fn transactions_id_get(
             serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };
@@ -25,6 +25,7 @@ pub async fn get_page<T: DeserializeOwned, E: DeserializeOwned>(
     let local_var_resp = local_var_client.execute(local_var_req).await?;
 
     let local_var_status = local_var_resp.status();
+    let local_var_headers = local_var_resp.headers().clone();
     let local_var_content = local_var_resp.text().await?;
 
     if !local_var_status.is_client_error() && !local_var_status.is_server_error() {
@@ -33,6 +34,7 @@ pub async fn get_page<T: DeserializeOwned, E: DeserializeOwned>(
         let local_var_entity: Option<E> = serde_json::from_str(&local_var_content).ok();
         let local_var_error = ResponseContent {
             status: local_var_status,
+            headers: local_var_headers,
             content: local_var_content,
             entity: local_var_entity,
         };